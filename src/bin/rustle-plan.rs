@@ -1,8 +1,17 @@
 use anyhow::{Context, Result};
-use clap::{Parser, ValueEnum};
-use rustle_plan::{ExecutionPlanner, ExecutionStrategy, PlanningOptions};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand, ValueEnum};
+use indexmap::IndexMap;
+use rustle_plan::{
+    execution_plan_schema, BatchSize, BinaryPlanFormat, DiffKind, ExecutionPlan, ExecutionPlanner,
+    ExecutionStrategy, HostOrder, PlanDiff, PlanDiffer, PlanError, PlanIntegrity, PlanMigrator,
+    PlanSigner, PlanValidator, PlanVerifier, PlanVisualizer, PlanningOptions, SecretOpener,
+    SecretSealer, TopologySpread,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::{error, info};
 
 #[derive(Parser)]
@@ -10,9 +19,13 @@ use tracing::{error, info};
     name = "rustle-plan",
     version,
     about = "Generate optimized execution plans from parsed playbooks",
-    long_about = "The rustle-plan tool takes parsed playbooks and generates optimized execution plans with binary deployment strategies. It analyzes task dependencies, determines parallelization opportunities, and produces detailed execution graphs."
+    long_about = "The rustle-plan tool takes parsed playbooks and generates optimized execution plans with binary deployment strategies. It analyzes task dependencies, determines parallelization opportunities, and produces detailed execution graphs.",
+    args_conflicts_with_subcommands = true
 )]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// Path to parsed playbook file (or stdin if -)
     #[arg(value_name = "PARSED_PLAYBOOK")]
     playbook: Option<PathBuf>,
@@ -33,9 +46,43 @@ struct Cli {
     #[arg(short, long, value_enum, default_value = "binary-hybrid")]
     strategy: StrategyArg,
 
-    /// Number of hosts to run at once
-    #[arg(long, value_name = "NUM")]
-    serial: Option<u32>,
+    /// Hosts per rolling batch, as a number, a percentage or a ramp (e.g. 1,5,25%)
+    #[arg(long, value_name = "SIZES", value_delimiter = ',')]
+    serial: Vec<BatchSize>,
+
+    /// Spread rolling and canary rollout batches across the failure domains
+    /// named by this host variable
+    #[arg(long, value_name = "VAR")]
+    spread_by: Option<String>,
+
+    /// Most hosts of one failure domain per rolling batch, as a number or a percentage
+    #[arg(long, value_name = "SIZE", requires = "spread_by")]
+    max_per_domain: Option<BatchSize>,
+
+    /// Order plays work through their hosts: inventory, reverse_inventory,
+    /// sorted, reverse_sorted, shuffle, shuffle:SEED or host_priority
+    #[arg(long, value_name = "ORDER", default_value = "inventory")]
+    order: HostOrder,
+
+    /// Hosts in the canary batch of the canary strategy, as a number or a percentage
+    #[arg(long, value_name = "SIZE", default_value = "1")]
+    canary_size: BatchSize,
+
+    /// Inventory group that stays live until the blue-green switch
+    #[arg(long, value_name = "GROUP", default_value = "blue")]
+    blue_group: String,
+
+    /// Inventory group the blue-green strategy deploys to and switches to
+    #[arg(long, value_name = "GROUP", default_value = "green")]
+    green_group: String,
+
+    /// Abort a play once more than this percentage of a batch's hosts fail
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u32).range(0..=100))]
+    max_fail_percentage: Option<u32>,
+
+    /// Abort a play as soon as any host fails
+    #[arg(long)]
+    any_errors_fatal: bool,
 
     /// Maximum parallel processes
     #[arg(long, default_value = "50")]
@@ -61,6 +108,30 @@ struct Cli {
     #[arg(long)]
     force_ssh: bool,
 
+    /// Directory of the playbook, for finding files embedded in binaries
+    /// [default: the directory of the playbook named in the input's metadata]
+    #[arg(long, value_name = "DIR")]
+    playbook_dir: Option<PathBuf>,
+
+    /// Also redact values whose key matches this regex (case-insensitive);
+    /// passwords, secrets, tokens, API keys, private keys and credentials
+    /// always are
+    #[arg(long = "redact-key", value_name = "PATTERN")]
+    redact_keys: Vec<String>,
+
+    /// Seal redacted secrets into the plan for the executor holding the
+    /// secret key of this public key, as written by `keygen --sealing`
+    #[arg(long, value_name = "PATH")]
+    seal_secrets_to: Option<PathBuf>,
+
+    /// Pin the timestamp and shuffle seeds so the same input gives a byte-identical plan
+    #[arg(long)]
+    reproducible: bool,
+
+    /// Expand loops with static items into one task per item
+    #[arg(long)]
+    expand_loops: bool,
+
     /// List all planned tasks
     #[arg(long)]
     list_tasks: bool,
@@ -73,6 +144,18 @@ struct Cli {
     #[arg(long)]
     list_binaries: bool,
 
+    /// Report the critical path and slack of each play
+    #[arg(long)]
+    critical_path: bool,
+
+    /// Show a per-host timeline of planned task runs
+    #[arg(long)]
+    timeline: bool,
+
+    /// Report how many hosts can fail before the plan aborts
+    #[arg(long)]
+    failure_impact: bool,
+
     /// Generate execution graph visualization
     #[arg(long)]
     visualize: bool,
@@ -81,6 +164,10 @@ struct Cli {
     #[arg(short, long, value_enum, default_value = "json")]
     output: OutputFormat,
 
+    /// Compress binary output with zstd
+    #[arg(long)]
+    compress: bool,
+
     /// Enable execution optimizations
     #[arg(long)]
     optimize: bool,
@@ -98,6 +185,142 @@ struct Cli {
     verbose: bool,
 }
 
+#[derive(Subcommand)]
+enum Commands {
+    /// Generate an Ed25519 keypair for signing plans
+    Keygen {
+        /// Where to write the secret key; the public key is written next to
+        /// it with a .pub extension [default: ~/.rustle/plan-signing.key, or
+        /// ~/.rustle/plan-secrets.key with --sealing]
+        #[arg(long, value_name = "PATH")]
+        key: Option<PathBuf>,
+
+        /// Generate an X25519 keypair for sealing secrets to an executor instead
+        #[arg(long)]
+        sealing: bool,
+
+        /// Overwrite an existing keypair
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Sign an execution plan and print the signed plan
+    Sign {
+        /// Execution plan to sign (or stdin if -)
+        #[arg(value_name = "PLAN")]
+        plan: Option<PathBuf>,
+
+        /// Secret key to sign with [default: ~/.rustle/plan-signing.key]
+        #[arg(long, value_name = "PATH")]
+        key: Option<PathBuf>,
+    },
+
+    /// Check that a plan was signed by a trusted key and has not changed since
+    Verify {
+        /// Signed execution plan (or stdin if -)
+        #[arg(value_name = "PLAN")]
+        plan: Option<PathBuf>,
+
+        /// Trusted public key [default: ~/.rustle/plan-signing.key.pub]
+        #[arg(long, value_name = "PATH")]
+        public_key: Option<PathBuf>,
+    },
+
+    /// Report on a saved execution plan without re-planning
+    Inspect {
+        /// Execution plan to inspect, as JSON or binary (or stdin if -)
+        #[arg(value_name = "PLAN")]
+        plan: Option<PathBuf>,
+
+        /// List all planned tasks
+        #[arg(long)]
+        list_tasks: bool,
+
+        /// List all target hosts
+        #[arg(long)]
+        list_hosts: bool,
+
+        /// List planned binary deployments
+        #[arg(long)]
+        list_binaries: bool,
+
+        /// Report the critical path and slack of each play
+        #[arg(long)]
+        critical_path: bool,
+
+        /// Show a per-host timeline of planned task runs
+        #[arg(long)]
+        timeline: bool,
+
+        /// Report how many hosts can fail before the plan aborts
+        #[arg(long)]
+        failure_impact: bool,
+
+        /// Print the execution graph in Graphviz DOT format
+        #[arg(long)]
+        dot: bool,
+
+        /// Print the execution graph as a Mermaid flowchart
+        #[arg(long)]
+        mermaid: bool,
+
+        /// Print the execution graph as a PlantUML diagram
+        #[arg(long)]
+        plantuml: bool,
+
+        /// Print a self-contained HTML report of the plan
+        #[arg(long)]
+        html: bool,
+    },
+
+    /// Re-validate a saved execution plan and check its digest
+    Validate {
+        /// Execution plan to validate, as JSON or binary (or stdin if -)
+        #[arg(value_name = "PLAN")]
+        plan: Option<PathBuf>,
+
+        /// Fail on warnings as well as errors
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Show how an execution plan changed, task by task
+    Diff {
+        /// The earlier plan, as JSON or binary (or stdin if -)
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// The later plan, as JSON or binary (or stdin if -)
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        output: DiffFormat,
+    },
+
+    /// Print the JSON Schema of execution plans or of rustle-parse output
+    Schema {
+        /// Document to describe
+        #[arg(value_enum, default_value = "plan")]
+        target: SchemaTarget,
+    },
+}
+
+#[derive(ValueEnum, Clone)]
+enum DiffFormat {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone)]
+enum SchemaTarget {
+    /// Execution plans written by rustle-plan
+    Plan,
+    /// rustle-parse output read by rustle-plan
+    Input,
+}
+
 #[derive(ValueEnum, Clone)]
 enum StrategyArg {
     Linear,
@@ -106,17 +329,31 @@ enum StrategyArg {
     HostPinned,
     BinaryHybrid,
     BinaryOnly,
+    Canary,
+    BlueGreen,
 }
 
 impl From<StrategyArg> for ExecutionStrategy {
     fn from(strategy: StrategyArg) -> Self {
         match strategy {
             StrategyArg::Linear => ExecutionStrategy::Linear,
-            StrategyArg::Rolling => ExecutionStrategy::Rolling { batch_size: 5 },
+            StrategyArg::Rolling => ExecutionStrategy::Rolling {
+                batch_sizes: vec![BatchSize::Hosts(5)],
+                topology: None,
+            },
             StrategyArg::Free => ExecutionStrategy::Free,
             StrategyArg::HostPinned => ExecutionStrategy::HostPinned,
             StrategyArg::BinaryHybrid => ExecutionStrategy::BinaryHybrid,
             StrategyArg::BinaryOnly => ExecutionStrategy::BinaryOnly,
+            StrategyArg::Canary => ExecutionStrategy::Canary {
+                canary_size: BatchSize::Hosts(1),
+                batch_sizes: vec![BatchSize::Percent(100)],
+                topology: None,
+            },
+            StrategyArg::BlueGreen => ExecutionStrategy::BlueGreen {
+                blue: "blue".to_string(),
+                green: "green".to_string(),
+            },
         }
     }
 }
@@ -126,17 +363,33 @@ enum OutputFormat {
     Json,
     Binary,
     Dot,
+    Mermaid,
+    Plantuml,
+    Html,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Initialize tracing - suppress logging if outputting JSON to stdout
-    // This prevents log messages from interfering with piped JSON output
-    let should_log = !(matches!(cli.output, OutputFormat::Json)
-        && !cli.list_tasks
+    if let Some(command) = cli.command {
+        return run_command(command);
+    }
+
+    // Initialize tracing - suppress logging if outputting a plan to stdout
+    // This prevents log messages from interfering with piped plan output
+    let should_log = !(matches!(
+        cli.output,
+        OutputFormat::Json
+            | OutputFormat::Binary
+            | OutputFormat::Mermaid
+            | OutputFormat::Plantuml
+            | OutputFormat::Html
+    ) && !cli.list_tasks
         && !cli.list_hosts
         && !cli.list_binaries
+        && !cli.critical_path
+        && !cli.timeline
+        && !cli.failure_impact
         && !cli.dry_run);
 
     if should_log {
@@ -149,29 +402,45 @@ fn main() -> Result<()> {
     }
 
     // Read playbook input
-    let playbook_content = if let Some(ref path) = cli.playbook {
-        if path.as_os_str() == "-" {
-            let mut content = String::new();
-            io::stdin()
-                .read_to_string(&mut content)
-                .context("Failed to read playbook from stdin")?;
-            content
-        } else {
-            std::fs::read_to_string(path)
-                .with_context(|| format!("Failed to read playbook file: {}", path.display()))?
-        }
-    } else {
-        let mut content = String::new();
-        io::stdin()
-            .read_to_string(&mut content)
-            .context("Failed to read playbook from stdin")?;
-        content
-    };
+    let playbook_content = read_input(cli.playbook.as_deref(), "playbook")?;
 
     // Parse the combined output from rustle-parse (includes both playbook and inventory)
-    let (parsed_playbook, parsed_inventory) = parse_rustle_output(&playbook_content)?;
+    let (parsed_playbook, parsed_inventory, playbook_path) =
+        parse_rustle_output(&playbook_content)?;
+    let playbook_dir = cli.playbook_dir.clone().unwrap_or_else(|| {
+        playbook_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+    });
 
     // Create planning options
+    let topology = cli.spread_by.clone().map(|domain_var| TopologySpread {
+        domain_var,
+        max_per_domain: cli.max_per_domain.unwrap_or(BatchSize::Percent(100)),
+    });
+    let strategy = match cli.strategy.clone().into() {
+        ExecutionStrategy::Rolling { batch_sizes, .. } => ExecutionStrategy::Rolling {
+            batch_sizes,
+            topology,
+        },
+        ExecutionStrategy::Canary { batch_sizes, .. } => ExecutionStrategy::Canary {
+            canary_size: cli.canary_size,
+            batch_sizes,
+            topology,
+        },
+        _ if topology.is_some() => Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--spread-by can only be used with --strategy rolling or canary",
+            )
+            .exit(),
+        ExecutionStrategy::BlueGreen { .. } => ExecutionStrategy::BlueGreen {
+            blue: cli.blue_group.clone(),
+            green: cli.green_group.clone(),
+        },
+        strategy => strategy,
+    };
     let planning_options = PlanningOptions {
         limit: cli.limit,
         tags: cli.tags,
@@ -179,20 +448,32 @@ fn main() -> Result<()> {
         check_mode: cli.check,
         diff_mode: cli.diff,
         forks: cli.forks,
-        serial: cli.serial,
-        strategy: cli.strategy.into(),
+        serial: (!cli.serial.is_empty()).then_some(cli.serial),
+        max_fail_percentage: cli.max_fail_percentage,
+        any_errors_fatal: cli.any_errors_fatal,
+        host_order: cli.order,
+        redact_keys: cli.redact_keys,
+        strategy,
         binary_threshold: cli.binary_threshold,
         force_binary: cli.force_binary,
         force_ssh: cli.force_ssh,
+        reproducible: cli.reproducible,
     };
 
     // Create execution planner
-    let planner = ExecutionPlanner::new()
+    let mut planner = ExecutionPlanner::new()
         .with_strategy(planning_options.strategy.clone())
         .with_forks(cli.forks)
         .with_optimization(cli.optimize)
         .with_check_mode(cli.check)
-        .with_binary_threshold(cli.binary_threshold);
+        .with_binary_threshold(cli.binary_threshold)
+        .with_loop_expansion(cli.expand_loops)
+        .with_playbook_dir(playbook_dir);
+    if let Some(path) = &cli.seal_secrets_to {
+        let public_key = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sealing key: {}", path.display()))?;
+        planner = planner.with_secret_sealer(SecretSealer::from_hex(&public_key)?);
+    }
 
     info!("Planning execution for playbook");
 
@@ -217,6 +498,21 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if cli.critical_path {
+        report_critical_path(&execution_plan);
+        return Ok(());
+    }
+
+    if cli.timeline {
+        print_timeline(&execution_plan);
+        return Ok(());
+    }
+
+    if cli.failure_impact {
+        report_failure_impact(&execution_plan);
+        return Ok(());
+    }
+
     if cli.dry_run {
         info!("Dry run completed successfully");
         if cli.estimate_time {
@@ -238,8 +534,12 @@ fn main() -> Result<()> {
             println!("{json}");
         }
         OutputFormat::Binary => {
-            // For binary output, we could use a more compact serialization format
-            let binary = serde_json::to_vec(&execution_plan)
+            let mut format = BinaryPlanFormat::new();
+            if cli.compress {
+                format = format.with_compression(0);
+            }
+            let binary = format
+                .encode(&execution_plan)
                 .context("Failed to serialize execution plan to binary")?;
             io::stdout()
                 .write_all(&binary)
@@ -253,30 +553,293 @@ fn main() -> Result<()> {
                 std::process::exit(1);
             }
         }
+        OutputFormat::Mermaid => print!("{}", PlanVisualizer::new().to_mermaid(&execution_plan)),
+        OutputFormat::Plantuml => print!("{}", PlanVisualizer::new().to_plantuml(&execution_plan)),
+        OutputFormat::Html => print!("{}", PlanVisualizer::new().to_html(&execution_plan)),
+    }
+
+    Ok(())
+}
+
+/// Reads a file, or stdin when the path is missing or `-`
+fn read_input(path: Option<&Path>, what: &str) -> Result<String> {
+    String::from_utf8(read_input_bytes(path, what)?)
+        .with_context(|| format!("The {what} is not valid UTF-8"))
+}
+
+fn read_input_bytes(path: Option<&Path>, what: &str) -> Result<Vec<u8>> {
+    match path {
+        Some(path) if path.as_os_str() != "-" => std::fs::read(path)
+            .with_context(|| format!("Failed to read {what} file: {}", path.display())),
+        _ => {
+            let mut content = Vec::new();
+            io::stdin()
+                .read_to_end(&mut content)
+                .with_context(|| format!("Failed to read {what} from stdin"))?;
+            Ok(content)
+        }
+    }
+}
+
+fn run_command(command: Commands) -> Result<()> {
+    match command {
+        Commands::Keygen {
+            key,
+            sealing,
+            force,
+        } => {
+            let key_path = match key {
+                Some(path) => path,
+                None if sealing => default_key_path("plan-secrets.key")?,
+                None => signing_key_path(None)?,
+            };
+            let public_key_path = public_key_path(&key_path);
+            if !force && (key_path.exists() || public_key_path.exists()) {
+                anyhow::bail!(
+                    "Signing key {} already exists; pass --force to replace it",
+                    key_path.display()
+                );
+            }
+
+            let (secret_key, public_key) = if sealing {
+                let opener = SecretOpener::generate();
+                (opener.secret_key_hex(), opener.public_key_hex())
+            } else {
+                let signer = PlanSigner::generate();
+                (signer.secret_key_hex(), signer.public_key_hex())
+            };
+            write_secret_key(&key_path, &secret_key)?;
+            std::fs::write(&public_key_path, format!("{public_key}\n"))
+                .with_context(|| format!("Failed to write {}", public_key_path.display()))?;
+
+            println!("Secret key: {}", key_path.display());
+            println!("Public key: {}", public_key_path.display());
+        }
+        Commands::Sign { plan, key } => {
+            let key_path = signing_key_path(key)?;
+            let secret_key = std::fs::read_to_string(&key_path)
+                .with_context(|| format!("Failed to read signing key: {}", key_path.display()))?;
+            let signer = PlanSigner::from_hex(&secret_key)?;
+
+            let mut execution_plan = read_plan(plan.as_deref())?;
+            signer
+                .sign(&mut execution_plan)
+                .context("Refusing to sign execution plan")?;
+
+            let json = serde_json::to_string_pretty(&execution_plan)
+                .context("Failed to serialize execution plan to JSON")?;
+            println!("{json}");
+        }
+        Commands::Verify { plan, public_key } => {
+            let public_key_path = match public_key {
+                Some(path) => path,
+                None => public_key_path(&signing_key_path(None)?),
+            };
+            let public_key = std::fs::read_to_string(&public_key_path).with_context(|| {
+                format!("Failed to read public key: {}", public_key_path.display())
+            })?;
+            let verifier = PlanVerifier::from_hex(&public_key)?;
+
+            let execution_plan = read_plan(plan.as_deref())?;
+            verifier
+                .verify(&execution_plan)
+                .context("Execution plan failed verification")?;
+
+            println!(
+                "Plan verified: signed by {} and unchanged since",
+                public_key.trim()
+            );
+        }
+        Commands::Inspect {
+            plan,
+            list_tasks: show_tasks,
+            list_hosts: show_hosts,
+            list_binaries,
+            critical_path,
+            timeline,
+            failure_impact,
+            dot,
+            mermaid,
+            plantuml,
+            html,
+        } => {
+            let execution_plan = read_plan(plan.as_deref())?;
+            let reports = [
+                (show_tasks, list_tasks as fn(&ExecutionPlan)),
+                (show_hosts, list_hosts),
+                (list_binaries, list_binary_deployments),
+                (critical_path, report_critical_path),
+                (timeline, print_timeline),
+                (failure_impact, report_failure_impact),
+            ];
+
+            let visualizer = PlanVisualizer::new();
+            let diagrams = [
+                (
+                    dot,
+                    PlanVisualizer::to_dot as fn(&PlanVisualizer, &ExecutionPlan) -> String,
+                ),
+                (mermaid, PlanVisualizer::to_mermaid),
+                (plantuml, PlanVisualizer::to_plantuml),
+                (html, PlanVisualizer::to_html),
+            ];
+
+            if !reports.iter().any(|(selected, _)| *selected)
+                && !diagrams.iter().any(|(selected, _)| *selected)
+            {
+                print_summary(&execution_plan);
+            }
+            for (_, report) in reports.iter().filter(|(selected, _)| *selected) {
+                report(&execution_plan);
+            }
+            for (_, render) in diagrams.iter().filter(|(selected, _)| *selected) {
+                print!("{}", render(&visualizer, &execution_plan));
+            }
+        }
+        Commands::Validate { plan, strict } => {
+            let execution_plan = read_plan(plan.as_deref())?;
+            let mut report = PlanValidator::new().validate(&execution_plan)?;
+
+            // Plans from before digests can still be checked for consistency
+            match PlanIntegrity::new().verify(&execution_plan) {
+                Ok(()) => {}
+                Err(PlanError::MissingPlanDigest) => report
+                    .warnings
+                    .push("Plan has no digest, so changes to it can't be detected".to_string()),
+                Err(err) => report.errors.push(err.to_string()),
+            }
+
+            for error in &report.errors {
+                println!("Error: {error}");
+            }
+            for warning in &report.warnings {
+                println!("Warning: {warning}");
+            }
+
+            if !report.errors.is_empty() || (strict && !report.warnings.is_empty()) {
+                anyhow::bail!(
+                    "Execution plan failed validation with {} errors and {} warnings",
+                    report.errors.len(),
+                    report.warnings.len()
+                );
+            }
+            println!("Plan is valid with {} warnings", report.warnings.len());
+        }
+        Commands::Diff { old, new, output } => {
+            let old_plan = read_plan(Some(&old))?;
+            let new_plan = read_plan(Some(&new))?;
+            let diff = PlanDiffer::new().diff(&old_plan, &new_plan);
+
+            match output {
+                DiffFormat::Text => print_plan_diff(&diff),
+                DiffFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+            }
+        }
+        Commands::Schema { target } => {
+            let schema = match target {
+                SchemaTarget::Plan => execution_plan_schema(),
+                SchemaTarget::Input => schemars::schema_for!(RustleParseOutput).to_value(),
+            };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
     }
 
     Ok(())
 }
 
+/// Reads a plan in JSON or binary form, upgrading JSON plans written with
+/// older schema versions
+fn read_plan(path: Option<&Path>) -> Result<ExecutionPlan> {
+    let content = read_input_bytes(path, "execution plan")?;
+    if BinaryPlanFormat::is_binary_plan(&content) {
+        return BinaryPlanFormat::new()
+            .decode(&content)
+            .context("Failed to read binary execution plan");
+    }
+    let plan = serde_json::from_slice(&content).context("Failed to parse execution plan JSON")?;
+    PlanMigrator::new()
+        .load(plan)
+        .context("Failed to load execution plan")
+}
+
+fn signing_key_path(key: Option<PathBuf>) -> Result<PathBuf> {
+    match key {
+        Some(path) => Ok(path),
+        None => default_key_path("plan-signing.key"),
+    }
+}
+
+fn default_key_path(name: &str) -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("HOME is not set; pass the key path explicitly")?;
+    Ok(PathBuf::from(home).join(".rustle").join(name))
+}
+
+fn public_key_path(key_path: &Path) -> PathBuf {
+    let mut path = key_path.as_os_str().to_owned();
+    path.push(".pub");
+    PathBuf::from(path)
+}
+
+/// Writes the secret key readable by its owner only
+fn write_secret_key(path: &Path, secret_key: &str) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    // The mode only applies to new files, so tighten keys being replaced
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict {}", path.display()))?;
+    writeln!(file, "{secret_key}").with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum StringOrVec {
+    String(String),
+    Vec(Vec<String>),
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum SizeOrVec {
+    Size(BatchSize),
+    Vec(Vec<BatchSize>),
+}
+
 fn deserialize_hosts<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    use serde::Deserialize;
-
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum StringOrVec {
-        String(String),
-        Vec(Vec<String>),
-    }
-
     match StringOrVec::deserialize(deserializer)? {
         StringOrVec::String(s) => Ok(vec![s]),
         StringOrVec::Vec(v) => Ok(v),
     }
 }
 
+fn deserialize_serial<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<rustle_plan::BatchSize>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(
+        Option::<SizeOrVec>::deserialize(deserializer)?.map(|serial| match serial {
+            SizeOrVec::Size(size) => vec![size],
+            SizeOrVec::Vec(sizes) => sizes,
+        }),
+    )
+}
+
 fn remove_first_inventory_field(content: &str) -> String {
     // Count occurrences of "inventory": field
     let inventory_pattern = r#""inventory":"#;
@@ -299,127 +862,170 @@ fn remove_first_inventory_field(content: &str) -> String {
     }
 }
 
+/// The output of rustle-parse
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseOutput {
+    metadata: RustleParseMetadata,
+    plays: Vec<RustleParsePlay>,
+    variables: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    inventory: Option<RustleParseInventory>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseMetadata {
+    file_path: String,
+    #[serde(default)]
+    #[allow(dead_code)]
+    version: Option<String>,
+    #[allow(dead_code)]
+    created_at: String,
+    #[allow(dead_code)]
+    checksum: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParsePlay {
+    name: String,
+    #[serde(deserialize_with = "deserialize_hosts")]
+    #[schemars(with = "StringOrVec")]
+    hosts: Vec<String>,
+    tasks: Vec<RustleParseTask>,
+    handlers: Vec<RustleParseHandler>,
+    vars: HashMap<String, serde_json::Value>,
+    #[serde(default, deserialize_with = "deserialize_serial")]
+    #[schemars(with = "Option<SizeOrVec>")]
+    serial: Option<Vec<rustle_plan::BatchSize>>, // A size or a ramp of sizes
+    #[serde(default)]
+    max_fail_percentage: Option<u32>,
+    #[serde(default)]
+    any_errors_fatal: bool,
+    #[serde(default)]
+    order: Option<rustle_plan::HostOrder>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseTask {
+    id: String,
+    name: String,
+    module: String,
+    args: HashMap<String, serde_json::Value>,
+    dependencies: Vec<String>,
+    tags: Vec<String>,
+    when: Option<String>,
+    notify: Vec<String>,
+    #[serde(default)]
+    vars: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    loop_items: Option<serde_json::Value>,
+    #[serde(default)]
+    changed_when: Option<serde_json::Value>, // String, bool or list of conditions
+    #[serde(default)]
+    failed_when: Option<serde_json::Value>,
+    #[serde(default)]
+    ignore_errors: Option<bool>,
+    #[serde(default)]
+    delegate_to: Option<String>,
+    #[serde(default)]
+    no_log: Option<serde_json::Value>, // A bool or a template
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseHandler {
+    id: String,
+    name: String,
+    module: String,
+    args: HashMap<String, serde_json::Value>,
+    when: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_hosts")]
+    #[schemars(with = "StringOrVec")]
+    listen: Vec<String>, // A single topic or a list of topics
+    #[serde(default)]
+    no_log: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseInventory {
+    // Support both old format (host array) and new format (host objects)
+    #[serde(default)]
+    hosts: Option<RustleParseHosts>,
+    #[serde(default)]
+    groups: Option<serde_json::Value>, // Can be HashMap<String, Vec<String>> or HashMap<String, RustleParseGroup>
+    #[serde(default)]
+    host_vars: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
+    #[serde(default)]
+    variables: Option<HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    vars: Option<HashMap<String, serde_json::Value>>, // Alternative field name for variables
+    #[serde(default)]
+    #[allow(dead_code)] // Future use for host facts integration
+    host_facts: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum RustleParseHosts {
+    Names(Vec<String>),
+    // Keyed by host name, in file order
+    Detailed(
+        #[schemars(with = "HashMap<String, RustleParseHost>")] IndexMap<String, RustleParseHost>,
+    ),
+    #[allow(dead_code)] // Unrecognised formats give no hosts
+    Other(serde_json::Value),
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseHost {
+    #[allow(dead_code)] // Used for deserialization compatibility
+    name: String,
+    #[serde(default)]
+    #[allow(dead_code)] // Used for deserialization compatibility
+    address: Option<String>,
+    #[serde(default)]
+    #[allow(dead_code)] // Used for deserialization compatibility
+    port: Option<u16>,
+    #[serde(default)]
+    #[allow(dead_code)] // Used for deserialization compatibility
+    user: Option<String>,
+    #[serde(default)]
+    #[allow(dead_code)] // Used for deserialization compatibility
+    groups: Vec<String>,
+    #[serde(default)]
+    vars: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RustleParseGroup {
+    #[allow(dead_code)] // Used for deserialization compatibility
+    name: String,
+    #[serde(default)]
+    hosts: Vec<String>,
+    #[serde(default)]
+    children: Vec<String>,
+    #[serde(default)]
+    vars: HashMap<String, serde_json::Value>,
+}
+
 fn parse_rustle_output(
     content: &str,
-) -> Result<(rustle_plan::ParsedPlaybook, rustle_plan::ParsedInventory)> {
-    use serde::Deserialize;
-    use std::collections::HashMap;
-
+) -> Result<(
+    rustle_plan::ParsedPlaybook,
+    rustle_plan::ParsedInventory,
+    PathBuf,
+)> {
     // Handle duplicate inventory fields by removing the first occurrence
     let processed_content = remove_first_inventory_field(content);
 
-    // Parse the processed content
-    let json_value: serde_json::Value = serde_json::from_str(&processed_content)
-        .context("Failed to parse JSON from rustle-parse")?;
-
-    #[derive(Deserialize)]
-    struct RustleParseOutput {
-        metadata: RustleParseMetadata,
-        plays: Vec<RustleParsePlay>,
-        variables: HashMap<String, serde_json::Value>,
-        #[serde(default)]
-        inventory: Option<RustleParseInventory>,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParseMetadata {
-        file_path: String,
-        #[serde(default)]
-        #[allow(dead_code)]
-        version: Option<String>,
-        #[allow(dead_code)]
-        created_at: String,
-        #[allow(dead_code)]
-        checksum: String,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParsePlay {
-        name: String,
-        #[serde(deserialize_with = "deserialize_hosts")]
-        hosts: Vec<String>,
-        tasks: Vec<RustleParseTask>,
-        handlers: Vec<RustleParseHandler>,
-        vars: HashMap<String, serde_json::Value>,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParseTask {
-        id: String,
-        name: String,
-        module: String,
-        args: HashMap<String, serde_json::Value>,
-        dependencies: Vec<String>,
-        tags: Vec<String>,
-        when: Option<String>,
-        notify: Vec<String>,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParseHandler {
-        id: String,
-        name: String,
-        module: String,
-        args: HashMap<String, serde_json::Value>,
-        when: Option<String>,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParseInventory {
-        // Support both old format (host array) and new format (host objects)
-        #[serde(default)]
-        hosts: Option<serde_json::Value>, // Can be Vec<String> or HashMap<String, RustleParseHost>
-        #[serde(default)]
-        groups: Option<serde_json::Value>, // Can be HashMap<String, Vec<String>> or HashMap<String, RustleParseGroup>
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        host_vars: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
-        #[serde(default)]
-        variables: Option<HashMap<String, serde_json::Value>>,
-        #[serde(default)]
-        vars: Option<HashMap<String, serde_json::Value>>, // Alternative field name for variables
-        #[serde(default)]
-        #[allow(dead_code)] // Future use for host facts integration
-        host_facts: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParseHost {
-        #[allow(dead_code)] // Used for deserialization compatibility
-        name: String,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        address: Option<String>,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        port: Option<u16>,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        user: Option<String>,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        groups: Vec<String>,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        vars: HashMap<String, serde_json::Value>,
-    }
-
-    #[derive(Deserialize)]
-    struct RustleParseGroup {
-        #[allow(dead_code)] // Used for deserialization compatibility
-        name: String,
-        #[serde(default)]
-        hosts: Vec<String>,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        children: Vec<String>,
-        #[serde(default)]
-        #[allow(dead_code)] // Used for deserialization compatibility
-        vars: HashMap<String, serde_json::Value>,
-    }
-
-    let parsed: RustleParseOutput = serde_json::from_value(json_value)
-        .context("Failed to parse structured data from rustle-parse")?;
+    // Parse the processed content straight from the text, so inventory hosts
+    // keep the order they have in the file
+    let parsed: RustleParseOutput = serde_json::from_str(&processed_content).map_err(|err| {
+        let context = if err.is_data() {
+            "Failed to parse structured data from rustle-parse"
+        } else {
+            "Failed to parse JSON from rustle-parse"
+        };
+        anyhow::Error::new(err).context(context)
+    })?;
 
     // Extract playbook name from file path
     let playbook_name = std::path::Path::new(&parsed.metadata.file_path)
@@ -444,6 +1050,13 @@ fn parse_rustle_output(
                     tags: task.tags,
                     when: task.when,
                     notify: task.notify,
+                    vars: task.vars,
+                    loop_items: task.loop_items,
+                    changed_when: task.changed_when.and_then(condition_to_string),
+                    failed_when: task.failed_when.and_then(condition_to_string),
+                    ignore_errors: task.ignore_errors.unwrap_or(false),
+                    no_log: task.no_log.is_some_and(no_log_enabled),
+                    delegate_to: task.delegate_to,
                 })
                 .collect();
 
@@ -456,6 +1069,8 @@ fn parse_rustle_output(
                     module: handler.module,
                     args: handler.args,
                     when: handler.when,
+                    listen: handler.listen,
+                    no_log: handler.no_log.is_some_and(no_log_enabled),
                 })
                 .collect();
 
@@ -465,6 +1080,10 @@ fn parse_rustle_output(
                 tasks,
                 handlers,
                 vars: play.vars,
+                serial: play.serial,
+                max_fail_percentage: play.max_fail_percentage,
+                any_errors_fatal: play.any_errors_fatal,
+                order: play.order,
             }
         })
         .collect();
@@ -476,24 +1095,28 @@ fn parse_rustle_output(
     };
 
     let parsed_inventory = if let Some(inventory) = parsed.inventory {
-        // Extract host names - support both old format (Vec<String>) and new format (HashMap)
-        let hosts = if let Some(hosts_value) = inventory.hosts {
-            if let Ok(host_vec) = serde_json::from_value::<Vec<String>>(hosts_value.clone()) {
-                // Old format: simple array of host names
-                host_vec
-            } else if let Ok(host_map) =
-                serde_json::from_value::<HashMap<String, RustleParseHost>>(hosts_value)
-            {
-                // New format: object with host details
-                host_map.keys().cloned().collect()
-            } else {
-                vec![]
+        let mut host_vars = inventory.host_vars.unwrap_or_default();
+
+        // Extract host names - support both old format (Vec<String>) and new format (map)
+        let hosts = match inventory.hosts {
+            // Old format: simple array of host names
+            Some(RustleParseHosts::Names(host_vec)) => host_vec,
+            // New format: object with host details; inline vars override host_vars
+            Some(RustleParseHosts::Detailed(host_map)) => {
+                let names: Vec<String> = host_map.keys().cloned().collect();
+                for (name, host) in host_map {
+                    if !host.vars.is_empty() {
+                        host_vars.entry(name).or_default().extend(host.vars);
+                    }
+                }
+                names
             }
-        } else {
-            vec![]
+            Some(RustleParseHosts::Other(_)) | None => vec![],
         };
 
         // Extract group-to-hosts mapping - support both old and new formats
+        let mut group_children = HashMap::new();
+        let mut group_vars = HashMap::new();
         let groups = if let Some(groups_value) = inventory.groups {
             if let Ok(group_map) =
                 serde_json::from_value::<HashMap<String, Vec<String>>>(groups_value.clone())
@@ -503,11 +1126,18 @@ fn parse_rustle_output(
             } else if let Ok(group_objects) =
                 serde_json::from_value::<HashMap<String, RustleParseGroup>>(groups_value)
             {
-                // New format: object with group details
-                group_objects
-                    .into_iter()
-                    .map(|(name, group)| (name, group.hosts))
-                    .collect()
+                // New format: object with group details, children and vars
+                let mut groups = HashMap::new();
+                for (name, group) in group_objects {
+                    if !group.children.is_empty() {
+                        group_children.insert(name.clone(), group.children);
+                    }
+                    if !group.vars.is_empty() {
+                        group_vars.insert(name.clone(), group.vars);
+                    }
+                    groups.insert(name, group.hosts);
+                }
+                groups
             } else {
                 HashMap::new()
             }
@@ -526,12 +1156,51 @@ fn parse_rustle_output(
             groups,
             vars,
             host_facts,
+            group_children,
+            group_vars,
+            host_vars,
         }
     } else {
         create_default_inventory()
     };
 
-    Ok((parsed_playbook, parsed_inventory))
+    Ok((
+        parsed_playbook,
+        parsed_inventory,
+        PathBuf::from(parsed.metadata.file_path),
+    ))
+}
+
+/// Normalizes a `changed_when`/`failed_when` value to a single expression.
+/// A list of conditions must all hold, as in Ansible.
+fn condition_to_string(value: serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(expression) => Some(expression),
+        serde_json::Value::Array(conditions) => {
+            let parts: Vec<String> = conditions
+                .into_iter()
+                .filter_map(condition_to_string)
+                .map(|c| format!("({c})"))
+                .collect();
+            (!parts.is_empty()).then(|| parts.join(" and "))
+        }
+        other => Some(other.to_string()),
+    }
+}
+
+/// Whether a `no_log` value hides output. Templated values can only be
+/// decided at runtime, so they count as enabled.
+fn no_log_enabled(value: serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Bool(enabled) => enabled,
+        serde_json::Value::String(text) => !matches!(
+            text.trim().to_lowercase().as_str(),
+            "" | "false" | "no" | "off" | "0"
+        ),
+        serde_json::Value::Null => false,
+        _ => true,
+    }
 }
 
 fn create_default_inventory() -> rustle_plan::ParsedInventory {
@@ -540,6 +1209,176 @@ fn create_default_inventory() -> rustle_plan::ParsedInventory {
         groups: std::collections::HashMap::new(),
         vars: std::collections::HashMap::new(),
         host_facts: std::collections::HashMap::new(),
+        group_children: std::collections::HashMap::new(),
+        group_vars: std::collections::HashMap::new(),
+        host_vars: std::collections::HashMap::new(),
+    }
+}
+
+fn print_plan_diff(diff: &PlanDiff) {
+    if diff.is_empty() {
+        println!("No differences");
+        return;
+    }
+
+    if !diff.hosts_added.is_empty() || !diff.hosts_removed.is_empty() {
+        println!(
+            "Hosts:{}",
+            item_changes(&diff.hosts_added, &diff.hosts_removed)
+        );
+    }
+    if let Some(duration) = &diff.estimated_duration {
+        println!(
+            "Estimated duration: {} -> {}",
+            show_duration(duration.old),
+            show_duration(duration.new)
+        );
+    }
+
+    if !diff.tasks.is_empty() {
+        println!("Tasks:");
+    }
+    for task in &diff.tasks {
+        println!(
+            "  {} {}/{} {}",
+            diff_marker(task.kind),
+            task.play_id,
+            task.task_id,
+            task.name
+        );
+        if !task.hosts_added.is_empty() || !task.hosts_removed.is_empty() {
+            println!(
+                "      hosts:{}",
+                item_changes(&task.hosts_added, &task.hosts_removed)
+            );
+        }
+        if let Some(module) = &task.module {
+            println!("      module: {} -> {}", module.old, module.new);
+        }
+        for arg in &task.args {
+            let show = |value: &Option<serde_json::Value>| {
+                value
+                    .as_ref()
+                    .map_or_else(|| "(unset)".to_string(), ToString::to_string)
+            };
+            println!(
+                "      args.{}: {} -> {}",
+                arg.key,
+                show(&arg.old),
+                show(&arg.new)
+            );
+        }
+        if let Some(risk) = &task.risk_level {
+            println!("      risk: {:?} -> {:?}", risk.old, risk.new);
+        }
+        if let Some(duration) = &task.estimated_duration {
+            println!(
+                "      duration: {} -> {}",
+                show_duration(duration.old),
+                show_duration(duration.new)
+            );
+        }
+        if let Some(batches) = &task.batches {
+            println!(
+                "      batches: {} -> {}",
+                batches.old.join(", "),
+                batches.new.join(", ")
+            );
+        }
+    }
+
+    if !diff.batches.is_empty() {
+        println!("Batches:");
+    }
+    for batch in &diff.batches {
+        println!(
+            "  {} {}/{}",
+            diff_marker(batch.kind),
+            batch.play_id,
+            batch.batch_id
+        );
+        if let Some(position) = &batch.position {
+            println!("      position: {} -> {}", position.old, position.new);
+        }
+        if !batch.tasks_added.is_empty() || !batch.tasks_removed.is_empty() {
+            println!(
+                "      tasks:{}",
+                item_changes(&batch.tasks_added, &batch.tasks_removed)
+            );
+        }
+        if !batch.hosts_added.is_empty() || !batch.hosts_removed.is_empty() {
+            println!(
+                "      hosts:{}",
+                item_changes(&batch.hosts_added, &batch.hosts_removed)
+            );
+        }
+    }
+
+    if !diff.binary_deployments.is_empty() {
+        println!("Binary deployments:");
+    }
+    for deployment in &diff.binary_deployments {
+        println!(
+            "  {} {}/{}",
+            diff_marker(deployment.kind),
+            deployment.play_id,
+            deployment.deployment_id
+        );
+        if !deployment.split_into.is_empty() {
+            println!("      split into: {}", deployment.split_into.join(", "));
+        }
+        if !deployment.merged_from.is_empty() {
+            println!("      merged from: {}", deployment.merged_from.join(", "));
+        }
+        if !deployment.tasks_added.is_empty() || !deployment.tasks_removed.is_empty() {
+            println!(
+                "      tasks:{}",
+                item_changes(&deployment.tasks_added, &deployment.tasks_removed)
+            );
+        }
+        if !deployment.hosts_added.is_empty() || !deployment.hosts_removed.is_empty() {
+            println!(
+                "      hosts:{}",
+                item_changes(&deployment.hosts_added, &deployment.hosts_removed)
+            );
+        }
+    }
+}
+
+fn show_duration(duration: Option<std::time::Duration>) -> String {
+    duration.map_or_else(|| "unknown".to_string(), |duration| format!("{duration:?}"))
+}
+
+fn diff_marker(kind: DiffKind) -> char {
+    match kind {
+        DiffKind::Added => '+',
+        DiffKind::Removed => '-',
+        DiffKind::Changed => '~',
+    }
+}
+
+/// Added and removed items as ` +a +b -c`
+fn item_changes(added: &[String], removed: &[String]) -> String {
+    let added = added.iter().map(|item| format!(" +{item}"));
+    let removed = removed.iter().map(|item| format!(" -{item}"));
+    added.chain(removed).collect()
+}
+
+fn print_summary(plan: &ExecutionPlan) {
+    println!("Created: {}", plan.metadata.created_at);
+    println!(
+        "Planned by: rustle-plan {} (schema version {})",
+        plan.metadata.rustle_plan_version, plan.metadata.schema_version
+    );
+    println!("Plays: {}", plan.plays.len());
+    println!("Tasks: {}", plan.total_tasks);
+    println!("Hosts: {}", plan.hosts.len());
+    println!("Binary deployments: {}", plan.binary_deployments.len());
+    if let Some(duration) = plan.estimated_duration {
+        println!("Estimated execution time: {duration:?}");
+    }
+    if let Some(signature) = &plan.metadata.signature {
+        println!("Signed by: {}", signature.public_key);
     }
 }
 
@@ -553,6 +1392,16 @@ fn list_tasks(plan: &rustle_plan::ExecutionPlan) {
             }
         }
     }
+
+    if !plan.filtered_tasks.is_empty() {
+        println!("Filtered tasks:");
+        for filtered in &plan.filtered_tasks {
+            println!(
+                "  - {} ({}) on {}: {}",
+                filtered.task_name, filtered.task_id, filtered.host, filtered.reason
+            );
+        }
+    }
 }
 
 fn list_hosts(plan: &rustle_plan::ExecutionPlan) {
@@ -575,29 +1424,152 @@ fn list_binary_deployments(plan: &rustle_plan::ExecutionPlan) {
     }
 }
 
-fn generate_dot_visualization(plan: &rustle_plan::ExecutionPlan) -> Result<()> {
-    println!("digraph execution_plan {{");
-    println!("  rankdir=TB;");
-    println!("  node [shape=box];");
+fn report_failure_impact(plan: &rustle_plan::ExecutionPlan) {
+    let planner = rustle_plan::FailurePolicyPlanner::new();
 
+    println!("Failure policy:");
     for (play_idx, play) in plan.plays.iter().enumerate() {
-        println!("  subgraph cluster_{play_idx} {{");
-        println!("    label=\"{}\";", play.name);
+        let policy = match (play.any_errors_fatal, play.max_fail_percentage) {
+            (true, _) => "any errors fatal".to_string(),
+            (false, Some(percent)) => format!("max {percent}% failed"),
+            (false, None) => "abort when all hosts fail".to_string(),
+        };
+        println!("  Play {}: {} ({})", play_idx + 1, play.name, policy);
 
         for batch in &play.batches {
-            for task in &batch.tasks {
-                println!("    \"{}\" [label=\"{}\"];", task.task_id, task.name);
+            if let Some(gate) = &batch.proceed_if {
+                println!(
+                    "    - {}: aborts at {} of {} failed hosts",
+                    batch.batch_id,
+                    gate.abort_threshold,
+                    gate.hosts.len()
+                );
+            }
+        }
+    }
 
-                for dep in &task.dependencies {
-                    println!("    \"{}\" -> \"{}\";", dep, task.task_id);
-                }
+    println!(
+        "Worst case: {} of {} hosts fail before the plan aborts",
+        planner.worst_case_failed_hosts(&plan.plays),
+        plan.hosts.len()
+    );
+}
+
+fn report_critical_path(plan: &rustle_plan::ExecutionPlan) {
+    println!("Critical path:");
+    for (play_idx, play) in plan.plays.iter().enumerate() {
+        let Some(critical_path) = &play.critical_path else {
+            println!("  Play {}: {} (no tasks)", play_idx + 1, play.name);
+            continue;
+        };
+        println!(
+            "  Play {}: {} ({:?})",
+            play_idx + 1,
+            play.name,
+            critical_path.duration
+        );
+
+        let mut tasks: Vec<&rustle_plan::TaskPlan> =
+            play.batches.iter().flat_map(|batch| &batch.tasks).collect();
+        for task in tasks.iter().filter(|task| {
+            task.schedule
+                .as_ref()
+                .is_some_and(|schedule| schedule.on_critical_path)
+        }) {
+            println!(
+                "    * {} ({}): {:?}",
+                task.name,
+                task.task_id,
+                task.estimated_duration.unwrap_or_default()
+            );
+        }
+
+        // Longest critical tasks first: shortening them pays off the most
+        tasks.sort_by_key(|task| {
+            (
+                task.schedule.as_ref().map(|s| s.slack).unwrap_or_default(),
+                std::cmp::Reverse(task.estimated_duration.unwrap_or_default()),
+            )
+        });
+        println!("    Slack:");
+        for task in tasks {
+            if let Some(schedule) = &task.schedule {
+                println!(
+                    "      - {} ({}): start {:?}..{:?}, slack {:?}",
+                    task.name,
+                    task.task_id,
+                    schedule.earliest_start,
+                    schedule.latest_start,
+                    schedule.slack
+                );
             }
         }
+    }
+}
+
+fn print_timeline(plan: &rustle_plan::ExecutionPlan) {
+    const WIDTH: usize = 40;
+
+    let total = plan
+        .host_timelines
+        .iter()
+        .map(|timeline| timeline.finish)
+        .max()
+        .unwrap_or_default();
+    let column = |offset: std::time::Duration| {
+        if total.is_zero() {
+            0
+        } else {
+            ((offset.as_secs_f64() / total.as_secs_f64()) * WIDTH as f64).round() as usize
+        }
+    };
 
-        println!("  }}");
+    println!(
+        "Timeline (forks: {}, total {:?}):",
+        plan.metadata.planning_options.forks, total
+    );
+    for timeline in &plan.host_timelines {
+        println!("  {} (finishes at {:?})", timeline.host, timeline.finish);
+        for entry in &timeline.entries {
+            // '.' marks time spent waiting for a fork, '#' the task itself
+            let ready = column(entry.start - entry.fork_wait);
+            let start = column(entry.start).max(ready);
+            let end = column(entry.end).max(start + 1);
+            let bar = format!(
+                "{}{}{}",
+                " ".repeat(ready),
+                ".".repeat(start - ready),
+                "#".repeat(end - start)
+            );
+            println!(
+                "    |{:<width$}| {} ({}): {:?}..{:?}",
+                bar,
+                entry.task_name,
+                entry.task_id,
+                entry.start,
+                entry.end,
+                width = WIDTH
+            );
+        }
     }
 
-    println!("}}");
+    let waits: Vec<&rustle_plan::TimelineEntry> = plan
+        .host_timelines
+        .iter()
+        .flat_map(|timeline| &timeline.entries)
+        .filter(|entry| !entry.fork_wait.is_zero())
+        .collect();
+    if let Some(longest) = waits.iter().map(|entry| entry.fork_wait).max() {
+        println!(
+            "Fork limit delayed {} task runs, by up to {:?}",
+            waits.len(),
+            longest
+        );
+    }
+}
+
+fn generate_dot_visualization(plan: &rustle_plan::ExecutionPlan) -> Result<()> {
+    print!("{}", PlanVisualizer::new().to_dot(plan));
     Ok(())
 }
 
@@ -3,12 +3,19 @@ pub mod types;
 
 // Re-export specific items to avoid ambiguous glob imports
 pub use planner::{
-    BinaryDeploymentPlanner, BinarySuitabilityAnalyzer, DependencyAnalyzer, ExecutionOptimizer,
-    ExecutionPlanner, PlanError, PlanValidator, StrategyPlanner, TaskEstimator,
+    execution_plan_schema, BinaryDeploymentPlanner, BinaryPlanFormat, BinarySuitabilityAnalyzer,
+    CriticalPathAnalyzer, DependencyAnalyzer, ExecutionOptimizer, ExecutionPlanner,
+    FailurePolicyPlanner, HandlerScheduler, HostOrderer, LoopExpander, PlanDiffer, PlanError,
+    PlanIntegrity, PlanMigrator, PlanSigner, PlanValidator, PlanVerifier, PlanVisualizer,
+    SecretOpener, SecretRedactor, SecretSealer, StrategyPlanner, TaskEstimator, TimelineBuilder,
 };
 
 pub use types::{
-    BinaryDeployment, ExecutionBatch, ExecutionCondition, ExecutionPlan, ExecutionStrategy,
-    HandlerPlan, ParsedHandler, ParsedInventory, ParsedPlay, ParsedPlaybook, ParsedTask,
-    PlanMetadata, PlanningOptions, PlayPlan, RiskLevel, TaskPlan,
+    canonical_json, sha256_digest, ArgChange, BatchDiff, BatchSize, BinaryDeployment, Change,
+    CriticalPath, DeploymentDiff, DiffKind, ExecutionBatch, ExecutionCondition, ExecutionPlan,
+    ExecutionStrategy, FilteredTask, HandlerFlush, HandlerPlan, HostOrder, HostTimeline,
+    LoopExpansion, ParsedHandler, ParsedInventory, ParsedPlay, ParsedPlaybook, ParsedTask,
+    PlanDiff, PlanMetadata, PlanSignature, PlanningOptions, PlayPlan, ProceedGate, RiskLevel,
+    SealedSecrets, StageGate, TaskDiff, TaskPlan, TaskSchedule, TimelineEntry, TopologySpread,
+    PLAN_SCHEMA_VERSION,
 };
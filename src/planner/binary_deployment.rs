@@ -1,11 +1,15 @@
 use crate::planner::error::PlanError;
+use crate::planner::expression::to_bool;
 use crate::types::*;
 use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 pub struct BinaryDeploymentPlanner {
     _compilation_cache: CompilationCache,
     _target_profiles: HashMap<String, TargetProfile>,
+    base_dir: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -55,9 +59,17 @@ impl BinaryDeploymentPlanner {
                 _cached_builds: HashMap::new(),
             },
             _target_profiles: target_profiles,
+            base_dir: PathBuf::from("."),
         }
     }
 
+    /// Directory relative `src` paths of embedded files are resolved
+    /// against, normally the playbook's directory
+    pub fn with_base_dir(mut self, base_dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = base_dir.into();
+        self
+    }
+
     pub fn plan_deployments(
         &self,
         tasks: &[TaskPlan],
@@ -204,6 +216,7 @@ impl BinaryDeploymentPlanner {
 
         Ok(BinaryDeployment {
             deployment_id: group.id.clone(),
+            play_id: String::new(), // Set by the execution planner
             target_hosts: deployment_hosts.clone(),
             binary_name: format!("rustle-runner-{}", group.id),
             tasks: group.tasks.iter().map(|t| t.task_id.clone()).collect(),
@@ -240,14 +253,19 @@ impl BinaryDeploymentPlanner {
         let mut files = Vec::new();
 
         for task in tasks {
-            if task.module == "copy" || task.module == "template" {
+            // A remote_src copy reads its source on the target host, so
+            // there is nothing to embed
+            let remote_src = task.args.get("remote_src").is_some_and(to_bool);
+            if task.module == "template" || (task.module == "copy" && !remote_src) {
                 if let Some(src) = task.args.get("src").and_then(|v| v.as_str()) {
                     if let Some(dest) = task.args.get("dest").and_then(|v| v.as_str()) {
+                        let contents = self.read_static_file(&task.module, src);
+
                         files.push(EmbeddedFile {
                             src_path: src.to_string(),
                             dest_path: dest.to_string(),
-                            checksum: "placeholder-checksum".to_string(), // Would calculate real checksum
-                            size: 1024, // Would calculate real size
+                            checksum: contents.as_deref().map(sha256_digest),
+                            size: contents.map_or(0, |data| data.len() as u64),
                         });
                     }
                 }
@@ -305,6 +323,50 @@ impl BinaryDeploymentPlanner {
         Ok(facts)
     }
 
+    /// Reads a `copy` or `template` source the way Ansible finds it: from
+    /// the `files/` or `templates/` directory next to the playbook, then
+    /// from the playbook directory itself. Templated paths can't be read
+    /// until runtime and give `None`, as do missing and unreadable files.
+    fn read_static_file(&self, module: &str, src: &str) -> Option<Vec<u8>> {
+        if src.contains("{{") {
+            return None;
+        }
+
+        let path = Path::new(src);
+        let candidates = if path.is_absolute() {
+            vec![path.to_path_buf()]
+        } else {
+            let subdir = if module == "template" {
+                "templates"
+            } else {
+                "files"
+            };
+            vec![
+                self.base_dir.join(subdir).join(path),
+                self.base_dir.join(path),
+            ]
+        };
+
+        for candidate in candidates {
+            match std::fs::read(&candidate) {
+                Ok(contents) => return Some(contents),
+                Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::IsADirectory) => {
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Static file '{}' can't be read ({}), checksum unknown",
+                        candidate.display(),
+                        err
+                    );
+                    return None;
+                }
+            }
+        }
+
+        tracing::warn!("Static file '{}' not found, checksum unknown", src);
+        None
+    }
+
     fn estimate_binary_size(&self, group: &TaskGroup) -> Result<u64, PlanError> {
         // Base binary size (Rust runtime + our code)
         let base_size = 5 * 1024 * 1024; // 5MB
@@ -423,7 +485,7 @@ impl BinaryDeploymentPlanner {
         deployments: &mut Vec<BinaryDeployment>,
     ) -> Result<(), PlanError> {
         // Sort by estimated benefit (larger deployments first)
-        deployments.sort_by(|a, b| b.estimated_size.cmp(&a.estimated_size));
+        deployments.sort_by_key(|d| std::cmp::Reverse(d.estimated_size));
 
         // Remove duplicate deployments for the same hosts
         deployments.dedup_by(|a, b| a.target_hosts == b.target_hosts);
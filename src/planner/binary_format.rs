@@ -0,0 +1,158 @@
+use crate::planner::error::PlanError;
+use crate::types::*;
+use std::io::{BufReader, Read, Write};
+
+/// First bytes of every binary plan
+pub const BINARY_PLAN_MAGIC: &[u8; 4] = b"RPLN";
+
+/// Version of the binary plan layout written by this release
+pub const BINARY_PLAN_VERSION: u16 = 1;
+
+const FLAG_ZSTD: u8 = 0b0000_0001;
+const HEADER_LEN: usize = 7;
+
+/// Compact binary encoding of an `ExecutionPlan`.
+///
+/// A plan is the 4-byte magic `RPLN`, a little-endian `u16` format version
+/// and a flags byte, followed by the plan as MessagePack with named fields,
+/// zstd-compressed when the flags say so. Named fields keep plans readable
+/// after fields are added, like the JSON output.
+pub struct BinaryPlanFormat {
+    compression_level: Option<i32>,
+}
+
+impl BinaryPlanFormat {
+    pub fn new() -> Self {
+        Self {
+            compression_level: None,
+        }
+    }
+
+    /// Compress written plans with zstd at this level; 0 is zstd's default
+    pub fn with_compression(mut self, level: i32) -> Self {
+        self.compression_level = Some(level);
+        self
+    }
+
+    /// Whether `bytes` start like a binary plan
+    pub fn is_binary_plan(bytes: &[u8]) -> bool {
+        bytes.starts_with(BINARY_PLAN_MAGIC)
+    }
+
+    pub fn encode(&self, plan: &ExecutionPlan) -> Result<Vec<u8>, PlanError> {
+        let mut bytes = Vec::new();
+        self.write(plan, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn write<W: Write>(&self, plan: &ExecutionPlan, mut writer: W) -> Result<(), PlanError> {
+        let flags = if self.compression_level.is_some() {
+            FLAG_ZSTD
+        } else {
+            0
+        };
+        writer.write_all(BINARY_PLAN_MAGIC)?;
+        writer.write_all(&BINARY_PLAN_VERSION.to_le_bytes())?;
+        writer.write_all(&[flags])?;
+
+        match self.compression_level {
+            Some(level) => {
+                let mut encoder = zstd::Encoder::new(writer, level)?;
+                rmp_serde::encode::write_named(&mut encoder, plan).map_err(encoding_error)?;
+                encoder.finish()?;
+            }
+            None => rmp_serde::encode::write_named(&mut writer, plan).map_err(encoding_error)?,
+        }
+        Ok(())
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<ExecutionPlan, PlanError> {
+        self.read(bytes)
+    }
+
+    /// Reads a plan written by `write`, compressed or not
+    pub fn read<R: Read>(&self, mut reader: R) -> Result<ExecutionPlan, PlanError> {
+        let mut header = [0u8; HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| invalid("truncated header"))?;
+
+        if !Self::is_binary_plan(&header) {
+            return Err(invalid("missing RPLN magic"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != BINARY_PLAN_VERSION {
+            return Err(PlanError::UnsupportedPlanVersion {
+                version: version.to_string(),
+            });
+        }
+        let flags = header[6];
+        if flags & !FLAG_ZSTD != 0 {
+            return Err(invalid(&format!("unknown flags {flags:#04x}")));
+        }
+
+        if flags & FLAG_ZSTD != 0 {
+            let decoder = zstd::Decoder::new(reader)?;
+            rmp_serde::from_read(decoder).map_err(decoding_error)
+        } else {
+            rmp_serde::from_read(BufReader::new(reader)).map_err(decoding_error)
+        }
+    }
+}
+
+impl Default for BinaryPlanFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(reason: &str) -> PlanError {
+    PlanError::InvalidPlanFormat {
+        reason: reason.to_string(),
+    }
+}
+
+fn encoding_error(err: rmp_serde::encode::Error) -> PlanError {
+    invalid(&err.to_string())
+}
+
+fn decoding_error(err: rmp_serde::decode::Error) -> PlanError {
+    invalid(&err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        let format = BinaryPlanFormat::new();
+
+        assert!(matches!(
+            format.decode(b"RPL"),
+            Err(PlanError::InvalidPlanFormat { .. })
+        ));
+        assert!(matches!(
+            format.decode(b"{\"metadata\": {}}"),
+            Err(PlanError::InvalidPlanFormat { reason }) if reason.contains("magic")
+        ));
+        assert!(matches!(
+            format.decode(b"RPLN\x09\x00\x00"),
+            Err(PlanError::UnsupportedPlanVersion { version }) if version == "9"
+        ));
+        assert!(matches!(
+            format.decode(b"RPLN\x01\x00\x80"),
+            Err(PlanError::InvalidPlanFormat { reason }) if reason.contains("flags")
+        ));
+        assert!(matches!(
+            format.decode(b"RPLN\x01\x00\x00\xc1"),
+            Err(PlanError::InvalidPlanFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn test_detects_binary_plans() {
+        assert!(BinaryPlanFormat::is_binary_plan(b"RPLN\x01\x00\x00"));
+        assert!(!BinaryPlanFormat::is_binary_plan(b"{}"));
+    }
+}
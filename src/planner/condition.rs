@@ -1,5 +1,7 @@
 use crate::planner::error::PlanError;
+use crate::planner::expression::{ConditionResult, Expression};
 use crate::types::*;
+use std::collections::HashMap;
 
 pub struct ConditionEvaluator;
 
@@ -21,34 +23,125 @@ impl ConditionEvaluator {
         Ok(true)
     }
 
-    fn evaluate_condition(
+    /// Evaluates a condition with three-valued logic.
+    ///
+    /// Only a `ConditionResult::False` means the task can be skipped at plan time.
+    pub fn evaluate(
         &self,
         condition: &ExecutionCondition,
         context: &ExecutionContext,
-    ) -> Result<bool, PlanError> {
+    ) -> Result<ConditionResult, PlanError> {
         match condition {
             ExecutionCondition::When { expression } => {
-                // Simplified expression evaluation
-                // In a real implementation, this would use a proper expression parser
-                Ok(!expression.is_empty())
+                self.evaluate_expression(expression, context)
             }
-            ExecutionCondition::Tag { tags } => {
-                Ok(tags.iter().any(|tag| context.active_tags.contains(tag)))
+            ExecutionCondition::Tag { tags } => Ok(tags
+                .iter()
+                .any(|tag| context.active_tags.contains(tag))
+                .into()),
+            ExecutionCondition::Host { pattern } => {
+                Ok(context.current_host.contains(pattern).into())
             }
-            ExecutionCondition::Host { pattern } => Ok(context.current_host.contains(pattern)),
             ExecutionCondition::SkipTag { tags } => {
-                Ok(!tags.iter().any(|tag| context.active_tags.contains(tag)))
+                Ok((!tags.iter().any(|tag| context.active_tags.contains(tag))).into())
+            }
+            ExecutionCondition::CheckMode { enabled } => {
+                Ok((*enabled == context.check_mode).into())
             }
-            ExecutionCondition::CheckMode { enabled } => Ok(*enabled == context.check_mode),
         }
     }
+
+    pub fn evaluate_expression(
+        &self,
+        expression: &str,
+        context: &ExecutionContext,
+    ) -> Result<ConditionResult, PlanError> {
+        // An empty expression never executes
+        if expression.trim().is_empty() {
+            return Ok(ConditionResult::False);
+        }
+
+        let parsed = Expression::parse(expression)?;
+        Ok(parsed.evaluate(|name| context.lookup(name)))
+    }
+
+    fn evaluate_condition(
+        &self,
+        condition: &ExecutionCondition,
+        context: &ExecutionContext,
+    ) -> Result<bool, PlanError> {
+        Ok(!self.evaluate(condition, context)?.is_false())
+    }
 }
 
 pub struct ExecutionContext {
     pub current_host: String,
     pub active_tags: Vec<String>,
     pub check_mode: bool,
-    pub variables: std::collections::HashMap<String, serde_json::Value>,
+    pub variables: HashMap<String, serde_json::Value>,
+}
+
+impl ExecutionContext {
+    /// Builds a context for `host` whose only variables are its gathered facts.
+    ///
+    /// Facts are exposed both as top-level `ansible_*` variables and, with the
+    /// prefix stripped, under `ansible_facts`, matching how Ansible injects them.
+    pub fn from_facts(
+        host: &str,
+        facts: &HashMap<String, serde_json::Value>,
+        active_tags: &[String],
+        check_mode: bool,
+    ) -> Self {
+        let mut variables = facts.clone();
+        let mut ansible_facts = match facts.get("ansible_facts") {
+            Some(serde_json::Value::Object(map)) => map.clone(),
+            _ => serde_json::Map::new(),
+        };
+
+        for (name, value) in facts {
+            if let Some(short_name) = name.strip_prefix("ansible_") {
+                if name != "ansible_facts" && !ansible_facts.contains_key(short_name) {
+                    ansible_facts.insert(short_name.to_string(), value.clone());
+                }
+            }
+        }
+        for (name, value) in &ansible_facts {
+            variables
+                .entry(format!("ansible_{name}"))
+                .or_insert_with(|| value.clone());
+        }
+        variables.insert(
+            "ansible_facts".to_string(),
+            serde_json::Value::Object(ansible_facts),
+        );
+
+        Self {
+            current_host: host.to_string(),
+            active_tags: active_tags.to_vec(),
+            check_mode,
+            variables,
+        }
+    }
+
+    /// Resolves a variable, falling back to the magic variables derived from
+    /// the context itself. Returns `None` when the value is unknown at plan time.
+    pub fn lookup(&self, name: &str) -> Option<serde_json::Value> {
+        if let Some(value) = self.variables.get(name) {
+            return Some(value.clone());
+        }
+
+        match name {
+            "inventory_hostname" => Some(serde_json::Value::String(self.current_host.clone())),
+            "inventory_hostname_short" => self
+                .current_host
+                .split('.')
+                .next()
+                .map(|short| serde_json::Value::String(short.to_string())),
+            "ansible_check_mode" => Some(serde_json::Value::Bool(self.check_mode)),
+            "ansible_run_tags" => Some(serde_json::json!(self.active_tags)),
+            _ => None,
+        }
+    }
 }
 
 impl Default for ConditionEvaluator {
@@ -60,7 +153,6 @@ impl Default for ConditionEvaluator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
 
     fn create_test_context() -> ExecutionContext {
         ExecutionContext {
@@ -86,6 +178,15 @@ mod tests {
             can_run_parallel: true,
             estimated_duration: None,
             risk_level: RiskLevel::Low,
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
         }
     }
 
@@ -171,6 +272,76 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn test_evaluate_when_condition_with_variables() {
+        let evaluator = ConditionEvaluator::new();
+        let mut context = create_test_context();
+        context.variables.insert(
+            "ansible_os_family".to_string(),
+            serde_json::Value::String("Debian".to_string()),
+        );
+
+        let matching = ExecutionCondition::When {
+            expression: "ansible_os_family == 'Debian'".to_string(),
+        };
+        let not_matching = ExecutionCondition::When {
+            expression: "ansible_os_family == 'RedHat'".to_string(),
+        };
+
+        assert_eq!(
+            evaluator.evaluate(&matching, &context).unwrap(),
+            ConditionResult::True
+        );
+        assert_eq!(
+            evaluator.evaluate(&not_matching, &context).unwrap(),
+            ConditionResult::False
+        );
+        assert!(!evaluator
+            .evaluate_condition(&not_matching, &context)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_evaluate_when_condition_unknown_is_not_pruned() {
+        let evaluator = ConditionEvaluator::new();
+        let condition = ExecutionCondition::When {
+            expression: "result.rc == 0".to_string(),
+        };
+        let context = create_test_context();
+
+        assert_eq!(
+            evaluator.evaluate(&condition, &context).unwrap(),
+            ConditionResult::Unknown
+        );
+        assert!(evaluator.evaluate_condition(&condition, &context).unwrap());
+    }
+
+    #[test]
+    fn test_evaluate_when_condition_magic_variables() {
+        let evaluator = ConditionEvaluator::new();
+        let context = create_test_context();
+
+        let result = evaluator
+            .evaluate_expression(
+                "inventory_hostname == 'web-server-01' and not ansible_check_mode",
+                &context,
+            )
+            .unwrap();
+        assert_eq!(result, ConditionResult::True);
+    }
+
+    #[test]
+    fn test_evaluate_when_condition_invalid_expression() {
+        let evaluator = ConditionEvaluator::new();
+        let condition = ExecutionCondition::When {
+            expression: "port ==".to_string(),
+        };
+        let context = create_test_context();
+
+        let result = evaluator.evaluate(&condition, &context);
+        assert!(matches!(result, Err(PlanError::InvalidCondition { .. })));
+    }
+
     #[test]
     fn test_evaluate_tag_condition_matching() {
         let evaluator = ConditionEvaluator::new();
@@ -274,6 +445,26 @@ mod tests {
         assert!(result);
     }
 
+    #[test]
+    fn test_execution_context_from_facts() {
+        let mut facts = HashMap::new();
+        facts.insert(
+            "ansible_os_family".to_string(),
+            serde_json::Value::String("RedHat".to_string()),
+        );
+        let context = ExecutionContext::from_facts("db1", &facts, &[], false);
+        let evaluator = ConditionEvaluator::new();
+
+        let result = evaluator
+            .evaluate_expression("ansible_facts['os_family'] == 'RedHat'", &context)
+            .unwrap();
+        assert_eq!(result, ConditionResult::True);
+        assert_eq!(
+            context.lookup("ansible_os_family"),
+            Some(serde_json::Value::String("RedHat".to_string()))
+        );
+    }
+
     #[test]
     fn test_execution_context_creation() {
         let context = ExecutionContext {
@@ -0,0 +1,360 @@
+use crate::types::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Computes earliest/latest start times, slack and the critical path of a
+/// play from its batches and task duration estimates.
+///
+/// Tasks are ordered the way the batches execute them: a batch starts once
+/// the batches it depends on have finished, tasks in parallel groups start
+/// together, and the remaining tasks of a batch run one after another.
+pub struct CriticalPathAnalyzer;
+
+// A task is addressed by (batch index, task index) since the same task id
+// can appear in several batches, for example with rolling updates.
+type TaskRef = (usize, usize);
+
+impl CriticalPathAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Fills in `TaskPlan.schedule` for every task and `PlayPlan.critical_path`.
+    pub fn analyze(&self, play: &mut PlayPlan) {
+        let (nodes, predecessors) = self.precedence(&play.batches);
+        if nodes.is_empty() {
+            play.critical_path = None;
+            return;
+        }
+
+        let durations: HashMap<TaskRef, Duration> = nodes
+            .iter()
+            .map(|&(b, t)| {
+                let task = &play.batches[b].tasks[t];
+                ((b, t), task.estimated_duration.unwrap_or(Duration::ZERO))
+            })
+            .collect();
+        let duration = |node: TaskRef| durations[&node];
+
+        let mut earliest_start: HashMap<TaskRef, Duration> = HashMap::new();
+        for &node in &nodes {
+            let start = predecessors[&node]
+                .iter()
+                .map(|&pred| earliest_start[&pred] + duration(pred))
+                .max()
+                .unwrap_or(Duration::ZERO);
+            earliest_start.insert(node, start);
+        }
+
+        let play_duration = nodes
+            .iter()
+            .map(|&node| earliest_start[&node] + duration(node))
+            .max()
+            .unwrap_or(Duration::ZERO);
+
+        let mut successors: HashMap<TaskRef, Vec<TaskRef>> = HashMap::new();
+        for (&node, preds) in &predecessors {
+            for &pred in preds {
+                successors.entry(pred).or_default().push(node);
+            }
+        }
+
+        let mut latest_start: HashMap<TaskRef, Duration> = HashMap::new();
+        for &node in nodes.iter().rev() {
+            let latest_finish = successors
+                .get(&node)
+                .into_iter()
+                .flatten()
+                .map(|succ| latest_start[succ])
+                .min()
+                .unwrap_or(play_duration);
+            latest_start.insert(node, latest_finish.saturating_sub(duration(node)));
+        }
+
+        for &(b, t) in &nodes {
+            let earliest = earliest_start[&(b, t)];
+            let latest = latest_start[&(b, t)];
+            let slack = latest.saturating_sub(earliest);
+            play.batches[b].tasks[t].schedule = Some(TaskSchedule {
+                earliest_start: earliest,
+                latest_start: latest,
+                slack,
+                on_critical_path: slack.is_zero(),
+            });
+        }
+
+        // Follow zero-slack tasks from the start of the play to its end
+        let is_critical = |node: &TaskRef| latest_start[node] == earliest_start[node];
+        let mut path = Vec::new();
+        let mut current = nodes
+            .iter()
+            .copied()
+            .find(|node| is_critical(node) && predecessors[node].is_empty());
+        while let Some(node) = current {
+            path.push(play.batches[node.0].tasks[node.1].task_id.clone());
+            let finish = earliest_start[&node] + duration(node);
+            current = successors
+                .get(&node)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|succ| is_critical(succ) && earliest_start[succ] == finish)
+                .min();
+        }
+
+        play.critical_path = Some(CriticalPath {
+            tasks: path,
+            duration: play_duration,
+        });
+    }
+
+    /// Returns the tasks in a topological order together with each task's
+    /// direct predecessors.
+    fn precedence(
+        &self,
+        batches: &[ExecutionBatch],
+    ) -> (Vec<TaskRef>, HashMap<TaskRef, Vec<TaskRef>>) {
+        let batch_index: HashMap<&str, usize> = batches
+            .iter()
+            .enumerate()
+            .map(|(index, batch)| (batch.batch_id.as_str(), index))
+            .collect();
+
+        let mut order = Vec::new();
+        let mut exits: Vec<Vec<TaskRef>> = Vec::new();
+        let mut predecessors: HashMap<TaskRef, Vec<TaskRef>> = HashMap::new();
+
+        for (b, batch) in batches.iter().enumerate() {
+            let in_group = |task: &TaskPlan| {
+                batch
+                    .parallel_groups
+                    .iter()
+                    .any(|group| group.tasks.contains(&task.task_id))
+            };
+            let parallel: Vec<TaskRef> = (0..batch.tasks.len())
+                .filter(|&t| in_group(&batch.tasks[t]))
+                .map(|t| (b, t))
+                .collect();
+            let sequential: Vec<TaskRef> = (0..batch.tasks.len())
+                .filter(|&t| !in_group(&batch.tasks[t]))
+                .map(|t| (b, t))
+                .collect();
+
+            let upstream: Vec<TaskRef> = batch
+                .dependencies
+                .iter()
+                .filter_map(|id| batch_index.get(id.as_str()))
+                .filter(|&&index| index < b)
+                .flat_map(|&index| exits[index].clone())
+                .collect();
+
+            // A batch without tasks, such as a stage gate, hands its
+            // upstream exits on to the batches that depend on it
+            let batch_exits = if batch.tasks.is_empty() {
+                upstream.clone()
+            } else {
+                match sequential.last() {
+                    Some(&last) => vec![last],
+                    None => parallel.clone(),
+                }
+            };
+
+            for &node in &parallel {
+                predecessors.insert(node, upstream.clone());
+            }
+            for (position, &node) in sequential.iter().enumerate() {
+                let preds = if position > 0 {
+                    vec![sequential[position - 1]]
+                } else if !parallel.is_empty() {
+                    parallel.clone()
+                } else {
+                    upstream.clone()
+                };
+                predecessors.insert(node, preds);
+            }
+
+            order.extend(parallel);
+            order.extend(sequential);
+            exits.push(batch_exits);
+        }
+
+        (order, predecessors)
+    }
+}
+
+impl Default for CriticalPathAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::fixtures;
+
+    fn create_test_task(id: &str, secs: u64) -> TaskPlan {
+        TaskPlan {
+            estimated_duration: Some(Duration::from_secs(secs)),
+            ..fixtures::task(id)
+        }
+    }
+
+    fn create_batch(
+        id: &str,
+        tasks: Vec<TaskPlan>,
+        parallel: &[&str],
+        dependencies: &[&str],
+    ) -> ExecutionBatch {
+        ExecutionBatch {
+            parallel_groups: if parallel.is_empty() {
+                vec![]
+            } else {
+                vec![ParallelGroup {
+                    group_id: "group_0".to_string(),
+                    tasks: fixtures::strings(parallel),
+                    max_parallelism: 4,
+                    shared_resources: vec![],
+                }]
+            },
+            dependencies: fixtures::strings(dependencies),
+            ..fixtures::batch(id, tasks)
+        }
+    }
+
+    fn create_play(batches: Vec<ExecutionBatch>) -> PlayPlan {
+        PlayPlan {
+            strategy: ExecutionStrategy::Free,
+            ..fixtures::play("play-0", batches)
+        }
+    }
+
+    fn schedule(play: &PlayPlan, task_id: &str) -> TaskSchedule {
+        play.batches
+            .iter()
+            .flat_map(|b| &b.tasks)
+            .find(|t| t.task_id == task_id)
+            .and_then(|t| t.schedule.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parallel_layers() {
+        // batch-0: a (10s) || b (2s); batch-1: c (5s) after a; batch-2: d (1s) after b
+        let mut play = create_play(vec![
+            create_batch(
+                "batch-0",
+                vec![create_test_task("a", 10), create_test_task("b", 2)],
+                &["a", "b"],
+                &[],
+            ),
+            create_batch("batch-1", vec![create_test_task("c", 5)], &[], &["batch-0"]),
+            create_batch("batch-2", vec![create_test_task("d", 1)], &[], &["batch-0"]),
+        ]);
+
+        CriticalPathAnalyzer::new().analyze(&mut play);
+
+        let critical_path = play.critical_path.clone().unwrap();
+        assert_eq!(critical_path.tasks, vec!["a", "c"]);
+        assert_eq!(critical_path.duration, Duration::from_secs(15));
+
+        let b = schedule(&play, "b");
+        assert_eq!(b.earliest_start, Duration::ZERO);
+        assert_eq!(b.slack, Duration::from_secs(8));
+        assert!(!b.on_critical_path);
+
+        let d = schedule(&play, "d");
+        assert_eq!(d.earliest_start, Duration::from_secs(10));
+        assert_eq!(d.latest_start, Duration::from_secs(14));
+        assert_eq!(d.slack, Duration::from_secs(4));
+
+        assert!(schedule(&play, "a").on_critical_path);
+        assert!(schedule(&play, "c").on_critical_path);
+    }
+
+    #[test]
+    fn test_sequential_batch_is_fully_critical() {
+        let mut play = create_play(vec![create_batch(
+            "rolling-batch-0",
+            vec![create_test_task("a", 1), create_test_task("b", 2)],
+            &[],
+            &[],
+        )]);
+
+        CriticalPathAnalyzer::new().analyze(&mut play);
+
+        let critical_path = play.critical_path.clone().unwrap();
+        assert_eq!(critical_path.tasks, vec!["a", "b"]);
+        assert_eq!(critical_path.duration, Duration::from_secs(3));
+        assert_eq!(schedule(&play, "b").earliest_start, Duration::from_secs(1));
+    }
+
+    fn create_gate(id: &str, gate: StageGate, after: &str) -> ExecutionBatch {
+        ExecutionBatch {
+            stage_gate: Some(gate),
+            ..create_batch(id, vec![], &[], &[after])
+        }
+    }
+
+    #[test]
+    fn test_canary_rollout_waits_for_canary() {
+        let mut play = create_play(vec![
+            create_batch("canary", vec![create_test_task("a", 3)], &[], &[]),
+            create_gate("canary-health-check", StageGate::HealthCheck, "canary"),
+            create_batch(
+                "rolling-batch-0",
+                vec![create_test_task("b", 4)],
+                &[],
+                &["canary-health-check"],
+            ),
+            create_batch(
+                "rolling-batch-1",
+                vec![create_test_task("c", 2)],
+                &[],
+                &["rolling-batch-0"],
+            ),
+        ]);
+
+        CriticalPathAnalyzer::new().analyze(&mut play);
+
+        let critical_path = play.critical_path.clone().unwrap();
+        assert_eq!(critical_path.tasks, vec!["a", "b", "c"]);
+        assert_eq!(critical_path.duration, Duration::from_secs(9));
+        assert_eq!(schedule(&play, "b").earliest_start, Duration::from_secs(3));
+        assert_eq!(schedule(&play, "c").earliest_start, Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_blue_cohort_waits_for_traffic_switch() {
+        let mut play = create_play(vec![
+            create_batch("green", vec![create_test_task("a", 5)], &[], &[]),
+            create_gate("green-health-check", StageGate::HealthCheck, "green"),
+            create_gate(
+                "switch-to-green",
+                StageGate::TrafficSwitch,
+                "green-health-check",
+            ),
+            create_batch(
+                "blue",
+                vec![create_test_task("b", 5)],
+                &[],
+                &["switch-to-green"],
+            ),
+        ]);
+
+        CriticalPathAnalyzer::new().analyze(&mut play);
+
+        let critical_path = play.critical_path.clone().unwrap();
+        assert_eq!(critical_path.tasks, vec!["a", "b"]);
+        assert_eq!(critical_path.duration, Duration::from_secs(10));
+        assert_eq!(schedule(&play, "b").earliest_start, Duration::from_secs(5));
+        assert_eq!(schedule(&play, "b").slack, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_empty_play() {
+        let mut play = create_play(vec![]);
+        CriticalPathAnalyzer::new().analyze(&mut play);
+        assert!(play.critical_path.is_none());
+    }
+}
@@ -0,0 +1,450 @@
+use crate::types::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+
+/// Compares two execution plans, such as the plans before and after a
+/// playbook or inventory change.
+///
+/// Tasks are matched by play and `task_id`, batches by play and `batch_id`,
+/// and binary deployments by play and `deployment_id`. A task repeated across
+/// rolling batches is compared as one task running on all their hosts.
+pub struct PlanDiffer;
+
+impl PlanDiffer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn diff(&self, old: &ExecutionPlan, new: &ExecutionPlan) -> PlanDiff {
+        let (hosts_added, hosts_removed) = set_changes(&old.hosts, &new.hosts);
+
+        PlanDiff {
+            hosts_added,
+            hosts_removed,
+            estimated_duration: change(old.estimated_duration, new.estimated_duration),
+            tasks: self.diff_tasks(old, new),
+            batches: self.diff_batches(old, new),
+            binary_deployments: self.diff_deployments(old, new),
+        }
+    }
+
+    fn diff_tasks(&self, old: &ExecutionPlan, new: &ExecutionPlan) -> Vec<TaskDiff> {
+        let before = task_summaries(old);
+        let after = task_summaries(new);
+        let mut diffs = Vec::new();
+
+        for (key, old_task) in &before.items {
+            match after.get(key) {
+                Some(new_task) => diffs.extend(changed_task(key, old_task, new_task)),
+                None => diffs.push(task_diff(key, old_task, DiffKind::Removed)),
+            }
+        }
+        for (key, new_task) in &after.items {
+            if before.get(key).is_none() {
+                diffs.push(task_diff(key, new_task, DiffKind::Added));
+            }
+        }
+        diffs
+    }
+
+    fn diff_batches(&self, old: &ExecutionPlan, new: &ExecutionPlan) -> Vec<BatchDiff> {
+        let before = batches_by_id(old);
+        let after = batches_by_id(new);
+        let old_positions = common_positions(&before, &after);
+        let new_positions = common_positions(&after, &before);
+        let mut diffs = Vec::new();
+
+        for (key, old_batch) in &before.items {
+            let Some(new_batch) = after.get(key) else {
+                diffs.push(batch_diff(key, old_batch, DiffKind::Removed));
+                continue;
+            };
+
+            let (tasks_added, tasks_removed) =
+                set_changes(&task_ids(old_batch), &task_ids(new_batch));
+            let (hosts_added, hosts_removed) = set_changes(&old_batch.hosts, &new_batch.hosts);
+            let diff = BatchDiff {
+                play_id: key.0.to_string(),
+                batch_id: key.1.to_string(),
+                kind: DiffKind::Changed,
+                position: change(old_positions[key], new_positions[key]),
+                tasks_added,
+                tasks_removed,
+                hosts_added,
+                hosts_removed,
+            };
+            if diff.position.is_some()
+                || !diff.tasks_added.is_empty()
+                || !diff.tasks_removed.is_empty()
+                || !diff.hosts_added.is_empty()
+                || !diff.hosts_removed.is_empty()
+            {
+                diffs.push(diff);
+            }
+        }
+        for (key, new_batch) in &after.items {
+            if before.get(key).is_none() {
+                diffs.push(batch_diff(key, new_batch, DiffKind::Added));
+            }
+        }
+        diffs
+    }
+
+    fn diff_deployments(&self, old: &ExecutionPlan, new: &ExecutionPlan) -> Vec<DeploymentDiff> {
+        let before = deployments_by_id(old);
+        let after = deployments_by_id(new);
+        let old_owners = deployments_by_task(old);
+        let new_owners = deployments_by_task(new);
+        let mut diffs = Vec::new();
+
+        for (key, deployment) in &before.items {
+            let mut diff = match after.get(key) {
+                Some(current) => deployment_change(key, deployment, current),
+                None => deployment_diff(key, deployment, DiffKind::Removed),
+            };
+            diff.split_into = owners(key.0, &deployment.tasks, &new_owners);
+            if diff.split_into.len() < 2 {
+                diff.split_into.clear();
+            }
+            diffs.push(diff);
+        }
+        for (key, deployment) in &after.items {
+            let merged_from = owners(key.0, &deployment.tasks, &old_owners);
+            let merged_from = if merged_from.len() < 2 {
+                vec![]
+            } else {
+                merged_from
+            };
+
+            if before.get(key).is_some() {
+                if let Some(diff) = diffs
+                    .iter_mut()
+                    .find(|d| (d.play_id.as_str(), d.deployment_id.as_str()) == *key)
+                {
+                    diff.merged_from = merged_from;
+                }
+            } else {
+                let mut diff = deployment_diff(key, deployment, DiffKind::Added);
+                diff.merged_from = merged_from;
+                diffs.push(diff);
+            }
+        }
+
+        diffs.retain(|diff| {
+            diff.kind != DiffKind::Changed
+                || !diff.tasks_added.is_empty()
+                || !diff.tasks_removed.is_empty()
+                || !diff.hosts_added.is_empty()
+                || !diff.hosts_removed.is_empty()
+                || !diff.split_into.is_empty()
+                || !diff.merged_from.is_empty()
+        });
+        diffs
+    }
+}
+
+impl Default for PlanDiffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type Key<'a> = (&'a str, &'a str);
+
+/// Items keyed by play and ID, in the order the plan first has them
+struct Keyed<'a, T> {
+    items: Vec<(Key<'a>, T)>,
+    index: HashMap<Key<'a>, usize>,
+}
+
+impl<'a, T> Keyed<'a, T> {
+    fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    fn entry(&mut self, key: Key<'a>, init: impl FnOnce() -> T) -> &mut T {
+        let index = *self.index.entry(key).or_insert_with(|| {
+            self.items.push((key, init()));
+            self.items.len() - 1
+        });
+        &mut self.items[index].1
+    }
+
+    fn get(&self, key: &Key<'a>) -> Option<&T> {
+        self.index.get(key).map(|&index| &self.items[index].1)
+    }
+}
+
+/// A task as it appears across the batches of its play
+struct TaskSummary<'a> {
+    task: &'a TaskPlan,
+    hosts: Vec<String>,
+    batches: Vec<String>,
+}
+
+fn task_summaries(plan: &ExecutionPlan) -> Keyed<'_, TaskSummary<'_>> {
+    let mut tasks = Keyed::new();
+    for play in &plan.plays {
+        for batch in &play.batches {
+            for task in &batch.tasks {
+                let summary = tasks.entry((&play.play_id, &task.task_id), || TaskSummary {
+                    task,
+                    hosts: vec![],
+                    batches: vec![],
+                });
+                extend_unique(&mut summary.hosts, &task.hosts);
+                extend_unique(&mut summary.batches, std::slice::from_ref(&batch.batch_id));
+            }
+        }
+    }
+    tasks
+}
+
+fn task_diff(key: &Key, summary: &TaskSummary, kind: DiffKind) -> TaskDiff {
+    let (hosts_added, hosts_removed) = match kind {
+        DiffKind::Removed => (vec![], summary.hosts.clone()),
+        _ => (summary.hosts.clone(), vec![]),
+    };
+    TaskDiff {
+        play_id: key.0.to_string(),
+        task_id: key.1.to_string(),
+        name: summary.task.name.clone(),
+        kind,
+        hosts_added,
+        hosts_removed,
+        module: None,
+        args: vec![],
+        risk_level: None,
+        estimated_duration: None,
+        batches: None,
+    }
+}
+
+fn changed_task(key: &Key, old: &TaskSummary, new: &TaskSummary) -> Option<TaskDiff> {
+    let (hosts_added, hosts_removed) = set_changes(&old.hosts, &new.hosts);
+    let diff = TaskDiff {
+        play_id: key.0.to_string(),
+        task_id: key.1.to_string(),
+        name: new.task.name.clone(),
+        kind: DiffKind::Changed,
+        hosts_added,
+        hosts_removed,
+        module: change(old.task.module.clone(), new.task.module.clone()),
+        args: arg_changes(old.task, new.task),
+        risk_level: change(old.task.risk_level.clone(), new.task.risk_level.clone()),
+        estimated_duration: change(old.task.estimated_duration, new.task.estimated_duration),
+        batches: change(old.batches.clone(), new.batches.clone()),
+    };
+
+    let changed = !diff.hosts_added.is_empty()
+        || !diff.hosts_removed.is_empty()
+        || diff.module.is_some()
+        || !diff.args.is_empty()
+        || diff.risk_level.is_some()
+        || diff.estimated_duration.is_some()
+        || diff.batches.is_some();
+    changed.then_some(diff)
+}
+
+fn arg_changes(old: &TaskPlan, new: &TaskPlan) -> Vec<ArgChange> {
+    let keys: BTreeSet<&String> = old.args.keys().chain(new.args.keys()).collect();
+    keys.into_iter()
+        .filter(|key| old.args.get(*key) != new.args.get(*key))
+        .map(|key| ArgChange {
+            key: key.clone(),
+            old: old.args.get(key).cloned(),
+            new: new.args.get(key).cloned(),
+        })
+        .collect()
+}
+
+fn batches_by_id(plan: &ExecutionPlan) -> Keyed<'_, &ExecutionBatch> {
+    let mut batches = Keyed::new();
+    for play in &plan.plays {
+        for batch in &play.batches {
+            batches.entry((&play.play_id, &batch.batch_id), || batch);
+        }
+    }
+    batches
+}
+
+/// Position of each batch among its play's batches that `other` also has,
+/// so adding or removing a batch doesn't count as reordering the rest
+fn common_positions<'a>(
+    batches: &Keyed<'a, &ExecutionBatch>,
+    other: &Keyed<'a, &ExecutionBatch>,
+) -> HashMap<Key<'a>, usize> {
+    let mut next: HashMap<&str, usize> = HashMap::new();
+    batches
+        .items
+        .iter()
+        .filter(|(key, _)| other.get(key).is_some())
+        .map(|(key, _)| {
+            let position = next.entry(key.0).or_default();
+            *position += 1;
+            (*key, *position - 1)
+        })
+        .collect()
+}
+
+fn task_ids(batch: &ExecutionBatch) -> Vec<String> {
+    batch
+        .tasks
+        .iter()
+        .map(|task| task.task_id.clone())
+        .collect()
+}
+
+fn batch_diff(key: &Key, batch: &ExecutionBatch, kind: DiffKind) -> BatchDiff {
+    let (tasks, hosts) = (task_ids(batch), batch.hosts.clone());
+    let (tasks_added, tasks_removed, hosts_added, hosts_removed) = match kind {
+        DiffKind::Removed => (vec![], tasks, vec![], hosts),
+        _ => (tasks, vec![], hosts, vec![]),
+    };
+    BatchDiff {
+        play_id: key.0.to_string(),
+        batch_id: key.1.to_string(),
+        kind,
+        position: None,
+        tasks_added,
+        tasks_removed,
+        hosts_added,
+        hosts_removed,
+    }
+}
+
+fn deployments_by_id(plan: &ExecutionPlan) -> Keyed<'_, &BinaryDeployment> {
+    let mut deployments = Keyed::new();
+    for deployment in &plan.binary_deployments {
+        deployments.entry((&deployment.play_id, &deployment.deployment_id), || {
+            deployment
+        });
+    }
+    deployments
+}
+
+/// The deployments running each task, keyed by play and task ID
+fn deployments_by_task(plan: &ExecutionPlan) -> HashMap<Key<'_>, Vec<&str>> {
+    let mut owners: HashMap<Key, Vec<&str>> = HashMap::new();
+    for deployment in &plan.binary_deployments {
+        for task in &deployment.tasks {
+            owners
+                .entry((&deployment.play_id, task))
+                .or_default()
+                .push(&deployment.deployment_id);
+        }
+    }
+    owners
+}
+
+/// The deployments of the play running any of `tasks`, in the order first
+/// found
+fn owners(play_id: &str, tasks: &[String], owners: &HashMap<Key, Vec<&str>>) -> Vec<String> {
+    let mut found = Vec::new();
+    for task in tasks {
+        for owner in owners.get(&(play_id, task.as_str())).into_iter().flatten() {
+            extend_unique(&mut found, &[owner.to_string()]);
+        }
+    }
+    found
+}
+
+fn deployment_diff(key: &Key, deployment: &BinaryDeployment, kind: DiffKind) -> DeploymentDiff {
+    let (tasks, hosts) = (deployment.tasks.clone(), deployment.target_hosts.clone());
+    let (tasks_added, tasks_removed, hosts_added, hosts_removed) = match kind {
+        DiffKind::Removed => (vec![], tasks, vec![], hosts),
+        _ => (tasks, vec![], hosts, vec![]),
+    };
+    DeploymentDiff {
+        play_id: key.0.to_string(),
+        deployment_id: key.1.to_string(),
+        kind,
+        tasks_added,
+        tasks_removed,
+        hosts_added,
+        hosts_removed,
+        split_into: vec![],
+        merged_from: vec![],
+    }
+}
+
+fn deployment_change(key: &Key, old: &BinaryDeployment, new: &BinaryDeployment) -> DeploymentDiff {
+    let (tasks_added, tasks_removed) = set_changes(&old.tasks, &new.tasks);
+    let (hosts_added, hosts_removed) = set_changes(&old.target_hosts, &new.target_hosts);
+    DeploymentDiff {
+        play_id: key.0.to_string(),
+        deployment_id: key.1.to_string(),
+        kind: DiffKind::Changed,
+        tasks_added,
+        tasks_removed,
+        hosts_added,
+        hosts_removed,
+        split_into: vec![],
+        merged_from: vec![],
+    }
+}
+
+fn change<T: PartialEq>(old: T, new: T) -> Option<Change<T>> {
+    (old != new).then_some(Change { old, new })
+}
+
+/// Items only in `new` and items only in `old`, each in order and once
+fn set_changes<T: Clone + Eq + Hash>(old: &[T], new: &[T]) -> (Vec<T>, Vec<T>) {
+    let only_in = |items: &[T], other: &[T]| {
+        let other: HashSet<&T> = other.iter().collect();
+        let mut found = Vec::new();
+        for item in items.iter().filter(|item| !other.contains(item)) {
+            extend_unique(&mut found, std::slice::from_ref(item));
+        }
+        found
+    };
+    (only_in(new, old), only_in(old, new))
+}
+
+fn extend_unique<T: Clone + PartialEq>(items: &mut Vec<T>, more: &[T]) {
+    for item in more {
+        if !items.contains(item) {
+            items.push(item.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_set_changes() {
+        let (added, removed) = set_changes(
+            &strings(&["web1", "web2", "web2", "db1"]),
+            &strings(&["web2", "web3", "web3", "db1"]),
+        );
+        assert_eq!(added, strings(&["web3"]));
+        assert_eq!(removed, strings(&["web1"]));
+    }
+
+    #[test]
+    fn test_owners_in_task_order() {
+        let owners_by_task = HashMap::from([
+            (("play-0", "a"), vec!["group_1"]),
+            (("play-0", "b"), vec!["group_0"]),
+            (("play-0", "c"), vec!["group_1"]),
+            (("play-1", "d"), vec!["group_0"]),
+        ]);
+        assert_eq!(
+            owners("play-0", &strings(&["a", "b", "c", "d"]), &owners_by_task),
+            strings(&["group_1", "group_0"])
+        );
+        assert_eq!(
+            owners("play-1", &strings(&["a", "d"]), &owners_by_task),
+            strings(&["group_0"])
+        );
+    }
+}
@@ -8,9 +8,18 @@ pub enum PlanError {
     #[error("Invalid host pattern '{pattern}': {reason}")]
     InvalidHostPattern { pattern: String, reason: String },
 
+    #[error("Circular group nesting detected in inventory: {cycle}")]
+    InventoryGroupCycle { cycle: String },
+
     #[error("Unknown task '{task_id}' referenced in dependency")]
     UnknownTaskDependency { task_id: String },
 
+    #[error("Invalid blue/green cohort '{group}': {reason}")]
+    InvalidCohort { group: String, reason: String },
+
+    #[error("Invalid host_priority for host '{host}': {value}")]
+    InvalidHostPriority { host: String, value: String },
+
     #[error("Conflicting execution strategies: {conflict}")]
     StrategyConflict { conflict: String },
 
@@ -20,6 +29,9 @@ pub enum PlanError {
     #[error("Planning timeout exceeded: {timeout_secs}s")]
     PlanningTimeout { timeout_secs: u64 },
 
+    #[error("Invalid condition expression '{expression}': {reason}")]
+    InvalidCondition { expression: String, reason: String },
+
     #[error("Invalid tag expression: {expression}")]
     InvalidTagExpression { expression: String },
 
@@ -38,6 +50,39 @@ pub enum PlanError {
     #[error("Cross-compilation failed for target {target}: {reason}")]
     CrossCompilationFailed { target: String, reason: String },
 
+    #[error("Plan has no digest")]
+    MissingPlanDigest,
+
+    #[error("Plan digest mismatch: recorded {expected}, computed {actual}")]
+    PlanDigestMismatch { expected: String, actual: String },
+
+    #[error("Invalid redaction pattern '{pattern}': {reason}")]
+    InvalidRedactPattern { pattern: String, reason: String },
+
+    #[error("Failed to seal secrets: {reason}")]
+    SecretSealing { reason: String },
+
+    #[error("Invalid key: {reason}")]
+    InvalidKey { reason: String },
+
+    #[error("Plan is not signed")]
+    UnsignedPlan,
+
+    #[error("Plan was signed by key {actual}, not the trusted key {expected}")]
+    SignerMismatch { expected: String, actual: String },
+
+    #[error("Plan signature does not match: the plan changed after it was signed")]
+    InvalidSignature,
+
+    #[error("Invalid plan file: {reason}")]
+    InvalidPlanFormat { reason: String },
+
+    #[error("Unsupported plan version {version}")]
+    UnsupportedPlanVersion { version: String },
+
+    #[error("Plan schema version {version} is newer than the supported version {supported}")]
+    UnsupportedSchemaVersion { version: u32, supported: u32 },
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -50,8 +50,11 @@ impl TaskEstimator {
         // Adjust based on task complexity
         let complexity_multiplier = self.calculate_complexity_multiplier(task);
 
+        // Each loop item is a separate module invocation
+        let iterations = self.loop_iterations(task) as f64;
+
         Some(Duration::from_nanos(
-            (base_duration.as_nanos() as f64 * complexity_multiplier) as u64,
+            (base_duration.as_nanos() as f64 * complexity_multiplier * iterations) as u64,
         ))
     }
 
@@ -130,7 +133,7 @@ impl TaskEstimator {
         }
     }
 
-    fn estimate_batch_duration(&self, batch: &ExecutionBatch) -> Result<Duration, PlanError> {
+    pub fn estimate_batch_duration(&self, batch: &ExecutionBatch) -> Result<Duration, PlanError> {
         if batch.tasks.is_empty() {
             return Ok(Duration::ZERO);
         }
@@ -210,6 +213,16 @@ impl TaskEstimator {
             multiplier *= 1.1;
         }
 
+        // Result conditions are evaluated after every run
+        if task.changed_when.is_some() || task.failed_when.is_some() {
+            multiplier *= 1.05;
+        }
+
+        // Delegation opens a connection to another host
+        if task.delegate_to.is_some() {
+            multiplier *= 1.2;
+        }
+
         // Module-specific adjustments
         match task.module.as_str() {
             "shell" | "command" | "raw" => {
@@ -224,17 +237,25 @@ impl TaskEstimator {
                     }
                 }
             }
-            "copy" | "template" => {
+            "copy" | "template" if task.args.contains_key("backup") => {
                 // File size affects copy time (simplified estimation)
-                if task.args.contains_key("backup") {
-                    multiplier *= 1.3;
-                }
+                multiplier *= 1.3;
             }
             _ => {}
         }
 
         multiplier
     }
+
+    /// Number of loop iterations. A loop over a template expression cannot be
+    /// counted before execution and is estimated as a single iteration.
+    fn loop_iterations(&self, task: &ParsedTask) -> usize {
+        match &task.loop_items {
+            Some(serde_json::Value::Array(items)) => items.len(),
+            Some(serde_json::Value::Object(items)) => items.len(),
+            _ => 1,
+        }
+    }
 }
 
 impl Default for TaskEstimator {
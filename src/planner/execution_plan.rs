@@ -1,7 +1,8 @@
 use crate::planner::*;
 use crate::types::*;
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::Duration;
 
 pub struct ExecutionPlanner {
@@ -12,6 +13,8 @@ pub struct ExecutionPlanner {
     task_estimator: TaskEstimator,
     binary_planner: BinaryDeploymentPlanner,
     binary_threshold: u32,
+    expand_loops: bool,
+    secret_sealer: Option<SecretSealer>,
 }
 
 impl ExecutionPlanner {
@@ -24,6 +27,8 @@ impl ExecutionPlanner {
             task_estimator: TaskEstimator::new(),
             binary_planner: BinaryDeploymentPlanner::new(),
             binary_threshold: 5,
+            expand_loops: false,
+            secret_sealer: None,
         }
     }
 
@@ -47,11 +52,28 @@ impl ExecutionPlanner {
         self
     }
 
+    pub fn with_loop_expansion(mut self, enabled: bool) -> Self {
+        self.expand_loops = enabled;
+        self
+    }
+
     pub fn with_binary_threshold(mut self, threshold: u32) -> Self {
         self.binary_threshold = threshold;
         self
     }
 
+    /// Seal redacted secrets into the plan for the executor
+    pub fn with_secret_sealer(mut self, sealer: SecretSealer) -> Self {
+        self.secret_sealer = Some(sealer);
+        self
+    }
+
+    /// Directory of the playbook, used to find files embedded in binaries
+    pub fn with_playbook_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.binary_planner = self.binary_planner.with_base_dir(dir);
+        self
+    }
+
     pub fn plan_execution(
         &self,
         playbook: &ParsedPlaybook,
@@ -60,59 +82,138 @@ impl ExecutionPlanner {
     ) -> Result<ExecutionPlan, PlanError> {
         let start_time = std::time::Instant::now();
 
+        // Reject inventories whose groups are nested inside themselves
+        InventoryResolver::new(inventory).validate()?;
+
         // Apply host filtering
-        let filtered_hosts = self.filter_hosts(&inventory.hosts, &options.limit)?;
+        let filtered_hosts = self.filter_hosts(inventory, &options.limit)?;
+
+        let playbook_hash = self.calculate_playbook_hash(playbook)?;
+        let inventory_hash = self.calculate_inventory_hash(inventory)?;
+
+        // Seed unseeded shuffles once and record the seed, so the host
+        // order can be reproduced
+        let shuffle_seed = self.shuffle_seed(options, &playbook_hash, &inventory_hash);
+        let host_order = options.host_order.with_seed(shuffle_seed);
+        let orderer = HostOrderer::new(inventory);
+        let redactor = SecretRedactor::new().with_patterns(&options.redact_keys)?;
+        let mut secrets = BTreeMap::new();
 
         // Plan each play
         let mut plays = Vec::new();
         let mut all_binary_deployments = Vec::new();
+        let mut all_filtered_tasks = Vec::new();
         let mut total_tasks = 0;
 
         for (play_index, parsed_play) in playbook.plays.iter().enumerate() {
-            let play_hosts = self.resolve_play_hosts(parsed_play, &filtered_hosts, inventory)?;
+            let play_id = format!("play-{play_index}");
+            let play_order = parsed_play
+                .order
+                .map_or(host_order, |order| order.with_seed(shuffle_seed));
+            let play_hosts = orderer.order(
+                &self.resolve_play_hosts(parsed_play, &filtered_hosts, inventory)?,
+                &play_order,
+            )?;
 
             // Filter tasks by tags
             let filtered_tasks = self.filter_tasks_by_tags(&parsed_play.tasks, options)?;
-            total_tasks += filtered_tasks.len();
+
+            // Expand static loops so each item is analyzed as its own task
+            let expanded_tasks = self.expand_task_loops(&filtered_tasks);
+            let parsed_tasks: Vec<ParsedTask> = expanded_tasks
+                .iter()
+                .map(|(task, _)| task.clone())
+                .collect();
 
             // Analyze dependencies
-            let _dependency_graph = self.analyze_dependencies(&filtered_tasks)?;
+            let dependency_graph = self.analyze_dependencies(&parsed_tasks)?;
 
             // Convert parsed tasks to task plans
-            let mut task_plans = self.create_task_plans(&filtered_tasks, &play_hosts)?;
+            let mut task_plans = self.create_task_plans(&expanded_tasks, &play_hosts)?;
+
+            // Keep secrets out of the plan, binaries and skipped tasks included
+            for task in &mut task_plans {
+                redactor.redact_task(&play_id, task, &mut secrets);
+            }
+
+            // Drop tasks from hosts whose facts rule them out
+            all_filtered_tasks.extend(self.filter_tasks_by_facts(
+                &mut task_plans,
+                &play_id,
+                inventory,
+                options,
+            ));
+            total_tasks += task_plans.len();
 
             // Optimize execution order if enabled
             if self.optimize {
                 task_plans = self.optimize_execution_order(&task_plans)?;
             }
 
-            // Create execution batches based on strategy
-            let batches =
-                self.create_execution_batches(&task_plans, &options.strategy, options.serial)?;
-
             // Plan binary deployments for this play
-            let binary_deployments = if !options.force_ssh {
+            let mut binary_deployments = if !options.force_ssh {
                 self.plan_binary_deployments_with_inventory(&task_plans, &play_hosts, inventory)?
             } else {
                 Vec::new()
             };
+            for deployment in &mut binary_deployments {
+                deployment.play_id = play_id.clone();
+            }
+
+            // Create execution batches based on strategy
+            let strategy = self.play_strategy(parsed_play, options)?;
+            let batches = StrategyPlanner::new()
+                .with_forks(options.forks)
+                .with_cohorts(self.resolve_cohorts(&strategy, inventory)?)
+                .with_failure_domains(self.resolve_failure_domains(
+                    &strategy,
+                    &play_hosts,
+                    inventory,
+                )?)
+                .plan_strategy(
+                    &strategy,
+                    &task_plans,
+                    &play_hosts,
+                    &dependency_graph,
+                    &binary_deployments,
+                )?;
 
             all_binary_deployments.extend(binary_deployments);
 
-            // Create handlers plans
-            let handler_plans = self.create_handler_plans(&parsed_play.handlers)?;
+            // Create handlers plans and place them at their flush points
+            let mut handler_plans = self.create_handler_plans(&parsed_play.handlers)?;
+            for handler in &mut handler_plans {
+                redactor.redact_handler(&play_id, handler, &mut secrets);
+            }
+            HandlerScheduler::new().schedule(&task_plans, &mut handler_plans);
 
-            let play_plan = PlayPlan {
-                play_id: format!("play-{play_index}"),
+            let mut play_plan = PlayPlan {
+                play_id,
                 name: parsed_play.name.clone(),
-                strategy: options.strategy.clone(),
-                serial: options.serial,
+                serial: match &strategy {
+                    ExecutionStrategy::Rolling { batch_sizes, .. }
+                    | ExecutionStrategy::Canary { batch_sizes, .. } => Some(batch_sizes.clone()),
+                    _ => None,
+                },
+                strategy,
+                max_fail_percentage: parsed_play
+                    .max_fail_percentage
+                    .or(options.max_fail_percentage),
+                any_errors_fatal: parsed_play.any_errors_fatal || options.any_errors_fatal,
+                host_order: play_order,
                 hosts: play_hosts,
                 batches,
                 handlers: handler_plans,
                 estimated_duration: None, // Will be calculated later
+                critical_path: None,
             };
 
+            // Gate each batch on the play's failure policy
+            FailurePolicyPlanner::new().apply(&mut play_plan);
+
+            // Find the chain of tasks that bounds the play's duration
+            CriticalPathAnalyzer::new().analyze(&mut play_plan);
+
             plays.push(play_plan);
         }
 
@@ -129,18 +230,39 @@ impl ExecutionPlanner {
             None
         };
 
+        // Lay out each host's tasks against the fork limit
+        let host_timelines = TimelineBuilder::new(options.forks).build(&plays);
+
+        let sealed_secrets = match &self.secret_sealer {
+            Some(sealer) if !secrets.is_empty() => Some(sealer.seal(&secrets)?),
+            _ => None,
+        };
+        if sealed_secrets.is_none() && !secrets.is_empty() {
+            tracing::warn!(
+                "Redacted {} secrets without sealing them for the executor",
+                secrets.len()
+            );
+        }
+
         // Calculate scores
         let parallelism_score = self.calculate_parallelism_score(&plays);
         let network_efficiency_score =
             self.calculate_network_efficiency_score(&plays, &all_binary_deployments);
 
-        let execution_plan = ExecutionPlan {
+        let mut execution_plan = ExecutionPlan {
             metadata: PlanMetadata {
-                created_at: Utc::now(),
+                created_at: if options.reproducible {
+                    DateTime::UNIX_EPOCH
+                } else {
+                    Utc::now()
+                },
                 rustle_plan_version: env!("CARGO_PKG_VERSION").to_string(),
-                playbook_hash: self.calculate_playbook_hash(playbook)?,
-                inventory_hash: self.calculate_inventory_hash(inventory)?,
+                schema_version: PLAN_SCHEMA_VERSION,
+                playbook_hash,
+                inventory_hash,
                 planning_options: options.clone(),
+                host_order,
+                signature: None,
             },
             plays,
             binary_deployments: all_binary_deployments,
@@ -150,7 +272,13 @@ impl ExecutionPlanner {
             parallelism_score,
             network_efficiency_score,
             hosts: filtered_hosts,
+            filtered_tasks: all_filtered_tasks,
+            host_timelines,
+            redacted_secrets: secrets.into_keys().collect(),
+            sealed_secrets,
+            plan_digest: None,
         };
+        PlanIntegrity::new().seal(&mut execution_plan)?;
 
         let planning_duration = start_time.elapsed();
         tracing::info!(
@@ -165,53 +293,166 @@ impl ExecutionPlanner {
 
     fn filter_hosts(
         &self,
-        hosts: &[String],
+        inventory: &ParsedInventory,
         limit: &Option<String>,
     ) -> Result<Vec<String>, PlanError> {
+        let resolver = HostPatternResolver::new(inventory);
+
         if let Some(pattern) = limit {
-            // Simple pattern matching for now
-            // In a real implementation, this would support complex patterns
-            if pattern == "all" {
-                Ok(hosts.to_vec())
-            } else {
-                let filtered: Vec<String> = hosts
-                    .iter()
-                    .filter(|host| host.contains(pattern))
-                    .cloned()
-                    .collect();
-
-                if filtered.is_empty() {
-                    return Err(PlanError::InvalidHostPattern {
-                        pattern: pattern.clone(),
-                        reason: "No hosts match the pattern".to_string(),
-                    });
-                }
+            let filtered = resolver.resolve_limit(pattern)?;
 
-                Ok(filtered)
+            if filtered.is_empty() {
+                return Err(PlanError::InvalidHostPattern {
+                    pattern: pattern.clone(),
+                    reason: "No hosts match the pattern".to_string(),
+                });
             }
+
+            Ok(filtered)
         } else {
-            Ok(hosts.to_vec())
+            Ok(resolver.all_hosts().to_vec())
+        }
+    }
+
+    /// Seed for shuffles that were not given one. Reproducible plans derive
+    /// it from the input, so the same input always shuffles the same way.
+    fn shuffle_seed(
+        &self,
+        options: &PlanningOptions,
+        playbook_hash: &str,
+        inventory_hash: &str,
+    ) -> u64 {
+        if options.reproducible {
+            let digest = sha256_digest(format!("{playbook_hash}:{inventory_hash}").as_bytes());
+            // 16 hex digits after the "sha256:" prefix make a u64
+            return u64::from_str_radix(&digest[7..23], 16).unwrap_or_default();
+        }
+
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+    }
+
+    /// The strategy a play runs with. A play's own `serial` turns it into a
+    /// rolling play whatever the base strategy, as in Ansible; the `serial`
+    /// option only resizes rolling plays and the rollout after a canary.
+    /// Blue/green plays batch by cohort, so they can't take a `serial`.
+    fn play_strategy(
+        &self,
+        play: &ParsedPlay,
+        options: &PlanningOptions,
+    ) -> Result<ExecutionStrategy, PlanError> {
+        let serial = play.serial.clone().or_else(|| options.serial.clone());
+        let strategy = match (&options.strategy, serial) {
+            (ExecutionStrategy::Rolling { topology, .. }, Some(batch_sizes)) => {
+                ExecutionStrategy::Rolling {
+                    batch_sizes,
+                    topology: topology.clone(),
+                }
+            }
+            (
+                ExecutionStrategy::Canary {
+                    canary_size,
+                    topology,
+                    ..
+                },
+                Some(batch_sizes),
+            ) => ExecutionStrategy::Canary {
+                canary_size: *canary_size,
+                batch_sizes,
+                topology: topology.clone(),
+            },
+            (ExecutionStrategy::BlueGreen { .. }, _) if play.serial.is_some() => {
+                return Err(PlanError::StrategyConflict {
+                    conflict: format!(
+                        "play '{}' sets serial, which the blue-green strategy can't honour",
+                        play.name
+                    ),
+                });
+            }
+            // Only rolling and canary strategies carry a topology to keep
+            (_, Some(batch_sizes)) if play.serial.is_some() => ExecutionStrategy::Rolling {
+                batch_sizes,
+                topology: None,
+            },
+            (strategy, _) => strategy.clone(),
+        };
+        Ok(strategy)
+    }
+
+    /// Hosts of the blue and green inventory groups of a blue/green strategy
+    fn resolve_cohorts(
+        &self,
+        strategy: &ExecutionStrategy,
+        inventory: &ParsedInventory,
+    ) -> Result<HashMap<String, Vec<String>>, PlanError> {
+        let mut cohorts = HashMap::new();
+        if let ExecutionStrategy::BlueGreen { blue, green } = strategy {
+            let resolver = InventoryResolver::new(inventory);
+            for group in [blue, green] {
+                if resolver.has_group(group) {
+                    cohorts.insert(group.clone(), resolver.group_hosts(group)?);
+                }
+            }
+        }
+        Ok(cohorts)
+    }
+
+    /// Failure domain of each play host for a topology-aware rolling or
+    /// canary strategy
+    fn resolve_failure_domains(
+        &self,
+        strategy: &ExecutionStrategy,
+        hosts: &[String],
+        inventory: &ParsedInventory,
+    ) -> Result<HashMap<String, String>, PlanError> {
+        let mut domains = HashMap::new();
+        if let ExecutionStrategy::Rolling {
+            topology: Some(topology),
+            ..
+        }
+        | ExecutionStrategy::Canary {
+            topology: Some(topology),
+            ..
+        } = strategy
+        {
+            let resolver = InventoryResolver::new(inventory);
+            for host in hosts {
+                let vars = resolver.host_variables(host)?;
+                if let Some(value) = vars.get(&topology.domain_var) {
+                    let domain = match value {
+                        serde_json::Value::String(domain) => domain.clone(),
+                        other => other.to_string(),
+                    };
+                    domains.insert(host.clone(), domain);
+                }
+            }
         }
+        Ok(domains)
     }
 
     fn resolve_play_hosts(
         &self,
         play: &ParsedPlay,
         available_hosts: &[String],
-        _inventory: &ParsedInventory,
+        inventory: &ParsedInventory,
     ) -> Result<Vec<String>, PlanError> {
-        // Simple host resolution - in a real implementation this would handle groups
-        if play.hosts.contains(&"all".to_string()) {
-            Ok(available_hosts.to_vec())
-        } else {
-            let resolved: Vec<String> = play
-                .hosts
-                .iter()
-                .filter(|host| available_hosts.contains(host))
-                .cloned()
-                .collect();
-            Ok(resolved)
+        let resolved = HostPatternResolver::new(inventory).resolve_all(&play.hosts)?;
+
+        let play_hosts: Vec<String> = resolved
+            .into_iter()
+            .filter(|host| available_hosts.contains(host))
+            .collect();
+
+        if play_hosts.is_empty() {
+            tracing::warn!(
+                "Play '{}' matched no hosts for pattern '{}'",
+                play.name,
+                play.hosts.join(",")
+            );
         }
+
+        Ok(play_hosts)
     }
 
     fn filter_tasks_by_tags(
@@ -244,15 +485,122 @@ impl ExecutionPlanner {
         Ok(filtered_tasks)
     }
 
+    fn filter_tasks_by_facts(
+        &self,
+        task_plans: &mut Vec<TaskPlan>,
+        play_id: &str,
+        inventory: &ParsedInventory,
+        options: &PlanningOptions,
+    ) -> Vec<FilteredTask> {
+        let evaluator = ConditionEvaluator::new();
+        let mut filtered = Vec::new();
+        let mut removed_tasks = HashSet::new();
+
+        for task in task_plans.iter_mut() {
+            let expressions: Vec<String> = task
+                .conditions
+                .iter()
+                .filter_map(|condition| match condition {
+                    ExecutionCondition::When { expression } => Some(expression.clone()),
+                    _ => None,
+                })
+                .collect();
+
+            if expressions.is_empty() || task.hosts.is_empty() {
+                continue;
+            }
+
+            task.hosts.retain(|host| {
+                // Hosts without gathered facts cannot be decided at plan time
+                let Some(facts) = inventory.host_facts.get(host) else {
+                    return true;
+                };
+
+                // Only facts are trusted here; play and inventory vars can still
+                // be overridden at runtime, so they stay unknown
+                let context =
+                    ExecutionContext::from_facts(host, facts, &options.tags, options.check_mode);
+
+                for expression in &expressions {
+                    match evaluator.evaluate_expression(expression, &context) {
+                        Ok(ConditionResult::False) => {
+                            filtered.push(FilteredTask {
+                                task_id: task.task_id.clone(),
+                                task_name: task.name.clone(),
+                                play_id: play_id.to_string(),
+                                host: host.clone(),
+                                condition: expression.clone(),
+                                reason: Self::describe_fact_mismatch(expression, &context),
+                            });
+                            return false;
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            tracing::warn!("Keeping task '{}' on {}: {}", task.task_id, host, e);
+                        }
+                    }
+                }
+
+                true
+            });
+
+            if task.hosts.is_empty() {
+                removed_tasks.insert(task.task_id.clone());
+            }
+        }
+
+        // Tasks that no longer run anywhere are dropped, and so are the
+        // dependencies pointing at them since a skipped task never blocks
+        if !removed_tasks.is_empty() {
+            task_plans.retain(|task| !removed_tasks.contains(&task.task_id));
+            for task in task_plans.iter_mut() {
+                task.dependencies.retain(|dep| !removed_tasks.contains(dep));
+            }
+        }
+
+        if !filtered.is_empty() {
+            tracing::info!(
+                "Filtered {} task/host pairs in {} due to host facts",
+                filtered.len(),
+                play_id
+            );
+        }
+
+        filtered
+    }
+
+    fn describe_fact_mismatch(expression: &str, context: &ExecutionContext) -> String {
+        let facts: Vec<String> = Expression::parse(expression)
+            .map(|parsed| parsed.variables())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|name| context.lookup(&name).map(|value| format!("{name}={value}")))
+            .collect();
+
+        if facts.is_empty() {
+            "Condition is false for host facts".to_string()
+        } else {
+            format!("Condition is false for host facts ({})", facts.join(", "))
+        }
+    }
+
+    fn expand_task_loops(&self, tasks: &[ParsedTask]) -> Vec<(ParsedTask, Option<LoopExpansion>)> {
+        if self.expand_loops {
+            LoopExpander::new().expand(tasks)
+        } else {
+            tasks.iter().map(|task| (task.clone(), None)).collect()
+        }
+    }
+
     fn create_task_plans(
         &self,
-        tasks: &[ParsedTask],
+        tasks: &[(ParsedTask, Option<LoopExpansion>)],
         hosts: &[String],
     ) -> Result<Vec<TaskPlan>, PlanError> {
         let mut task_plans = Vec::new();
 
-        for (index, task) in tasks.iter().enumerate() {
-            let risk_level = self.assess_task_risk(&task.module);
+        for (index, (task, loop_expansion)) in tasks.iter().enumerate() {
+            let risk_level = self.assess_task_risk(task);
             let can_run_parallel = self.can_task_run_parallel(task, &risk_level);
 
             let task_plan = TaskPlan {
@@ -269,6 +617,15 @@ impl ExecutionPlanner {
                 can_run_parallel,
                 estimated_duration: self.task_estimator.estimate_task_duration(task),
                 risk_level,
+                vars: task.vars.clone(),
+                loop_items: task.loop_items.clone(),
+                changed_when: task.changed_when.clone(),
+                failed_when: task.failed_when.clone(),
+                ignore_errors: task.ignore_errors,
+                no_log: task.no_log,
+                delegate_to: task.delegate_to.clone(),
+                loop_expansion: loop_expansion.clone(),
+                schedule: None,
             };
 
             task_plans.push(task_plan);
@@ -302,17 +659,44 @@ impl ExecutionPlanner {
         Ok(conditions)
     }
 
-    fn assess_task_risk(&self, module: &str) -> RiskLevel {
-        match module {
+    fn assess_task_risk(&self, task: &ParsedTask) -> RiskLevel {
+        let risk_level = match task.module.as_str() {
             "debug" | "assert" | "fail" | "meta" => RiskLevel::Low,
             "copy" | "template" | "file" | "lineinfile" => RiskLevel::Medium,
             "service" | "systemd" | "package" | "yum" | "apt" => RiskLevel::High,
-            "shell" | "command" | "raw" => RiskLevel::Critical,
+            "shell" | "command" | "raw" => {
+                // `changed_when: false` is the idiom for a read-only command
+                if task.changed_when.as_deref().map(str::trim) == Some("false") {
+                    RiskLevel::Medium
+                } else {
+                    RiskLevel::Critical
+                }
+            }
             _ => RiskLevel::Medium,
+        };
+
+        // A failure of an ignore_errors task can't stop the rollout
+        let risk_level = match risk_level {
+            RiskLevel::Critical if task.ignore_errors => RiskLevel::High,
+            RiskLevel::High if task.ignore_errors => RiskLevel::Medium,
+            RiskLevel::Medium if task.ignore_errors => RiskLevel::Low,
+            risk_level => risk_level,
+        };
+
+        // Delegated tasks act on a host outside the play's host list
+        match (&task.delegate_to, risk_level) {
+            (Some(delegate), RiskLevel::Low) if !is_local_host(delegate) => RiskLevel::Medium,
+            (Some(delegate), RiskLevel::Medium) if !is_local_host(delegate) => RiskLevel::High,
+            (_, risk_level) => risk_level,
         }
     }
 
     fn can_task_run_parallel(&self, task: &ParsedTask, risk_level: &RiskLevel) -> bool {
+        // Every host of a delegated task runs it on the same delegate
+        if task.delegate_to.is_some() && *risk_level != RiskLevel::Low {
+            return false;
+        }
+
         // Tasks that modify the same resources or have high risk generally can't run in parallel
         match risk_level {
             RiskLevel::Critical => false,
@@ -324,138 +708,6 @@ impl ExecutionPlanner {
         }
     }
 
-    fn create_execution_batches(
-        &self,
-        tasks: &[TaskPlan],
-        strategy: &ExecutionStrategy,
-        serial: Option<u32>,
-    ) -> Result<Vec<ExecutionBatch>, PlanError> {
-        match strategy {
-            ExecutionStrategy::Linear => {
-                // All tasks in sequence, one batch per task
-                let batches: Vec<ExecutionBatch> = tasks
-                    .iter()
-                    .enumerate()
-                    .map(|(index, task)| ExecutionBatch {
-                        batch_id: format!("batch-{index}"),
-                        hosts: task.hosts.clone(),
-                        tasks: vec![task.clone()],
-                        parallel_groups: Vec::new(),
-                        dependencies: if index > 0 {
-                            vec![format!("batch-{}", index - 1)]
-                        } else {
-                            Vec::new()
-                        },
-                        estimated_duration: task.estimated_duration,
-                    })
-                    .collect();
-                Ok(batches)
-            }
-            ExecutionStrategy::Free => {
-                // All tasks that can run in parallel
-                let parallel_tasks: Vec<TaskPlan> = tasks
-                    .iter()
-                    .filter(|task| task.can_run_parallel)
-                    .cloned()
-                    .collect();
-
-                let sequential_tasks: Vec<TaskPlan> = tasks
-                    .iter()
-                    .filter(|task| !task.can_run_parallel)
-                    .cloned()
-                    .collect();
-
-                let mut batches = Vec::new();
-
-                // Add parallel batch if any
-                if !parallel_tasks.is_empty() {
-                    batches.push(ExecutionBatch {
-                        batch_id: "parallel-batch".to_string(),
-                        hosts: parallel_tasks[0].hosts.clone(),
-                        tasks: parallel_tasks,
-                        parallel_groups: Vec::new(),
-                        dependencies: Vec::new(),
-                        estimated_duration: None,
-                    });
-                }
-
-                // Add sequential batches
-                for (index, task) in sequential_tasks.iter().enumerate() {
-                    batches.push(ExecutionBatch {
-                        batch_id: format!("sequential-batch-{index}"),
-                        hosts: task.hosts.clone(),
-                        tasks: vec![task.clone()],
-                        parallel_groups: Vec::new(),
-                        dependencies: if index > 0 {
-                            vec![format!("sequential-batch-{}", index - 1)]
-                        } else if !batches.is_empty() {
-                            vec!["parallel-batch".to_string()]
-                        } else {
-                            Vec::new()
-                        },
-                        estimated_duration: task.estimated_duration,
-                    });
-                }
-
-                Ok(batches)
-            }
-            ExecutionStrategy::Rolling { batch_size } => {
-                // Rolling deployment with specified batch size
-                let batch_size = serial.unwrap_or(*batch_size) as usize;
-                let host_count = tasks.first().map(|t| t.hosts.len()).unwrap_or(0);
-
-                if host_count == 0 {
-                    return Ok(Vec::new());
-                }
-
-                let num_batches = host_count.div_ceil(batch_size);
-                let mut batches = Vec::new();
-
-                for batch_index in 0..num_batches {
-                    let start_host = batch_index * batch_size;
-                    let end_host = std::cmp::min(start_host + batch_size, host_count);
-
-                    let batch_hosts: Vec<String> = tasks[0].hosts[start_host..end_host].to_vec();
-
-                    let batch_tasks: Vec<TaskPlan> = tasks
-                        .iter()
-                        .map(|task| {
-                            let mut task_clone = task.clone();
-                            task_clone.hosts = batch_hosts.clone();
-                            task_clone
-                        })
-                        .collect();
-
-                    batches.push(ExecutionBatch {
-                        batch_id: format!("rolling-batch-{batch_index}"),
-                        hosts: batch_hosts,
-                        tasks: batch_tasks,
-                        parallel_groups: Vec::new(),
-                        dependencies: if batch_index > 0 {
-                            vec![format!("rolling-batch-{}", batch_index - 1)]
-                        } else {
-                            Vec::new()
-                        },
-                        estimated_duration: None,
-                    });
-                }
-
-                Ok(batches)
-            }
-            _ => {
-                // For binary strategies, create simple batches for now
-                Ok(vec![ExecutionBatch {
-                    batch_id: "binary-batch".to_string(),
-                    hosts: tasks.first().map(|t| t.hosts.clone()).unwrap_or_default(),
-                    tasks: tasks.to_vec(),
-                    parallel_groups: Vec::new(),
-                    dependencies: Vec::new(),
-                    estimated_duration: None,
-                }])
-            }
-        }
-    }
-
     fn create_handler_plans(
         &self,
         handlers: &[ParsedHandler],
@@ -478,6 +730,10 @@ impl ExecutionPlanner {
                 args: handler.args.clone(),
                 conditions,
                 execution_order: index as u32,
+                listen: handler.listen.clone(),
+                no_log: handler.no_log,
+                notified_by: Vec::new(),
+                flushes: Vec::new(),
             });
         }
 
@@ -485,13 +741,11 @@ impl ExecutionPlanner {
     }
 
     fn calculate_playbook_hash(&self, playbook: &ParsedPlaybook) -> Result<String, PlanError> {
-        let serialized = serde_json::to_string(playbook)?;
-        Ok(format!("{:x}", md5::compute(serialized.as_bytes())))
+        Ok(sha256_digest(canonical_json(playbook)?.as_bytes()))
     }
 
     fn calculate_inventory_hash(&self, inventory: &ParsedInventory) -> Result<String, PlanError> {
-        let serialized = serde_json::to_string(inventory)?;
-        Ok(format!("{:x}", md5::compute(serialized.as_bytes())))
+        Ok(sha256_digest(canonical_json(inventory)?.as_bytes()))
     }
 
     fn calculate_parallelism_score(&self, plays: &[PlayPlan]) -> f32 {
@@ -600,11 +854,12 @@ impl ExecutionPlanner {
     }
 }
 
+fn is_local_host(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
 impl Default for ExecutionPlanner {
     fn default() -> Self {
         Self::new()
     }
 }
-
-// Add md5 dependency to Cargo.toml for hash calculation
-use md5;
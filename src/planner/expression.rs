@@ -0,0 +1,1087 @@
+use crate::planner::error::PlanError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Outcome of evaluating a condition at plan time.
+///
+/// Variables that are not known while planning (registered results, facts
+/// that were never gathered, ...) make an expression `Unknown` rather than
+/// false, so callers should only prune on a definite `False`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionResult {
+    True,
+    False,
+    Unknown,
+}
+
+impl ConditionResult {
+    pub fn is_false(self) -> bool {
+        self == ConditionResult::False
+    }
+
+    pub fn and(self, other: ConditionResult) -> ConditionResult {
+        match (self, other) {
+            (ConditionResult::False, _) | (_, ConditionResult::False) => ConditionResult::False,
+            (ConditionResult::True, ConditionResult::True) => ConditionResult::True,
+            _ => ConditionResult::Unknown,
+        }
+    }
+
+    pub fn or(self, other: ConditionResult) -> ConditionResult {
+        match (self, other) {
+            (ConditionResult::True, _) | (_, ConditionResult::True) => ConditionResult::True,
+            (ConditionResult::False, ConditionResult::False) => ConditionResult::False,
+            _ => ConditionResult::Unknown,
+        }
+    }
+
+    pub fn negate(self) -> ConditionResult {
+        match self {
+            ConditionResult::True => ConditionResult::False,
+            ConditionResult::False => ConditionResult::True,
+            ConditionResult::Unknown => ConditionResult::Unknown,
+        }
+    }
+}
+
+impl From<bool> for ConditionResult {
+    fn from(value: bool) -> Self {
+        if value {
+            ConditionResult::True
+        } else {
+            ConditionResult::False
+        }
+    }
+}
+
+/// A parsed Jinja-style `when:` expression.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    source: String,
+    root: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    List(Vec<Expr>),
+    Variable(String),
+    Attribute(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    Filter {
+        input: Box<Expr>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    Test {
+        input: Box<Expr>,
+        name: String,
+        args: Vec<Expr>,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    NotIn,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Concat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(Value),
+    Op(&'static str),
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, PlanError> {
+        let trimmed = strip_template_delimiters(source.trim());
+        let tokens = tokenize(trimmed).map_err(|reason| invalid(source, reason))?;
+        if tokens.is_empty() {
+            return Err(invalid(source, "expression is empty".to_string()));
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let root = parser
+            .parse_or()
+            .map_err(|reason| invalid(source, reason))?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(source, format!("unexpected token {token:?}")));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Names of the top-level variables the expression references.
+    pub fn variables(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        collect_variables(&self.root, &mut names);
+        names
+    }
+
+    /// Evaluates the expression, resolving variables through `lookup`.
+    ///
+    /// `lookup` returns `None` for variables that are not known at plan time.
+    pub fn evaluate<F>(&self, lookup: F) -> ConditionResult
+    where
+        F: Fn(&str) -> Option<Value>,
+    {
+        eval_condition(&self.root, &lookup)
+    }
+
+    /// Evaluates the expression to a value, or `None` if it depends on
+    /// something not known at plan time.
+    pub fn evaluate_value<F>(&self, lookup: F) -> Option<Value>
+    where
+        F: Fn(&str) -> Option<Value>,
+    {
+        eval_value(&self.root, &lookup)
+    }
+}
+
+/// Substitutes every `{{ ... }}` block of `template` that can be evaluated
+/// through `lookup`, leaving the others untouched.
+///
+/// A template that is a single block evaluates to the block's value, so
+/// `"{{ item }}"` keeps the type of `item`.
+pub fn render_template<F>(template: &str, lookup: F) -> Value
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    let evaluate = |block: &str| {
+        Expression::parse(block)
+            .ok()
+            .and_then(|expression| expression.evaluate_value(&lookup))
+    };
+
+    let trimmed = template.trim();
+    if trimmed.starts_with("{{") && trimmed.ends_with("}}") && trimmed.matches("{{").count() == 1 {
+        return evaluate(trimmed).unwrap_or_else(|| Value::String(template.to_string()));
+    }
+
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        let block = &rest[start..start + length + 2];
+        rendered.push_str(&rest[..start]);
+        match evaluate(block) {
+            Some(value) => rendered.push_str(&stringify(&value)),
+            None => rendered.push_str(block),
+        }
+        rest = &rest[start + length + 2..];
+    }
+    rendered.push_str(rest);
+
+    Value::String(rendered)
+}
+
+fn collect_variables(expr: &Expr, names: &mut Vec<String>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Variable(name) => {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        Expr::List(items) => items.iter().for_each(|item| collect_variables(item, names)),
+        Expr::Attribute(target, _) | Expr::Not(target) | Expr::Negate(target) => {
+            collect_variables(target, names)
+        }
+        Expr::Index(left, right)
+        | Expr::And(left, right)
+        | Expr::Or(left, right)
+        | Expr::Binary(left, _, right) => {
+            collect_variables(left, names);
+            collect_variables(right, names);
+        }
+        Expr::Filter { input, args, .. } | Expr::Test { input, args, .. } => {
+            collect_variables(input, names);
+            args.iter().for_each(|arg| collect_variables(arg, names));
+        }
+    }
+}
+
+fn invalid(expression: &str, reason: String) -> PlanError {
+    PlanError::InvalidCondition {
+        expression: expression.to_string(),
+        reason,
+    }
+}
+
+fn strip_template_delimiters(source: &str) -> &str {
+    source
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+        .map(str::trim)
+        .unwrap_or(source)
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    const OPERATORS: [&str; 21] = [
+        "==", "!=", "<=", ">=", "<", ">", "=", "(", ")", "[", "]", ",", ".", "|", "+", "-", "*",
+        "/", "%", "~", ":",
+    ];
+
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let quote = c;
+            let mut value = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err("unterminated string literal".to_string()),
+                    Some(&ch) if ch == quote => break,
+                    Some('\\') => {
+                        let escaped = chars
+                            .get(i + 1)
+                            .ok_or_else(|| "unterminated string literal".to_string())?;
+                        value.push(match escaped {
+                            'n' => '\n',
+                            't' => '\t',
+                            other => *other,
+                        });
+                        i += 2;
+                    }
+                    Some(&ch) => {
+                        value.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push(Token::Str(value));
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+                i += 1;
+            }
+            // Only treat '.' as a decimal point when a digit follows, so that
+            // `items.0` style lookups keep working
+            if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit() {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let literal: String = chars[start..i].iter().filter(|ch| **ch != '_').collect();
+            let number = if literal.contains('.') {
+                literal
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+            } else {
+                literal.parse::<i64>().ok().map(Value::from)
+            };
+            tokens.push(Token::Number(
+                number.ok_or_else(|| format!("invalid number '{literal}'"))?,
+            ));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("unexpected character '{c}'"))?;
+            i += op.len();
+            tokens.push(Token::Op(op));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name == keyword)
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Token::Op(found)) if *found == op)
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        if self.is_op(op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<(), String> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(token) => format!("expected '{op}', found {token:?}"),
+                None => format!("expected '{op}', found end of expression"),
+            })
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(name),
+            Some(token) => Err(format!("expected a name, found {token:?}")),
+            None => Err("expected a name, found end of expression".to_string()),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.is_keyword("or") {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.is_keyword("and") {
+            self.pos += 1;
+            let right = self.parse_not()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.is_keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    /// Chained comparisons work as in Jinja: `1 < x < 3` means
+    /// `1 < x and x < 3`.
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_additive()?;
+        let mut chain: Option<Expr> = None;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op("==")) => BinaryOp::Eq,
+                Some(Token::Op("!=")) => BinaryOp::Ne,
+                Some(Token::Op("<")) => BinaryOp::Lt,
+                Some(Token::Op("<=")) => BinaryOp::Le,
+                Some(Token::Op(">")) => BinaryOp::Gt,
+                Some(Token::Op(">=")) => BinaryOp::Ge,
+                Some(Token::Ident(name)) if name == "in" => BinaryOp::In,
+                Some(Token::Ident(name))
+                    if name == "not"
+                        && matches!(self.peek_at(1), Some(Token::Ident(next)) if next == "in") =>
+                {
+                    self.pos += 1;
+                    BinaryOp::NotIn
+                }
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.parse_additive()?;
+            let comparison = Expr::Binary(Box::new(left), op, Box::new(right.clone()));
+            chain = Some(match chain {
+                Some(previous) => Expr::And(Box::new(previous), Box::new(comparison)),
+                None => comparison,
+            });
+            left = right;
+        }
+        Ok(chain.unwrap_or(left))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op("+")) => BinaryOp::Add,
+                Some(Token::Op("-")) => BinaryOp::Sub,
+                Some(Token::Op("~")) => BinaryOp::Concat,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op("*")) => BinaryOp::Mul,
+                Some(Token::Op("/")) => BinaryOp::Div,
+                Some(Token::Op("%")) => BinaryOp::Mod,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat_op("-") {
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        let primary = self.parse_postfix()?;
+        self.parse_filters_and_tests(primary)
+    }
+
+    fn parse_filters_and_tests(&mut self, mut expr: Expr) -> Result<Expr, String> {
+        loop {
+            if self.eat_op("|") {
+                let name = self.expect_ident()?;
+                let args = self.parse_optional_args()?;
+                expr = Expr::Filter {
+                    input: Box::new(expr),
+                    name,
+                    args,
+                };
+            } else if self.is_keyword("is") {
+                self.pos += 1;
+                let negated = self.is_keyword("not");
+                if negated {
+                    self.pos += 1;
+                }
+                let name = self.expect_ident()?;
+                let args = self.parse_optional_args()?;
+                expr = Expr::Test {
+                    input: Box::new(expr),
+                    name,
+                    args,
+                    negated,
+                };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_optional_args(&mut self) -> Result<Vec<Expr>, String> {
+        if !self.eat_op("(") {
+            return Ok(Vec::new());
+        }
+        let args = self.parse_sequence(")")?;
+        Ok(args)
+    }
+
+    fn parse_sequence(&mut self, close: &str) -> Result<Vec<Expr>, String> {
+        let mut items = Vec::new();
+        if self.eat_op(close) {
+            return Ok(items);
+        }
+        loop {
+            // Keyword arguments (`default(value, boolean=true)`) are accepted
+            // positionally since the supported filters have fixed signatures
+            if matches!(self.peek(), Some(Token::Ident(_)))
+                && matches!(self.peek_at(1), Some(Token::Op("=")))
+            {
+                self.pos += 2;
+            }
+            items.push(self.parse_or()?);
+            if self.eat_op(close) {
+                return Ok(items);
+            }
+            self.expect_op(",")?;
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat_op(".") {
+                let attribute = match self.next() {
+                    Some(Token::Ident(name)) => name,
+                    Some(Token::Number(number)) => number.to_string(),
+                    Some(token) => return Err(format!("expected attribute, found {token:?}")),
+                    None => return Err("expected attribute, found end of expression".to_string()),
+                };
+                expr = Expr::Attribute(Box::new(expr), attribute);
+            } else if self.eat_op("[") {
+                let index = self.parse_or()?;
+                self.expect_op("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Str(value)) => Ok(Expr::Literal(Value::String(value))),
+            Some(Token::Number(number)) => Ok(Expr::Literal(number)),
+            Some(Token::Ident(name)) => Ok(match name.as_str() {
+                "true" | "True" => Expr::Literal(Value::Bool(true)),
+                "false" | "False" => Expr::Literal(Value::Bool(false)),
+                "none" | "None" | "null" => Expr::Literal(Value::Null),
+                "and" | "or" | "not" | "in" | "is" => {
+                    return Err(format!("unexpected keyword '{name}'"))
+                }
+                _ => Expr::Variable(name),
+            }),
+            Some(Token::Op("(")) => {
+                let expr = self.parse_or()?;
+                self.expect_op(")")?;
+                Ok(expr)
+            }
+            Some(Token::Op("[")) => Ok(Expr::List(self.parse_sequence("]")?)),
+            Some(token) => Err(format!("unexpected token {token:?}")),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+// Values are `None` when they cannot be resolved at plan time
+type Operand = Option<Value>;
+
+fn eval_condition<F>(expr: &Expr, lookup: &F) -> ConditionResult
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    match expr {
+        Expr::And(left, right) => eval_condition(left, lookup).and(eval_condition(right, lookup)),
+        Expr::Or(left, right) => eval_condition(left, lookup).or(eval_condition(right, lookup)),
+        Expr::Not(inner) => eval_condition(inner, lookup).negate(),
+        _ => match eval_value(expr, lookup) {
+            Some(value) => truthy(&value).into(),
+            None => ConditionResult::Unknown,
+        },
+    }
+}
+
+fn condition_to_operand(result: ConditionResult) -> Operand {
+    match result {
+        ConditionResult::True => Some(Value::Bool(true)),
+        ConditionResult::False => Some(Value::Bool(false)),
+        ConditionResult::Unknown => None,
+    }
+}
+
+fn eval_value<F>(expr: &Expr, lookup: &F) -> Operand
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    match expr {
+        Expr::Literal(value) => Some(value.clone()),
+        Expr::List(items) => items
+            .iter()
+            .map(|item| eval_value(item, lookup))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        Expr::Variable(name) => lookup(name),
+        Expr::Attribute(target, attribute) => get_item(
+            &eval_value(target, lookup)?,
+            &Value::String(attribute.clone()),
+        ),
+        Expr::Index(target, index) => {
+            get_item(&eval_value(target, lookup)?, &eval_value(index, lookup)?)
+        }
+        Expr::And(..) | Expr::Or(..) | Expr::Not(..) => {
+            condition_to_operand(eval_condition(expr, lookup))
+        }
+        Expr::Negate(inner) => {
+            let number = as_number(&eval_value(inner, lookup)?)?;
+            number_value(-number)
+        }
+        Expr::Binary(left, op, right) => {
+            let left = eval_value(left, lookup)?;
+            let right = eval_value(right, lookup)?;
+            eval_binary(&left, *op, &right)
+        }
+        Expr::Filter { input, name, args } => {
+            let input = eval_value(input, lookup)?;
+            let args = args
+                .iter()
+                .map(|arg| eval_value(arg, lookup))
+                .collect::<Option<Vec<_>>>()?;
+            apply_filter(&input, name, &args)
+        }
+        Expr::Test {
+            input,
+            name,
+            args,
+            negated,
+        } => {
+            let input = eval_value(input, lookup)?;
+            let args = args
+                .iter()
+                .map(|arg| eval_value(arg, lookup))
+                .collect::<Option<Vec<_>>>()?;
+            let passed = apply_test(&input, name, &args)?;
+            Some(Value::Bool(passed != *negated))
+        }
+    }
+}
+
+fn get_item(target: &Value, key: &Value) -> Operand {
+    match (target, key) {
+        (Value::Object(map), Value::String(key)) => map.get(key).cloned(),
+        (Value::Array(items), key) => {
+            let index = match key {
+                Value::Number(number) => number.as_i64()?,
+                Value::String(text) => text.parse().ok()?,
+                _ => return None,
+            };
+            let index = if index < 0 {
+                items.len() as i64 + index
+            } else {
+                index
+            };
+            items.get(usize::try_from(index).ok()?).cloned()
+        }
+        _ => None,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(flag) => *flag,
+        Value::Number(number) => number.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::Bool(flag) => Some(if *flag { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+fn number_value(number: f64) -> Operand {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Some(Value::from(number as i64))
+    } else {
+        serde_json::Number::from_f64(number).map(Value::Number)
+    }
+}
+
+fn loosely_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| loosely_equal(x, y))
+        }
+        _ => left == right,
+    }
+}
+
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => "None".to_string(),
+        Value::Bool(true) => "True".to_string(),
+        Value::Bool(false) => "False".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn eval_binary(left: &Value, op: BinaryOp, right: &Value) -> Operand {
+    let ordering = || match (left, right) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => as_number(left)?.partial_cmp(&as_number(right)?),
+    };
+
+    match op {
+        BinaryOp::Eq => Some(Value::Bool(loosely_equal(left, right))),
+        BinaryOp::Ne => Some(Value::Bool(!loosely_equal(left, right))),
+        BinaryOp::Lt => Some(Value::Bool(ordering()?.is_lt())),
+        BinaryOp::Le => Some(Value::Bool(ordering()?.is_le())),
+        BinaryOp::Gt => Some(Value::Bool(ordering()?.is_gt())),
+        BinaryOp::Ge => Some(Value::Bool(ordering()?.is_ge())),
+        BinaryOp::In => contains(right, left).map(Value::Bool),
+        BinaryOp::NotIn => contains(right, left).map(|found| Value::Bool(!found)),
+        BinaryOp::Concat => Some(Value::String(stringify(left) + &stringify(right))),
+        BinaryOp::Add => match (left, right) {
+            (Value::String(a), Value::String(b)) => Some(Value::String(format!("{a}{b}"))),
+            (Value::Array(a), Value::Array(b)) => {
+                Some(Value::Array(a.iter().chain(b).cloned().collect()))
+            }
+            _ => number_value(as_number(left)? + as_number(right)?),
+        },
+        BinaryOp::Sub => number_value(as_number(left)? - as_number(right)?),
+        BinaryOp::Mul => number_value(as_number(left)? * as_number(right)?),
+        BinaryOp::Div => {
+            let divisor = as_number(right)?;
+            if divisor == 0.0 {
+                return None;
+            }
+            serde_json::Number::from_f64(as_number(left)? / divisor).map(Value::Number)
+        }
+        BinaryOp::Mod => {
+            let divisor = as_number(right)?;
+            if divisor == 0.0 {
+                return None;
+            }
+            number_value(as_number(left)?.rem_euclid(divisor))
+        }
+    }
+}
+
+fn contains(haystack: &Value, needle: &Value) -> Option<bool> {
+    match haystack {
+        Value::String(text) => Some(text.contains(needle.as_str()?)),
+        Value::Array(items) => Some(items.iter().any(|item| loosely_equal(item, needle))),
+        Value::Object(map) => Some(map.contains_key(needle.as_str()?)),
+        _ => None,
+    }
+}
+
+pub(crate) fn to_bool(value: &Value) -> bool {
+    match value {
+        Value::Bool(flag) => *flag,
+        Value::Number(number) => number.as_f64() == Some(1.0),
+        Value::String(text) => matches!(
+            text.trim().to_lowercase().as_str(),
+            "yes" | "on" | "1" | "true" | "y" | "t"
+        ),
+        _ => false,
+    }
+}
+
+fn apply_filter(input: &Value, name: &str, args: &[Value]) -> Operand {
+    match name {
+        "bool" => Some(Value::Bool(to_bool(input))),
+        "default" | "d" => {
+            // Only reached for known values; an unresolved input stays unknown
+            // because the variable may still be defined at runtime
+            let use_default_for_falsy = args.get(1).is_some_and(truthy);
+            if use_default_for_falsy && !truthy(input) {
+                Some(
+                    args.first()
+                        .cloned()
+                        .unwrap_or(Value::String(String::new())),
+                )
+            } else {
+                Some(input.clone())
+            }
+        }
+        "lower" => Some(Value::String(stringify(input).to_lowercase())),
+        "upper" => Some(Value::String(stringify(input).to_uppercase())),
+        "trim" => Some(Value::String(stringify(input).trim().to_string())),
+        "string" => Some(Value::String(stringify(input))),
+        "int" => match input {
+            Value::String(text) => Some(Value::from(
+                text.trim()
+                    .parse::<f64>()
+                    .map(|n| n.trunc() as i64)
+                    .unwrap_or(0),
+            )),
+            other => Some(Value::from(as_number(other).unwrap_or(0.0).trunc() as i64)),
+        },
+        "float" => {
+            let number = match input {
+                Value::String(text) => text.trim().parse::<f64>().unwrap_or(0.0),
+                other => as_number(other).unwrap_or(0.0),
+            };
+            serde_json::Number::from_f64(number).map(Value::Number)
+        }
+        "length" | "count" => match input {
+            Value::String(text) => Some(Value::from(text.chars().count())),
+            Value::Array(items) => Some(Value::from(items.len())),
+            Value::Object(map) => Some(Value::from(map.len())),
+            _ => None,
+        },
+        "first" => match input {
+            Value::Array(items) => items.first().cloned(),
+            Value::String(text) => text.chars().next().map(|c| Value::String(c.to_string())),
+            _ => None,
+        },
+        "last" => match input {
+            Value::Array(items) => items.last().cloned(),
+            Value::String(text) => text.chars().last().map(|c| Value::String(c.to_string())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn apply_test(input: &Value, name: &str, args: &[Value]) -> Option<bool> {
+    match name {
+        // The input resolved, so it is defined
+        "defined" => Some(true),
+        "undefined" => Some(false),
+        "none" => Some(input.is_null()),
+        "true" => Some(*input == Value::Bool(true)),
+        "false" => Some(*input == Value::Bool(false)),
+        "boolean" => Some(input.is_boolean()),
+        "string" => Some(input.is_string()),
+        "number" => Some(input.is_number()),
+        "integer" => Some(input.is_i64() || input.is_u64()),
+        "float" => Some(input.is_f64()),
+        "mapping" => Some(input.is_object()),
+        "sequence" | "iterable" => Some(input.is_array() || input.is_string() || input.is_object()),
+        "eq" | "equalto" | "sameas" => Some(loosely_equal(input, args.first()?)),
+        "in" => contains(args.first()?, input),
+        "contains" => contains(input, args.first()?),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn variables() -> HashMap<String, Value> {
+        let mut vars = HashMap::new();
+        vars.insert("os_family".to_string(), json!("Debian"));
+        vars.insert("port".to_string(), json!(8080));
+        vars.insert("enabled".to_string(), json!("yes"));
+        vars.insert("empty".to_string(), json!(""));
+        vars.insert("packages".to_string(), json!(["nginx", "git"]));
+        vars.insert(
+            "ansible_facts".to_string(),
+            json!({"distribution": "Ubuntu", "memtotal_mb": 4096, "mounts": [{"mount": "/"}]}),
+        );
+        vars
+    }
+
+    fn eval(source: &str) -> ConditionResult {
+        let vars = variables();
+        Expression::parse(source)
+            .unwrap()
+            .evaluate(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(eval("os_family == 'Debian'"), ConditionResult::True);
+        assert_eq!(eval("os_family != \"Debian\""), ConditionResult::False);
+        assert_eq!(eval("port > 8000"), ConditionResult::True);
+        assert_eq!(eval("port <= 80"), ConditionResult::False);
+        assert_eq!(eval("port == 8080.0"), ConditionResult::True);
+        assert_eq!(
+            eval("ansible_facts.memtotal_mb / 1024 >= 4"),
+            ConditionResult::True
+        );
+    }
+
+    #[test]
+    fn test_chained_comparisons() {
+        assert_eq!(eval("8000 < port < 9000"), ConditionResult::True);
+        assert_eq!(eval("1 < port < 3"), ConditionResult::False);
+        assert_eq!(eval("80 <= port <= 8080 != 0"), ConditionResult::True);
+        assert_eq!(eval("1 < missing < 3"), ConditionResult::Unknown);
+        assert_eq!(eval("9000 < port < missing"), ConditionResult::False);
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        assert_eq!(
+            eval("os_family == 'Debian' and port == 8080"),
+            ConditionResult::True
+        );
+        assert_eq!(
+            eval("os_family == 'RedHat' or port == 1"),
+            ConditionResult::False
+        );
+        assert_eq!(eval("not os_family == 'RedHat'"), ConditionResult::True);
+        assert_eq!(
+            eval("(os_family == 'RedHat' or port == 8080) and not empty"),
+            ConditionResult::True
+        );
+    }
+
+    #[test]
+    fn test_unknown_variables_propagate() {
+        assert_eq!(eval("result.rc == 0"), ConditionResult::Unknown);
+        assert_eq!(eval("result is succeeded"), ConditionResult::Unknown);
+        assert_eq!(
+            eval("missing and os_family == 'RedHat'"),
+            ConditionResult::False
+        );
+        assert_eq!(
+            eval("missing or os_family == 'Debian'"),
+            ConditionResult::True
+        );
+        assert_eq!(eval("not missing"), ConditionResult::Unknown);
+    }
+
+    #[test]
+    fn test_membership() {
+        assert_eq!(eval("'nginx' in packages"), ConditionResult::True);
+        assert_eq!(eval("'apache' not in packages"), ConditionResult::True);
+        assert_eq!(eval("'Deb' in os_family"), ConditionResult::True);
+        assert_eq!(
+            eval("'distribution' in ansible_facts"),
+            ConditionResult::True
+        );
+        assert_eq!(
+            eval("os_family in ['RedHat', 'Suse']"),
+            ConditionResult::False
+        );
+    }
+
+    #[test]
+    fn test_defined_tests() {
+        assert_eq!(eval("os_family is defined"), ConditionResult::True);
+        assert_eq!(eval("os_family is undefined"), ConditionResult::False);
+        assert_eq!(eval("os_family is not defined"), ConditionResult::False);
+        assert_eq!(eval("missing is defined"), ConditionResult::Unknown);
+        assert_eq!(eval("missing is undefined"), ConditionResult::Unknown);
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(eval("enabled | bool"), ConditionResult::True);
+        assert_eq!(eval("empty | bool"), ConditionResult::False);
+        assert_eq!(eval("os_family | lower == 'debian'"), ConditionResult::True);
+        assert_eq!(eval("packages | length == 2"), ConditionResult::True);
+        assert_eq!(
+            eval("empty | default('x', true) == 'x'"),
+            ConditionResult::True
+        );
+        assert_eq!(eval("empty | d('x') == ''"), ConditionResult::True);
+        assert_eq!(eval("missing | default(false)"), ConditionResult::Unknown);
+        assert_eq!(
+            eval("os_family | unsupported_filter"),
+            ConditionResult::Unknown
+        );
+    }
+
+    #[test]
+    fn test_lookups() {
+        assert_eq!(
+            eval("ansible_facts['distribution'] == 'Ubuntu'"),
+            ConditionResult::True
+        );
+        assert_eq!(
+            eval("ansible_facts.mounts[0].mount == '/'"),
+            ConditionResult::True
+        );
+        assert_eq!(eval("packages[-1] == 'git'"), ConditionResult::True);
+        assert_eq!(
+            eval("ansible_facts.kernel == 'x'"),
+            ConditionResult::Unknown
+        );
+    }
+
+    #[test]
+    fn test_variables() {
+        let expression =
+            Expression::parse("ansible_facts.os_family == 'Debian' and port | default(x) > 1")
+                .unwrap();
+        assert_eq!(expression.variables(), vec!["ansible_facts", "port", "x"]);
+    }
+
+    #[test]
+    fn test_template_delimiters_are_stripped() {
+        assert_eq!(eval("{{ port == 8080 }}"), ConditionResult::True);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for source in ["", "port ==", "(port == 1", "port === 1", "'open", "a | "] {
+            let error = Expression::parse(source).unwrap_err();
+            assert!(
+                matches!(error, PlanError::InvalidCondition { .. }),
+                "{source:?} should fail to parse"
+            );
+        }
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        use ConditionResult::*;
+        assert_eq!(Unknown.and(False), False);
+        assert_eq!(Unknown.and(True), Unknown);
+        assert_eq!(Unknown.or(True), True);
+        assert_eq!(Unknown.or(False), Unknown);
+        assert_eq!(Unknown.negate(), Unknown);
+        assert_eq!(ConditionResult::from(true), True);
+    }
+
+    #[test]
+    fn test_render_template() {
+        let vars = variables();
+        let lookup = |name: &str| vars.get(name).cloned();
+
+        assert_eq!(render_template("{{ port }}", lookup), json!(8080));
+        assert_eq!(
+            render_template("/etc/{{ os_family | lower }}/{{ port }}.conf", lookup),
+            json!("/etc/debian/8080.conf")
+        );
+        // Unknown variables stay templated for runtime
+        assert_eq!(
+            render_template("{{ packages[0] }}-{{ version }}", lookup),
+            json!("nginx-{{ version }}")
+        );
+        assert_eq!(
+            render_template("{{ missing }}", lookup),
+            json!("{{ missing }}")
+        );
+        assert_eq!(render_template("plain text", lookup), json!("plain text"));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::types::*;
+use std::collections::HashSet;
+
+/// Turns a play's `max_fail_percentage` and `any_errors_fatal` into a
+/// proceed gate on each of its batches.
+///
+/// As in Ansible, failures are counted per batch for rolling plays and over
+/// the whole play otherwise; canary and blue/green stages count like rolling
+/// batches. The play aborts once more than `max_fail_percentage` of the
+/// counted hosts have failed, once any host has failed with
+/// `any_errors_fatal`, and otherwise once every host has failed.
+pub struct FailurePolicyPlanner;
+
+impl FailurePolicyPlanner {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Sets `ExecutionBatch.proceed_if` on every batch of the play.
+    pub fn apply(&self, play: &mut PlayPlan) {
+        // Staged rollouts count failures per stage, like rolling batches
+        let rolling = matches!(
+            play.strategy,
+            ExecutionStrategy::Rolling { .. }
+                | ExecutionStrategy::Canary { .. }
+                | ExecutionStrategy::BlueGreen { .. }
+        );
+
+        for batch in &mut play.batches {
+            let hosts = if rolling {
+                batch.hosts.clone()
+            } else {
+                play.hosts.clone()
+            };
+            let abort_threshold =
+                self.abort_threshold(hosts.len(), play.max_fail_percentage, play.any_errors_fatal);
+
+            batch.proceed_if = Some(ProceedGate {
+                hosts,
+                abort_threshold,
+                max_fail_percentage: play.max_fail_percentage,
+                any_errors_fatal: play.any_errors_fatal,
+            });
+        }
+    }
+
+    /// Number of failed hosts out of `host_count` that aborts the play.
+    pub fn abort_threshold(
+        &self,
+        host_count: usize,
+        max_fail_percentage: Option<u32>,
+        any_errors_fatal: bool,
+    ) -> usize {
+        let all_hosts = host_count.max(1);
+        if any_errors_fatal {
+            return 1;
+        }
+
+        match max_fail_percentage {
+            // Ansible aborts once the failure rate goes above the percentage
+            Some(percent) => (host_count * percent as usize / 100 + 1).min(all_hosts),
+            None => all_hosts,
+        }
+    }
+
+    /// The most hosts that can fail before the plan aborts: every gate lets
+    /// one host fewer than its threshold fail, and the failure that trips a
+    /// gate is the last one. Hosts that fail drop out of later plays, so the
+    /// result never exceeds the number of distinct hosts.
+    pub fn worst_case_failed_hosts(&self, plays: &[PlayPlan]) -> usize {
+        let mut tolerated = 0;
+        let mut hosts = HashSet::new();
+
+        for play in plays {
+            hosts.extend(play.hosts.iter());
+            for gate in self.gates(play) {
+                tolerated += gate.abort_threshold.saturating_sub(1);
+            }
+        }
+
+        if hosts.is_empty() {
+            0
+        } else {
+            (tolerated + 1).min(hosts.len())
+        }
+    }
+
+    /// The distinct gates of a play, one per group of hosts failures are
+    /// counted over.
+    pub fn gates<'a>(&self, play: &'a PlayPlan) -> Vec<&'a ProceedGate> {
+        let mut seen = HashSet::new();
+        play.batches
+            .iter()
+            .filter_map(|batch| batch.proceed_if.as_ref())
+            .filter(|gate| seen.insert(&gate.hosts))
+            .collect()
+    }
+}
+
+impl Default for FailurePolicyPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::fixtures;
+
+    fn create_batch(id: &str, hosts: &[&str]) -> ExecutionBatch {
+        ExecutionBatch {
+            hosts: fixtures::strings(hosts),
+            ..fixtures::batch(id, vec![])
+        }
+    }
+
+    fn create_play(strategy: ExecutionStrategy, batches: Vec<ExecutionBatch>) -> PlayPlan {
+        let mut hosts: Vec<String> = Vec::new();
+        for host in batches.iter().flat_map(|batch| &batch.hosts) {
+            if !hosts.contains(host) {
+                hosts.push(host.clone());
+            }
+        }
+
+        PlayPlan {
+            strategy,
+            hosts,
+            ..fixtures::play("play-0", batches)
+        }
+    }
+
+    fn rolling() -> ExecutionStrategy {
+        ExecutionStrategy::Rolling {
+            batch_sizes: vec![BatchSize::Hosts(4)],
+            topology: None,
+        }
+    }
+
+    #[test]
+    fn test_abort_threshold() {
+        let planner = FailurePolicyPlanner::new();
+
+        assert_eq!(planner.abort_threshold(10, None, false), 10);
+        assert_eq!(planner.abort_threshold(10, None, true), 1);
+        assert_eq!(planner.abort_threshold(10, Some(20), false), 3);
+        assert_eq!(planner.abort_threshold(10, Some(0), false), 1);
+        assert_eq!(planner.abort_threshold(10, Some(100), false), 10);
+        assert_eq!(planner.abort_threshold(0, None, false), 1);
+    }
+
+    #[test]
+    fn test_rolling_gates_count_batch_hosts() {
+        let mut play = create_play(
+            rolling(),
+            vec![
+                create_batch("rolling-batch-0", &["h1", "h2", "h3", "h4"]),
+                create_batch("rolling-batch-1", &["h5", "h6"]),
+            ],
+        );
+        play.max_fail_percentage = Some(25);
+
+        let planner = FailurePolicyPlanner::new();
+        planner.apply(&mut play);
+
+        let first = play.batches[0].proceed_if.as_ref().unwrap();
+        assert_eq!(first.hosts.len(), 4);
+        assert_eq!(first.abort_threshold, 2);
+        assert_eq!(first.max_fail_percentage, Some(25));
+        let second = play.batches[1].proceed_if.as_ref().unwrap();
+        assert_eq!(second.abort_threshold, 1);
+
+        // One failure tolerated in the first batch, then the next one aborts
+        assert_eq!(planner.worst_case_failed_hosts(&[play]), 2);
+    }
+
+    #[test]
+    fn test_linear_gates_count_play_hosts() {
+        let mut play = create_play(
+            ExecutionStrategy::Linear,
+            vec![
+                create_batch("batch-0", &["h1", "h2"]),
+                create_batch("batch-1", &["h1", "h2", "h3"]),
+            ],
+        );
+
+        let planner = FailurePolicyPlanner::new();
+        planner.apply(&mut play);
+
+        assert_eq!(planner.gates(&play).len(), 1);
+        let gate = play.batches[0].proceed_if.as_ref().unwrap();
+        assert_eq!(gate.hosts.len(), 3);
+        assert_eq!(gate.abort_threshold, 3);
+        assert_eq!(planner.worst_case_failed_hosts(&[play]), 3);
+    }
+
+    #[test]
+    fn test_any_errors_fatal_stops_at_first_failure() {
+        let mut play = create_play(
+            rolling(),
+            vec![
+                create_batch("rolling-batch-0", &["h1", "h2"]),
+                create_batch("rolling-batch-1", &["h3", "h4"]),
+            ],
+        );
+        play.any_errors_fatal = true;
+
+        let planner = FailurePolicyPlanner::new();
+        planner.apply(&mut play);
+
+        for batch in &play.batches {
+            assert_eq!(batch.proceed_if.as_ref().unwrap().abort_threshold, 1);
+        }
+        assert_eq!(planner.worst_case_failed_hosts(&[play]), 1);
+    }
+}
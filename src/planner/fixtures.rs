@@ -0,0 +1,86 @@
+//! Plan values shared by the planner unit tests. Each builder returns a
+//! minimal value that tests adjust with struct update syntax.
+
+use crate::types::*;
+use std::collections::HashMap;
+
+/// A parallel-safe `command` task on host1 with no duration estimate
+pub(crate) fn task(id: &str) -> TaskPlan {
+    TaskPlan {
+        task_id: id.to_string(),
+        name: format!("Test Task {id}"),
+        module: "command".to_string(),
+        args: HashMap::new(),
+        hosts: strings(&["host1"]),
+        dependencies: vec![],
+        conditions: vec![],
+        tags: vec![],
+        notify: vec![],
+        execution_order: 0,
+        can_run_parallel: true,
+        estimated_duration: None,
+        risk_level: RiskLevel::Low,
+        vars: HashMap::new(),
+        loop_items: None,
+        changed_when: None,
+        failed_when: None,
+        ignore_errors: false,
+        no_log: false,
+        delegate_to: None,
+        loop_expansion: None,
+        schedule: None,
+    }
+}
+
+/// A batch of `tasks` on host1 that depends on no other batch
+pub(crate) fn batch(id: &str, tasks: Vec<TaskPlan>) -> ExecutionBatch {
+    ExecutionBatch {
+        batch_id: id.to_string(),
+        hosts: strings(&["host1"]),
+        tasks,
+        parallel_groups: vec![],
+        dependencies: vec![],
+        estimated_duration: None,
+        binary_deployment: None,
+        proceed_if: None,
+        stage_gate: None,
+    }
+}
+
+/// A linear play on host1
+pub(crate) fn play(id: &str, batches: Vec<ExecutionBatch>) -> PlayPlan {
+    PlayPlan {
+        play_id: id.to_string(),
+        name: "Test Play".to_string(),
+        strategy: ExecutionStrategy::Linear,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        hosts: strings(&["host1"]),
+        batches,
+        handlers: vec![],
+        estimated_duration: None,
+        critical_path: None,
+    }
+}
+
+/// A `service` handler that nothing has notified yet
+pub(crate) fn handler(name: &str) -> HandlerPlan {
+    HandlerPlan {
+        handler_id: format!("handler-{name}"),
+        name: name.to_string(),
+        module: "service".to_string(),
+        args: HashMap::new(),
+        conditions: vec![],
+        execution_order: 0,
+        listen: vec![],
+        no_log: false,
+        notified_by: vec![],
+        flushes: vec![],
+    }
+}
+
+pub(crate) fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
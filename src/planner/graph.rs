@@ -1,5 +1,6 @@
 use crate::planner::error::PlanError;
 use crate::types::*;
+use std::collections::{HashMap, HashSet};
 
 pub struct DependencyGraphBuilder;
 
@@ -10,7 +11,7 @@ impl DependencyGraphBuilder {
 
     pub fn build_from_tasks(&self, tasks: &[TaskPlan]) -> Result<DependencyGraph, PlanError> {
         let mut graph = petgraph::Graph::new();
-        let mut task_nodes = std::collections::HashMap::new();
+        let mut task_nodes = HashMap::new();
 
         // Add nodes for all tasks
         for task in tasks {
@@ -38,24 +39,27 @@ impl DependencyGraphBuilder {
         dependency_graph: &DependencyGraph,
     ) -> Vec<ParallelGroup> {
         let mut groups = Vec::new();
-        let mut visited = std::collections::HashSet::new();
+        let mut visited = HashSet::new();
 
         for task in tasks {
             if visited.contains(&task.task_id) || !task.can_run_parallel {
                 continue;
             }
 
-            let mut group_tasks = vec![task.task_id.clone()];
+            let mut group_tasks = vec![task];
             visited.insert(task.task_id.clone());
 
-            // Find tasks that can run in parallel with this one
+            // Find tasks that can run in parallel with every task in the group
             for other_task in tasks {
                 if visited.contains(&other_task.task_id) {
                     continue;
                 }
 
-                if self.can_run_parallel(task, other_task, dependency_graph) {
-                    group_tasks.push(other_task.task_id.clone());
+                if group_tasks
+                    .iter()
+                    .all(|member| self.can_run_parallel(member, other_task, dependency_graph))
+                {
+                    group_tasks.push(other_task);
                     visited.insert(other_task.task_id.clone());
                 }
             }
@@ -63,7 +67,7 @@ impl DependencyGraphBuilder {
             if group_tasks.len() > 1 {
                 groups.push(ParallelGroup {
                     group_id: format!("group_{}", groups.len()),
-                    tasks: group_tasks,
+                    tasks: group_tasks.iter().map(|t| t.task_id.clone()).collect(),
                     max_parallelism: self.calculate_max_parallelism(task),
                     shared_resources: Vec::new(), // Simplified for now
                 });
@@ -73,6 +77,73 @@ impl DependencyGraphBuilder {
         groups
     }
 
+    /// Splits tasks into layers where every task depends only on tasks in
+    /// earlier layers. Tasks keep their relative order within a layer.
+    pub fn topological_layers(
+        &self,
+        tasks: &[TaskPlan],
+        dependency_graph: &DependencyGraph,
+    ) -> Result<Vec<Vec<TaskPlan>>, PlanError> {
+        let task_ids: HashSet<String> = tasks.iter().map(|t| t.task_id.clone()).collect();
+        let predecessors: HashMap<&str, Vec<String>> = tasks
+            .iter()
+            .map(|task| {
+                let mut preds = dependency_graph.predecessors_within(&task.task_id, &task_ids);
+                for dependency in &task.dependencies {
+                    if task_ids.contains(dependency) && !preds.contains(dependency) {
+                        preds.push(dependency.clone());
+                    }
+                }
+                (task.task_id.as_str(), preds)
+            })
+            .collect();
+
+        let mut layers_by_task: HashMap<String, usize> = HashMap::new();
+        for task in tasks {
+            Self::assign_layer(
+                &task.task_id,
+                &predecessors,
+                &mut layers_by_task,
+                &mut Vec::new(),
+            )?;
+        }
+
+        let layer_count = layers_by_task.values().max().map_or(0, |max| max + 1);
+        let mut layers = vec![Vec::new(); layer_count];
+        for task in tasks {
+            layers[layers_by_task[&task.task_id]].push(task.clone());
+        }
+
+        Ok(layers)
+    }
+
+    fn assign_layer(
+        task_id: &str,
+        predecessors: &HashMap<&str, Vec<String>>,
+        layers: &mut HashMap<String, usize>,
+        path: &mut Vec<String>,
+    ) -> Result<usize, PlanError> {
+        if let Some(layer) = layers.get(task_id) {
+            return Ok(*layer);
+        }
+        if path.iter().any(|id| id == task_id) {
+            path.push(task_id.to_string());
+            return Err(PlanError::CircularDependency {
+                cycle: path.join(" -> "),
+            });
+        }
+
+        path.push(task_id.to_string());
+        let mut layer = 0;
+        for predecessor in predecessors.get(task_id).into_iter().flatten() {
+            layer = layer.max(Self::assign_layer(predecessor, predecessors, layers, path)? + 1);
+        }
+        path.pop();
+
+        layers.insert(task_id.to_string(), layer);
+        Ok(layer)
+    }
+
     fn can_run_parallel(
         &self,
         task1: &TaskPlan,
@@ -101,10 +172,14 @@ impl DependencyGraphBuilder {
 
     fn has_resource_conflict(&self, task1: &TaskPlan, task2: &TaskPlan) -> bool {
         // Check if tasks modify the same files
-        if let (Some(dest1), Some(dest2)) = (
-            task1.args.get("dest").and_then(|v| v.as_str()),
-            task2.args.get("dest").and_then(|v| v.as_str()),
-        ) {
+        let target_file = |task: &TaskPlan| {
+            task.args
+                .get("dest")
+                .or_else(|| task.args.get("path"))
+                .and_then(|v| v.as_str())
+                .map(|path| path.to_string())
+        };
+        if let (Some(dest1), Some(dest2)) = (target_file(task1), target_file(task2)) {
             if dest1 == dest2 {
                 return true;
             }
@@ -159,6 +234,15 @@ mod tests {
             can_run_parallel: true,
             estimated_duration: Some(Duration::from_secs(5)),
             risk_level: RiskLevel::Low,
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
         }
     }
 
@@ -448,4 +532,66 @@ mod tests {
         assert_eq!(group.max_parallelism, 4);
         assert!(group.shared_resources.is_empty());
     }
+
+    #[test]
+    fn test_find_parallel_groups_checks_every_member() {
+        let builder = DependencyGraphBuilder::new();
+        let mut args = HashMap::new();
+        args.insert(
+            "dest".to_string(),
+            serde_json::Value::String("/etc/app.conf".to_string()),
+        );
+        let task1 = create_task_with_args("task1", "copy", args.clone());
+        let task2 = create_test_task("task2", "debug");
+        let mut path_args = HashMap::new();
+        path_args.insert(
+            "path".to_string(),
+            serde_json::Value::String("/etc/app.conf".to_string()),
+        );
+        let task3 = create_task_with_args("task3", "lineinfile", path_args);
+        let tasks = vec![task1, task2, task3];
+        let graph = builder.build_from_tasks(&tasks).unwrap();
+        let groups = builder.find_parallel_groups(&tasks, &graph);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].tasks, vec!["task1", "task2"]);
+    }
+
+    #[test]
+    fn test_topological_layers() {
+        let builder = DependencyGraphBuilder::new();
+        let tasks = vec![
+            create_test_task("a", "shell"),
+            create_test_task("b", "shell"),
+            create_task_with_dependencies("c", vec!["a".to_string()]),
+            create_task_with_dependencies("d", vec!["c".to_string(), "b".to_string()]),
+            create_task_with_dependencies("e", vec!["a".to_string()]),
+        ];
+        let graph = builder.build_from_tasks(&tasks).unwrap();
+        let layers = builder.topological_layers(&tasks, &graph).unwrap();
+
+        let ids: Vec<Vec<&str>> = layers
+            .iter()
+            .map(|layer| layer.iter().map(|t| t.task_id.as_str()).collect())
+            .collect();
+        assert_eq!(ids, vec![vec!["a", "b"], vec!["c", "e"], vec!["d"]]);
+    }
+
+    #[test]
+    fn test_topological_layers_through_removed_tasks() {
+        let builder = DependencyGraphBuilder::new();
+        let all_tasks = vec![
+            create_test_task("a", "shell"),
+            create_task_with_dependencies("b", vec!["a".to_string()]),
+            create_task_with_dependencies("c", vec!["b".to_string()]),
+        ];
+        let graph = builder.build_from_tasks(&all_tasks).unwrap();
+
+        // "b" was pruned, but "c" must still run after "a"
+        let tasks = vec![all_tasks[0].clone(), all_tasks[2].clone()];
+        let layers = builder.topological_layers(&tasks, &graph).unwrap();
+
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[1][0].task_id, "c");
+    }
 }
@@ -0,0 +1,227 @@
+use crate::types::*;
+
+/// Links task notifications to handlers and places each handler run at the
+/// flush point that triggers it.
+///
+/// Notifications collect until a `meta: flush_handlers` task or the end of
+/// the play. At each flush, every notified handler runs once per host no
+/// matter how many tasks notified it.
+pub struct HandlerScheduler;
+
+impl HandlerScheduler {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Fills in `notified_by` and `flushes` for each handler. Tasks are taken
+    /// in playbook order, so the order the optimizer chose does not move
+    /// tasks across flush points.
+    pub fn schedule(&self, tasks: &[TaskPlan], handlers: &mut [HandlerPlan]) {
+        let mut ordered: Vec<&TaskPlan> = tasks.iter().collect();
+        ordered.sort_by_key(|task| task.execution_order);
+
+        let mut pending: Vec<Option<HandlerFlush>> = vec![None; handlers.len()];
+
+        for task in ordered {
+            for target in &task.notify {
+                let matched = self.resolve(target, handlers);
+                if matched.is_empty() {
+                    tracing::warn!(
+                        "Task '{}' notifies '{}' which matches no handler",
+                        task.task_id,
+                        target
+                    );
+                }
+
+                for index in matched {
+                    let handler = &mut handlers[index];
+                    if !handler.notified_by.contains(&task.task_id) {
+                        handler.notified_by.push(task.task_id.clone());
+                    }
+
+                    let flush = pending[index].get_or_insert_with(|| HandlerFlush {
+                        flush_task: None,
+                        hosts: Vec::new(),
+                        notified_by: Vec::new(),
+                    });
+                    if !flush.notified_by.contains(&task.task_id) {
+                        flush.notified_by.push(task.task_id.clone());
+                    }
+                    for host in &task.hosts {
+                        if !flush.hosts.contains(host) {
+                            flush.hosts.push(host.clone());
+                        }
+                    }
+                }
+            }
+
+            if is_flush_handlers(task) {
+                self.flush(&mut pending, handlers, Some(&task.task_id));
+            }
+        }
+
+        self.flush(&mut pending, handlers, None);
+    }
+
+    /// Indices of the handlers a notify target refers to: the handler with
+    /// that name and every handler listening on it as a topic.
+    pub fn resolve(&self, target: &str, handlers: &[HandlerPlan]) -> Vec<usize> {
+        handlers
+            .iter()
+            .enumerate()
+            .filter(|(_, handler)| {
+                handler.name == target || handler.listen.iter().any(|topic| topic == target)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn flush(
+        &self,
+        pending: &mut [Option<HandlerFlush>],
+        handlers: &mut [HandlerPlan],
+        flush_task: Option<&str>,
+    ) {
+        for (index, slot) in pending.iter_mut().enumerate() {
+            if let Some(mut flush) = slot.take() {
+                // Notifying tasks that target no hosts leave nothing to run
+                if flush.hosts.is_empty() {
+                    continue;
+                }
+                flush.flush_task = flush_task.map(str::to_string);
+                handlers[index].flushes.push(flush);
+            }
+        }
+    }
+}
+
+impl Default for HandlerScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_flush_handlers(task: &TaskPlan) -> bool {
+    task.module == "meta"
+        && task
+            .args
+            .values()
+            .any(|value| value.as_str().map(str::trim) == Some("flush_handlers"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::fixtures;
+
+    fn create_test_task(id: &str, order: u32, notify: &[&str], hosts: &[&str]) -> TaskPlan {
+        TaskPlan {
+            module: "copy".to_string(),
+            hosts: fixtures::strings(hosts),
+            notify: fixtures::strings(notify),
+            execution_order: order,
+            ..fixtures::task(id)
+        }
+    }
+
+    fn create_flush_task(id: &str, order: u32) -> TaskPlan {
+        let mut task = create_test_task(id, order, &[], &["host1", "host2"]);
+        task.module = "meta".to_string();
+        task.args.insert(
+            "_raw_params".to_string(),
+            serde_json::Value::String("flush_handlers".to_string()),
+        );
+        task
+    }
+
+    fn create_test_handler(name: &str, listen: &[&str]) -> HandlerPlan {
+        HandlerPlan {
+            listen: fixtures::strings(listen),
+            ..fixtures::handler(name)
+        }
+    }
+
+    #[test]
+    fn test_notify_by_name_and_topic() {
+        let tasks = vec![
+            create_test_task("t1", 0, &["restart nginx"], &["host1"]),
+            create_test_task("t2", 1, &["web changed"], &["host2"]),
+        ];
+        let mut handlers = vec![
+            create_test_handler("restart nginx", &["web changed"]),
+            create_test_handler("reload haproxy", &["web changed"]),
+        ];
+
+        HandlerScheduler::new().schedule(&tasks, &mut handlers);
+
+        assert_eq!(handlers[0].notified_by, vec!["t1", "t2"]);
+        assert_eq!(handlers[1].notified_by, vec!["t2"]);
+        assert_eq!(handlers[0].flushes.len(), 1);
+        assert_eq!(handlers[0].flushes[0].flush_task, None);
+        assert_eq!(handlers[0].flushes[0].hosts, vec!["host1", "host2"]);
+    }
+
+    #[test]
+    fn test_deduplicated_per_host() {
+        let tasks = vec![
+            create_test_task("t1", 0, &["restart nginx"], &["host1", "host2"]),
+            create_test_task("t2", 1, &["restart nginx"], &["host1"]),
+        ];
+        let mut handlers = vec![create_test_handler("restart nginx", &[])];
+
+        HandlerScheduler::new().schedule(&tasks, &mut handlers);
+
+        let flushes = &handlers[0].flushes;
+        assert_eq!(flushes.len(), 1);
+        assert_eq!(flushes[0].hosts, vec!["host1", "host2"]);
+        assert_eq!(flushes[0].notified_by, vec!["t1", "t2"]);
+    }
+
+    #[test]
+    fn test_flush_handlers_splits_runs() {
+        let tasks = vec![
+            create_test_task("t1", 0, &["restart nginx"], &["host1"]),
+            create_flush_task("flush", 1),
+            create_test_task("t2", 2, &["restart nginx"], &["host2"]),
+            create_test_task("t3", 3, &[], &["host1"]),
+        ];
+        let mut handlers = vec![create_test_handler("restart nginx", &[])];
+
+        HandlerScheduler::new().schedule(&tasks, &mut handlers);
+
+        let flushes = &handlers[0].flushes;
+        assert_eq!(flushes.len(), 2);
+        assert_eq!(flushes[0].flush_task.as_deref(), Some("flush"));
+        assert_eq!(flushes[0].hosts, vec!["host1"]);
+        assert_eq!(flushes[1].flush_task, None);
+        assert_eq!(flushes[1].notified_by, vec!["t2"]);
+    }
+
+    #[test]
+    fn test_playbook_order_is_used() {
+        // The optimizer may have moved t2 ahead of the flush task
+        let tasks = vec![
+            create_test_task("t2", 2, &["restart nginx"], &["host1"]),
+            create_flush_task("flush", 1),
+            create_test_task("t1", 0, &["restart nginx"], &["host1"]),
+        ];
+        let mut handlers = vec![create_test_handler("restart nginx", &[])];
+
+        HandlerScheduler::new().schedule(&tasks, &mut handlers);
+
+        let flushes = &handlers[0].flushes;
+        assert_eq!(flushes[0].notified_by, vec!["t1"]);
+        assert_eq!(flushes[1].notified_by, vec!["t2"]);
+    }
+
+    #[test]
+    fn test_unresolved_notify_is_ignored() {
+        let tasks = vec![create_test_task("t1", 0, &["restart ngnix"], &["host1"])];
+        let mut handlers = vec![create_test_handler("restart nginx", &[])];
+
+        HandlerScheduler::new().schedule(&tasks, &mut handlers);
+
+        assert!(handlers[0].notified_by.is_empty());
+        assert!(handlers[0].flushes.is_empty());
+    }
+}
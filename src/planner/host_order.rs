@@ -0,0 +1,186 @@
+use crate::planner::error::PlanError;
+use crate::planner::inventory::InventoryResolver;
+use crate::types::*;
+use serde_json::Value;
+
+/// Puts a play's hosts in the order set by its `order` keyword.
+pub struct HostOrderer<'a> {
+    resolver: InventoryResolver<'a>,
+}
+
+impl<'a> HostOrderer<'a> {
+    pub fn new(inventory: &'a ParsedInventory) -> Self {
+        Self {
+            resolver: InventoryResolver::new(inventory),
+        }
+    }
+
+    /// Reorders `hosts`, which are expected in inventory order. An unseeded
+    /// shuffle keeps inventory order; seed it first with `HostOrder::with_seed`.
+    pub fn order(&self, hosts: &[String], order: &HostOrder) -> Result<Vec<String>, PlanError> {
+        let mut ordered = hosts.to_vec();
+        match order {
+            HostOrder::Inventory | HostOrder::Shuffle { seed: None } => {}
+            HostOrder::ReverseInventory => ordered.reverse(),
+            HostOrder::Sorted => ordered.sort(),
+            HostOrder::ReverseSorted => ordered.sort_by(|a, b| b.cmp(a)),
+            HostOrder::Shuffle { seed: Some(seed) } => shuffle(&mut ordered, *seed),
+            HostOrder::HostPriority => {
+                let mut prioritized = Vec::with_capacity(ordered.len());
+                for host in ordered {
+                    prioritized.push((self.priority(&host)?, host));
+                }
+                // Stable, so hosts of equal priority keep inventory order
+                prioritized.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+                ordered = prioritized.into_iter().map(|(_, host)| host).collect();
+            }
+        }
+        Ok(ordered)
+    }
+
+    fn priority(&self, host: &str) -> Result<f64, PlanError> {
+        let vars = self.resolver.host_variables(host)?;
+        let priority = match vars.get("host_priority") {
+            None | Some(Value::Null) => Some(0.0),
+            Some(Value::Number(number)) => number.as_f64(),
+            Some(Value::String(text)) => text.trim().parse().ok(),
+            Some(_) => None,
+        };
+
+        priority.ok_or_else(|| PlanError::InvalidHostPriority {
+            host: host.to_string(),
+            value: vars["host_priority"].to_string(),
+        })
+    }
+}
+
+/// Fisher-Yates shuffle driven by splitmix64, so a seed gives the same
+/// order on every platform and release.
+fn shuffle(hosts: &mut [String], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    for i in (1..hosts.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        hosts.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn create_inventory(priorities: &[(&str, Value)]) -> ParsedInventory {
+        ParsedInventory {
+            hosts: vec!["web2".to_string(), "db1".to_string(), "web1".to_string()],
+            groups: HashMap::new(),
+            vars: HashMap::new(),
+            host_facts: HashMap::new(),
+            group_children: HashMap::new(),
+            group_vars: HashMap::new(),
+            host_vars: priorities
+                .iter()
+                .map(|(host, priority)| {
+                    (
+                        host.to_string(),
+                        HashMap::from([("host_priority".to_string(), priority.clone())]),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn order(inventory: &ParsedInventory, order: HostOrder) -> Vec<String> {
+        HostOrderer::new(inventory)
+            .order(&inventory.hosts, &order)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_inventory_and_sorted_orders() {
+        let inventory = create_inventory(&[]);
+
+        assert_eq!(
+            order(&inventory, HostOrder::Inventory),
+            vec!["web2", "db1", "web1"]
+        );
+        assert_eq!(
+            order(&inventory, HostOrder::ReverseInventory),
+            vec!["web1", "db1", "web2"]
+        );
+        assert_eq!(
+            order(&inventory, HostOrder::Sorted),
+            vec!["db1", "web1", "web2"]
+        );
+        assert_eq!(
+            order(&inventory, HostOrder::ReverseSorted),
+            vec!["web2", "web1", "db1"]
+        );
+    }
+
+    #[test]
+    fn test_seeded_shuffle_is_reproducible() {
+        let mut inventory = create_inventory(&[]);
+        inventory.hosts = (1..=20).map(|i| format!("host{i}")).collect();
+
+        let first = order(&inventory, HostOrder::Shuffle { seed: Some(7) });
+        let second = order(&inventory, HostOrder::Shuffle { seed: Some(7) });
+        let other = order(&inventory, HostOrder::Shuffle { seed: Some(8) });
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_ne!(first, inventory.hosts);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        let mut expected = inventory.hosts.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn test_host_priority_puts_critical_hosts_last() {
+        let inventory = create_inventory(&[("db1", Value::from(10)), ("web1", Value::from("-1"))]);
+
+        assert_eq!(
+            order(&inventory, HostOrder::HostPriority),
+            vec!["web1", "web2", "db1"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_host_priority() {
+        let inventory = create_inventory(&[("db1", Value::from("high"))]);
+
+        let result = HostOrderer::new(&inventory).order(&inventory.hosts, &HostOrder::HostPriority);
+        assert!(matches!(
+            result,
+            Err(PlanError::InvalidHostPriority { host, .. }) if host == "db1"
+        ));
+    }
+
+    #[test]
+    fn test_parse_host_order() {
+        assert_eq!("sorted".parse(), Ok(HostOrder::Sorted));
+        assert_eq!(
+            "shuffle:42".parse(),
+            Ok(HostOrder::Shuffle { seed: Some(42) })
+        );
+        assert_eq!(
+            HostOrder::Shuffle { seed: Some(42) }.to_string(),
+            "shuffle:42"
+        );
+        assert!("random".parse::<HostOrder>().is_err());
+        assert_eq!(
+            HostOrder::Shuffle { seed: None }.with_seed(3),
+            HostOrder::Shuffle { seed: Some(3) }
+        );
+    }
+}
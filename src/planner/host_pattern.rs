@@ -0,0 +1,528 @@
+use crate::planner::error::PlanError;
+use crate::planner::inventory::InventoryResolver;
+use crate::types::*;
+use regex::Regex;
+
+/// Resolves Ansible host patterns against an inventory.
+///
+/// Supports `all`/`*`, group and host names, globs (`web*`), regexes
+/// (`~web\d+`), unions (`a:b` or `a,b`), intersections (`a:&b`), exclusions
+/// (`a:!b`) and inclusive index slices (`web[0]`, `web[0:2]`, `web[-1]`).
+pub struct HostPatternResolver<'a> {
+    inventory: &'a ParsedInventory,
+    groups: InventoryResolver<'a>,
+    all_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operation {
+    Union,
+    Intersection,
+    Exclusion,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Subscript {
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+}
+
+impl<'a> HostPatternResolver<'a> {
+    pub fn new(inventory: &'a ParsedInventory) -> Self {
+        let mut all_hosts = inventory.hosts.clone();
+        let mut group_names: Vec<&String> = inventory.groups.keys().collect();
+        group_names.sort();
+        for group in group_names {
+            for host in &inventory.groups[group] {
+                if !all_hosts.contains(host) {
+                    all_hosts.push(host.clone());
+                }
+            }
+        }
+
+        Self {
+            inventory,
+            groups: InventoryResolver::new(inventory),
+            all_hosts,
+        }
+    }
+
+    /// All hosts known to the inventory, in inventory order.
+    pub fn all_hosts(&self) -> &[String] {
+        &self.all_hosts
+    }
+
+    /// Resolves a single pattern string, which may combine several terms.
+    pub fn resolve(&self, pattern: &str) -> Result<Vec<String>, PlanError> {
+        let terms = split_pattern(pattern);
+        self.evaluate_terms(pattern, &terms)
+    }
+
+    /// Resolves a list of patterns as a union, like a play's `hosts:` list.
+    pub fn resolve_all(&self, patterns: &[String]) -> Result<Vec<String>, PlanError> {
+        let terms: Vec<String> = patterns.iter().flat_map(|p| split_pattern(p)).collect();
+        self.evaluate_terms(&patterns.join(","), &terms)
+    }
+
+    /// Resolves a `--limit` expression. Terms of the form `@path` are read
+    /// from a file containing one pattern per line.
+    pub fn resolve_limit(&self, limit: &str) -> Result<Vec<String>, PlanError> {
+        let mut terms = Vec::new();
+        for term in split_pattern(limit) {
+            if let Some(path) = term.strip_prefix('@') {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| invalid(limit, format!("cannot read limit file '{path}': {e}")))?;
+                terms.extend(
+                    content
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(str::to_string),
+                );
+            } else {
+                terms.push(term);
+            }
+        }
+        self.evaluate_terms(limit, &terms)
+    }
+
+    fn evaluate_terms(&self, pattern: &str, terms: &[String]) -> Result<Vec<String>, PlanError> {
+        let mut parsed = Vec::new();
+        for term in terms {
+            let (operation, body) = if let Some(rest) = term.strip_prefix('&') {
+                (Operation::Intersection, rest)
+            } else if let Some(rest) = term.strip_prefix('!') {
+                (Operation::Exclusion, rest)
+            } else {
+                (Operation::Union, term.as_str())
+            };
+
+            if body.is_empty() {
+                return Err(invalid(
+                    pattern,
+                    format!("empty host pattern term '{term}'"),
+                ));
+            }
+            parsed.push((operation, body));
+        }
+
+        if parsed.is_empty() {
+            return Err(invalid(pattern, "pattern is empty".to_string()));
+        }
+
+        // Like Ansible, apply unions first, then intersections, then exclusions,
+        // regardless of the order they were written in. A pattern made only of
+        // intersections and exclusions starts from all hosts.
+        let mut hosts: Vec<String> = if parsed.iter().any(|(op, _)| *op == Operation::Union) {
+            Vec::new()
+        } else {
+            self.all_hosts.clone()
+        };
+
+        for operation in [
+            Operation::Union,
+            Operation::Intersection,
+            Operation::Exclusion,
+        ] {
+            for (_, body) in parsed.iter().filter(|(op, _)| *op == operation) {
+                let matched = self.match_term(pattern, body)?;
+                match operation {
+                    Operation::Union => {
+                        for host in matched {
+                            if !hosts.contains(&host) {
+                                hosts.push(host);
+                            }
+                        }
+                    }
+                    Operation::Intersection => hosts.retain(|host| matched.contains(host)),
+                    Operation::Exclusion => hosts.retain(|host| !matched.contains(host)),
+                }
+            }
+        }
+
+        Ok(hosts)
+    }
+
+    fn match_term(&self, pattern: &str, term: &str) -> Result<Vec<String>, PlanError> {
+        let (base, subscript) = split_subscript(pattern, term)?;
+
+        let hosts = if let Some(expression) = base.strip_prefix('~') {
+            let regex = Regex::new(expression)
+                .map_err(|e| invalid(pattern, format!("invalid regex '{expression}': {e}")))?;
+            self.match_with(|name| regex.is_match(name))?
+        } else if base == "all" || base == "*" {
+            self.all_hosts.clone()
+        } else if let Some(hosts) = self.group_hosts(base)? {
+            hosts
+        } else if self.all_hosts.iter().any(|host| host == base) {
+            vec![base.to_string()]
+        } else if base.contains(['*', '?', '[']) {
+            let regex = glob_to_regex(base)
+                .map_err(|e| invalid(pattern, format!("invalid glob '{base}': {e}")))?;
+            self.match_with(|name| regex.is_match(name))?
+        } else {
+            Vec::new()
+        };
+
+        match subscript {
+            Some(subscript) => apply_subscript(pattern, base, hosts, subscript),
+            None => Ok(hosts),
+        }
+    }
+
+    fn group_hosts(&self, group: &str) -> Result<Option<Vec<String>>, PlanError> {
+        if self.groups.has_group(group) {
+            return self.groups.group_hosts(group).map(Some);
+        }
+
+        if group == "ungrouped" {
+            return Ok(Some(
+                self.all_hosts
+                    .iter()
+                    .filter(|host| {
+                        !self
+                            .inventory
+                            .groups
+                            .iter()
+                            .any(|(name, hosts)| name != "all" && hosts.contains(host))
+                    })
+                    .cloned()
+                    .collect(),
+            ));
+        }
+
+        Ok(None)
+    }
+
+    fn match_with<F>(&self, matches: F) -> Result<Vec<String>, PlanError>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut hosts = Vec::new();
+
+        for group in self.groups.group_names().iter().filter(|g| matches(g)) {
+            for host in self.group_hosts(group)?.unwrap_or_default() {
+                if !hosts.contains(&host) {
+                    hosts.push(host);
+                }
+            }
+        }
+
+        for host in self.all_hosts.iter().filter(|h| matches(h)) {
+            if !hosts.contains(host) {
+                hosts.push(host.clone());
+            }
+        }
+
+        Ok(hosts)
+    }
+}
+
+fn invalid(pattern: &str, reason: String) -> PlanError {
+    PlanError::InvalidHostPattern {
+        pattern: pattern.to_string(),
+        reason,
+    }
+}
+
+/// Splits a pattern into terms on commas, or on colons when there are no
+/// commas. Colons inside `[...]` subscripts never split.
+fn split_pattern(pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+
+    let separator = if pattern.contains(',') { ',' } else { ':' };
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+
+    for c in pattern.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if c == separator && depth == 0 {
+            terms.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    terms.push(current.trim().to_string());
+
+    terms
+}
+
+fn split_subscript<'p>(
+    pattern: &str,
+    term: &'p str,
+) -> Result<(&'p str, Option<Subscript>), PlanError> {
+    // Regexes may legitimately end in a character class
+    if term.starts_with('~') || !term.ends_with(']') {
+        return Ok((term, None));
+    }
+
+    let Some(open) = term.rfind('[') else {
+        return Err(invalid(pattern, format!("unbalanced ']' in '{term}'")));
+    };
+    let base = &term[..open];
+    let inner = &term[open + 1..term.len() - 1];
+
+    // `web[abc]` is a glob character class, not a subscript
+    if !inner
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '-' || c == ':')
+    {
+        return Ok((term, None));
+    }
+    if base.is_empty() {
+        return Err(invalid(pattern, format!("subscript '{term}' has no group")));
+    }
+
+    let parse_bound = |bound: &str| -> Result<Option<i64>, PlanError> {
+        if bound.is_empty() {
+            Ok(None)
+        } else {
+            bound.parse().map(Some).map_err(|_| {
+                invalid(
+                    pattern,
+                    format!("invalid subscript bound '{bound}' in '{term}'"),
+                )
+            })
+        }
+    };
+
+    let subscript = match inner.split_once(':') {
+        Some((start, end)) => {
+            if end.contains(':') {
+                return Err(invalid(pattern, format!("invalid subscript in '{term}'")));
+            }
+            Subscript::Slice(parse_bound(start)?, parse_bound(end)?)
+        }
+        None => match parse_bound(inner)? {
+            Some(index) => Subscript::Index(index),
+            None => return Err(invalid(pattern, format!("empty subscript in '{term}'"))),
+        },
+    };
+
+    Ok((base, Some(subscript)))
+}
+
+fn apply_subscript(
+    pattern: &str,
+    base: &str,
+    hosts: Vec<String>,
+    subscript: Subscript,
+) -> Result<Vec<String>, PlanError> {
+    let len = hosts.len() as i64;
+    let normalize = |index: i64| if index < 0 { len + index } else { index };
+
+    match subscript {
+        Subscript::Index(index) => {
+            let position = normalize(index);
+            if position < 0 || position >= len {
+                return Err(invalid(
+                    pattern,
+                    format!("index {index} is out of range for '{base}' ({len} hosts)"),
+                ));
+            }
+            Ok(vec![hosts[position as usize].clone()])
+        }
+        Subscript::Slice(start, end) => {
+            // Ansible slices include the end index
+            let start = normalize(start.unwrap_or(0)).clamp(0, len);
+            let end = normalize(end.unwrap_or(-1)).clamp(-1, len - 1);
+            if end < start {
+                return Ok(Vec::new());
+            }
+            Ok(hosts[start as usize..=end as usize].to_vec())
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> Result<Regex, String> {
+    let mut expression = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => expression.push_str(".*"),
+            '?' => expression.push('.'),
+            '[' => {
+                expression.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    expression.push('^');
+                }
+                let mut closed = false;
+                for class_char in chars.by_ref() {
+                    if class_char == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if class_char == '\\' || class_char == '[' {
+                        expression.push('\\');
+                    }
+                    expression.push(class_char);
+                }
+                if !closed {
+                    return Err("unclosed '['".to_string());
+                }
+                expression.push(']');
+            }
+            other => expression.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+
+    expression.push('$');
+    Regex::new(&expression).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn create_test_inventory() -> ParsedInventory {
+        let hosts: Vec<String> = ["web1", "web2", "web3", "db1", "db2", "lb1"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+
+        let mut groups = HashMap::new();
+        groups.insert(
+            "webservers".to_string(),
+            vec!["web1".to_string(), "web2".to_string(), "web3".to_string()],
+        );
+        groups.insert(
+            "dbservers".to_string(),
+            vec!["db1".to_string(), "db2".to_string()],
+        );
+        groups.insert(
+            "production".to_string(),
+            vec!["web1".to_string(), "db1".to_string()],
+        );
+
+        ParsedInventory {
+            hosts,
+            groups,
+            vars: HashMap::new(),
+            host_facts: HashMap::new(),
+            group_children: HashMap::new(),
+            group_vars: HashMap::new(),
+            host_vars: HashMap::new(),
+        }
+    }
+
+    fn resolve(pattern: &str) -> Vec<String> {
+        let inventory = create_test_inventory();
+        HostPatternResolver::new(&inventory)
+            .resolve(pattern)
+            .unwrap()
+    }
+
+    fn resolve_err(pattern: &str) -> String {
+        let inventory = create_test_inventory();
+        match HostPatternResolver::new(&inventory).resolve(pattern) {
+            Err(PlanError::InvalidHostPattern { reason, .. }) => reason,
+            other => panic!("expected InvalidHostPattern for {pattern:?}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_all_and_names() {
+        assert_eq!(resolve("all").len(), 6);
+        assert_eq!(resolve("*").len(), 6);
+        assert_eq!(resolve("webservers"), vec!["web1", "web2", "web3"]);
+        assert_eq!(resolve("db2"), vec!["db2"]);
+        assert_eq!(resolve("ungrouped"), vec!["lb1"]);
+        assert!(resolve("missing").is_empty());
+    }
+
+    #[test]
+    fn test_globs_and_regexes() {
+        assert_eq!(resolve("web*"), vec!["web1", "web2", "web3"]);
+        assert_eq!(resolve("db?"), vec!["db1", "db2"]);
+        assert_eq!(resolve("web[!2]"), vec!["web1", "web3"]);
+        assert_eq!(resolve("~(web|db)1"), vec!["web1", "db1"]);
+        assert_eq!(resolve("~^db"), vec!["db1", "db2"]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        assert_eq!(
+            resolve("webservers:dbservers"),
+            vec!["web1", "web2", "web3", "db1", "db2"]
+        );
+        assert_eq!(
+            resolve("webservers,lb1"),
+            vec!["web1", "web2", "web3", "lb1"]
+        );
+        assert_eq!(resolve("webservers:&production"), vec!["web1"]);
+        assert_eq!(resolve("webservers:!production"), vec!["web2", "web3"]);
+        // Exclusions apply after unions regardless of position
+        assert_eq!(resolve("!production:webservers"), vec!["web2", "web3"]);
+        assert_eq!(resolve("!webservers"), vec!["db1", "db2", "lb1"]);
+    }
+
+    #[test]
+    fn test_subscripts() {
+        assert_eq!(resolve("webservers[0]"), vec!["web1"]);
+        assert_eq!(resolve("webservers[-1]"), vec!["web3"]);
+        assert_eq!(resolve("webservers[0:1]"), vec!["web1", "web2"]);
+        assert_eq!(resolve("webservers[1:]"), vec!["web2", "web3"]);
+        assert_eq!(resolve("webservers[:1]"), vec!["web1", "web2"]);
+        assert_eq!(resolve("webservers[0:5]"), vec!["web1", "web2", "web3"]);
+        assert_eq!(
+            resolve("webservers[1:2]:dbservers[0]"),
+            vec!["web2", "web3", "db1"]
+        );
+    }
+
+    #[test]
+    fn test_malformed_patterns() {
+        assert!(resolve_err("webservers::dbservers").contains("empty host pattern term"));
+        assert!(resolve_err("webservers:!").contains("empty host pattern term"));
+        assert!(resolve_err("~web(").contains("invalid regex"));
+        assert!(resolve_err("webservers[5]").contains("out of range"));
+        assert!(resolve_err("webservers[1:2:3]").contains("invalid subscript"));
+        assert!(resolve_err("webservers[-]").contains("invalid subscript bound"));
+        assert!(resolve_err("[0]").contains("has no group"));
+        assert!(resolve_err("webservers[0:").contains("unclosed '['"));
+        assert!(resolve_err("").contains("pattern is empty"));
+    }
+
+    #[test]
+    fn test_resolve_all_play_hosts() {
+        let inventory = create_test_inventory();
+        let resolver = HostPatternResolver::new(&inventory);
+        let hosts = resolver
+            .resolve_all(&["dbservers".to_string(), "lb1".to_string()])
+            .unwrap();
+        assert_eq!(hosts, vec!["db1", "db2", "lb1"]);
+    }
+
+    #[test]
+    fn test_resolve_limit_from_file() {
+        let inventory = create_test_inventory();
+        let resolver = HostPatternResolver::new(&inventory);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("retry");
+        std::fs::write(&path, "web2\n# comment\n\ndb1\n").unwrap();
+
+        let hosts = resolver
+            .resolve_limit(&format!("@{}", path.display()))
+            .unwrap();
+        assert_eq!(hosts, vec!["web2", "db1"]);
+
+        let missing = resolver.resolve_limit("@/nonexistent/retry-file");
+        assert!(matches!(
+            missing,
+            Err(PlanError::InvalidHostPattern { reason, .. }) if reason.contains("cannot read limit file")
+        ));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::planner::error::PlanError;
+use crate::types::*;
+
+/// Computes and checks `ExecutionPlan.plan_digest`.
+///
+/// The digest is the SHA-256 of the plan's canonical JSON with
+/// `plan_digest` itself and any signature left out, so it survives a round
+/// trip through JSON and signing, and changes whenever anything else in the
+/// plan does.
+pub struct PlanIntegrity;
+
+impl PlanIntegrity {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn digest(&self, plan: &ExecutionPlan) -> Result<String, PlanError> {
+        let mut value = serde_json::to_value(plan)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("plan_digest");
+        }
+        if let Some(metadata) = value["metadata"].as_object_mut() {
+            metadata.remove("signature");
+        }
+        Ok(sha256_digest(canonical_json(&value)?.as_bytes()))
+    }
+
+    /// Records the plan's digest in `plan_digest`.
+    pub fn seal(&self, plan: &mut ExecutionPlan) -> Result<(), PlanError> {
+        plan.plan_digest = Some(self.digest(plan)?);
+        Ok(())
+    }
+
+    /// Checks that `plan_digest` matches the plan's content.
+    pub fn verify(&self, plan: &ExecutionPlan) -> Result<(), PlanError> {
+        let expected = plan
+            .plan_digest
+            .clone()
+            .ok_or(PlanError::MissingPlanDigest)?;
+        let actual = self.digest(plan)?;
+
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(PlanError::PlanDigestMismatch { expected, actual })
+        }
+    }
+}
+
+impl Default for PlanIntegrity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
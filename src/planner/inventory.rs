@@ -0,0 +1,302 @@
+use crate::planner::error::PlanError;
+use crate::types::*;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Resolves the group hierarchy of an inventory: nested `children`, the
+/// hosts each group expands to, and the variables each host ends up with.
+pub struct InventoryResolver<'a> {
+    inventory: &'a ParsedInventory,
+}
+
+impl<'a> InventoryResolver<'a> {
+    pub fn new(inventory: &'a ParsedInventory) -> Self {
+        Self { inventory }
+    }
+
+    /// Checks that no group is nested inside itself.
+    pub fn validate(&self) -> Result<(), PlanError> {
+        let mut groups: Vec<&String> = self.inventory.group_children.keys().collect();
+        groups.sort();
+        for group in groups {
+            self.visit(group, &mut Vec::new(), &mut |_| {})?;
+        }
+        Ok(())
+    }
+
+    /// Every group named in the inventory, sorted by name.
+    pub fn group_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .inventory
+            .groups
+            .keys()
+            .chain(self.inventory.group_children.keys())
+            .chain(self.inventory.group_children.values().flatten())
+            .chain(self.inventory.group_vars.keys())
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn has_group(&self, group: &str) -> bool {
+        self.inventory.groups.contains_key(group)
+            || self.inventory.group_children.contains_key(group)
+            || self.inventory.group_vars.contains_key(group)
+            || self
+                .inventory
+                .group_children
+                .values()
+                .any(|children| children.iter().any(|child| child == group))
+    }
+
+    /// Hosts in a group, including those of its child groups at any depth.
+    /// Direct members come first, then each child's hosts in declaration order.
+    pub fn group_hosts(&self, group: &str) -> Result<Vec<String>, PlanError> {
+        let mut hosts: Vec<String> = Vec::new();
+        self.visit(group, &mut Vec::new(), &mut |name| {
+            for host in self.inventory.groups.get(name).into_iter().flatten() {
+                if !hosts.contains(host) {
+                    hosts.push(host.clone());
+                }
+            }
+        })?;
+        Ok(hosts)
+    }
+
+    /// Groups a host belongs to, directly or through a child group, ordered
+    /// from least to most specific. `all` is always first.
+    pub fn host_groups(&self, host: &str) -> Result<Vec<String>, PlanError> {
+        let mut groups = Vec::new();
+        for group in self.group_names() {
+            if group != "all" && self.group_hosts(&group)?.iter().any(|h| h == host) {
+                groups.push(group);
+            }
+        }
+
+        let mut depths = HashMap::new();
+        for group in &groups {
+            let depth = self.group_depth(group, &mut Vec::new())?;
+            depths.insert(group.clone(), depth);
+        }
+        // Stable sort keeps groups of equal depth in name order
+        groups.sort_by_key(|group| depths[group]);
+
+        groups.insert(0, "all".to_string());
+        Ok(groups)
+    }
+
+    /// Variables for a host, merged in Ansible precedence order: inventory
+    /// vars and `all`, then parent groups before child groups, then the host.
+    pub fn host_variables(&self, host: &str) -> Result<HashMap<String, Value>, PlanError> {
+        let mut variables = self.inventory.vars.clone();
+
+        for group in self.host_groups(host)? {
+            if let Some(vars) = self.inventory.group_vars.get(&group) {
+                variables.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+        }
+
+        if let Some(vars) = self.inventory.host_vars.get(host) {
+            variables.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        Ok(variables)
+    }
+
+    /// Length of the longest chain of parents above a group. Top-level
+    /// groups have depth 1; `all` has depth 0.
+    fn group_depth(&self, group: &str, path: &mut Vec<String>) -> Result<usize, PlanError> {
+        if group == "all" {
+            return Ok(0);
+        }
+        if path.iter().any(|g| g == group) {
+            return Err(cycle_error(path, group));
+        }
+
+        path.push(group.to_string());
+        let mut parents: Vec<&String> = self
+            .inventory
+            .group_children
+            .iter()
+            .filter(|(parent, children)| {
+                parent.as_str() != "all" && children.iter().any(|c| c == group)
+            })
+            .map(|(parent, _)| parent)
+            .collect();
+        parents.sort();
+
+        let mut depth = 1;
+        for parent in parents {
+            depth = depth.max(self.group_depth(parent, path)? + 1);
+        }
+        path.pop();
+
+        Ok(depth)
+    }
+
+    /// Depth-first walk over a group and its descendants, failing on cycles.
+    fn visit<F>(
+        &self,
+        group: &str,
+        path: &mut Vec<String>,
+        on_group: &mut F,
+    ) -> Result<(), PlanError>
+    where
+        F: FnMut(&str),
+    {
+        if path.iter().any(|g| g == group) {
+            return Err(cycle_error(path, group));
+        }
+
+        on_group(group);
+        path.push(group.to_string());
+        for child in self
+            .inventory
+            .group_children
+            .get(group)
+            .into_iter()
+            .flatten()
+        {
+            self.visit(child, path, on_group)?;
+        }
+        path.pop();
+
+        Ok(())
+    }
+}
+
+fn cycle_error(path: &[String], group: &str) -> PlanError {
+    let start = path.iter().position(|g| g == group).unwrap_or(0);
+    let mut cycle: Vec<&str> = path[start..].iter().map(String::as_str).collect();
+    cycle.push(group);
+    PlanError::InventoryGroupCycle {
+        cycle: cycle.join(" -> "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    fn create_test_inventory() -> ParsedInventory {
+        let mut groups = HashMap::new();
+        groups.insert("web_east".to_string(), vec!["web1".to_string()]);
+        groups.insert("web_west".to_string(), vec!["web2".to_string()]);
+        groups.insert("databases".to_string(), vec!["db1".to_string()]);
+
+        let mut group_children = HashMap::new();
+        group_children.insert(
+            "webservers".to_string(),
+            vec!["web_east".to_string(), "web_west".to_string()],
+        );
+        group_children.insert(
+            "production".to_string(),
+            vec!["webservers".to_string(), "databases".to_string()],
+        );
+
+        let mut group_vars = HashMap::new();
+        group_vars.insert(
+            "all".to_string(),
+            vars(&[("env", json!("default")), ("port", json!(80))]),
+        );
+        group_vars.insert(
+            "production".to_string(),
+            vars(&[("env", json!("production")), ("region", json!("global"))]),
+        );
+        group_vars.insert("webservers".to_string(), vars(&[("port", json!(8080))]));
+        group_vars.insert("web_east".to_string(), vars(&[("region", json!("east"))]));
+
+        let mut host_vars = HashMap::new();
+        host_vars.insert("web1".to_string(), vars(&[("port", json!(9000))]));
+
+        ParsedInventory {
+            hosts: vec!["web1".to_string(), "web2".to_string(), "db1".to_string()],
+            groups,
+            vars: vars(&[("owner", json!("ops"))]),
+            host_facts: HashMap::new(),
+            group_children,
+            group_vars,
+            host_vars,
+        }
+    }
+
+    #[test]
+    fn test_group_hosts_expand_children() {
+        let inventory = create_test_inventory();
+        let resolver = InventoryResolver::new(&inventory);
+
+        assert_eq!(
+            resolver.group_hosts("production").unwrap(),
+            vec!["web1", "web2", "db1"]
+        );
+        assert_eq!(
+            resolver.group_hosts("webservers").unwrap(),
+            vec!["web1", "web2"]
+        );
+        assert!(resolver.group_hosts("missing").unwrap().is_empty());
+        assert!(resolver.has_group("webservers"));
+        assert!(!resolver.has_group("missing"));
+    }
+
+    #[test]
+    fn test_host_groups_ordered_by_depth() {
+        let inventory = create_test_inventory();
+        let resolver = InventoryResolver::new(&inventory);
+
+        assert_eq!(
+            resolver.host_groups("web1").unwrap(),
+            vec!["all", "production", "webservers", "web_east"]
+        );
+        assert_eq!(
+            resolver.host_groups("db1").unwrap(),
+            vec!["all", "production", "databases"]
+        );
+    }
+
+    #[test]
+    fn test_variable_precedence() {
+        let inventory = create_test_inventory();
+        let resolver = InventoryResolver::new(&inventory);
+
+        let web1 = resolver.host_variables("web1").unwrap();
+        assert_eq!(web1["owner"], json!("ops"));
+        assert_eq!(web1["env"], json!("production"));
+        assert_eq!(web1["region"], json!("east"));
+        assert_eq!(web1["port"], json!(9000));
+
+        let web2 = resolver.host_variables("web2").unwrap();
+        assert_eq!(web2["region"], json!("global"));
+        assert_eq!(web2["port"], json!(8080));
+
+        let db1 = resolver.host_variables("db1").unwrap();
+        assert_eq!(db1["port"], json!(80));
+    }
+
+    #[test]
+    fn test_group_cycles_detected() {
+        let mut inventory = create_test_inventory();
+        inventory
+            .group_children
+            .insert("web_east".to_string(), vec!["production".to_string()]);
+        let resolver = InventoryResolver::new(&inventory);
+
+        match resolver.validate() {
+            Err(PlanError::InventoryGroupCycle { cycle }) => {
+                assert!(cycle.contains("production -> webservers -> web_east -> production"))
+            }
+            other => panic!("expected InventoryGroupCycle, got {other:?}"),
+        }
+        assert!(resolver.group_hosts("webservers").is_err());
+        assert!(resolver.host_variables("web1").is_err());
+    }
+}
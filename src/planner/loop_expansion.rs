@@ -0,0 +1,284 @@
+use crate::planner::expression::render_template;
+use crate::types::*;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub struct LoopExpander;
+
+impl LoopExpander {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Whether every loop item is known at plan time. A loop given as a
+    /// template string, or with templated items, depends on runtime data.
+    pub fn is_static(&self, loop_items: &Value) -> bool {
+        match loop_items {
+            Value::Array(items) => !items.iter().any(contains_template),
+            Value::Object(_) => !contains_template(loop_items),
+            _ => false,
+        }
+    }
+
+    /// Expands static loops into one task per item and rewires dependencies
+    /// on a looped task to depend on all of its items.
+    ///
+    /// Tasks without a loop are returned unchanged; runtime loops stay as a
+    /// single task marked `LoopExpansion::Runtime`.
+    pub fn expand(&self, tasks: &[ParsedTask]) -> Vec<(ParsedTask, Option<LoopExpansion>)> {
+        let mut expanded = Vec::new();
+        let mut instance_ids: HashMap<String, Vec<String>> = HashMap::new();
+
+        for task in tasks {
+            match &task.loop_items {
+                Some(loop_items) if self.is_static(loop_items) => {
+                    let items = loop_item_values(loop_items);
+                    let ids = items
+                        .iter()
+                        .enumerate()
+                        .map(|(index, _)| item_task_id(&task.id, index))
+                        .collect();
+                    instance_ids.insert(task.id.clone(), ids);
+
+                    for (index, item) in items.into_iter().enumerate() {
+                        let instance = self.expand_item(task, index, &item);
+                        let expansion = LoopExpansion::Item {
+                            parent_task_id: task.id.clone(),
+                            index,
+                            item,
+                        };
+                        expanded.push((instance, Some(expansion)));
+                    }
+                }
+                Some(_) => expanded.push((task.clone(), Some(LoopExpansion::Runtime))),
+                None => expanded.push((task.clone(), None)),
+            }
+        }
+
+        for (task, _) in &mut expanded {
+            task.dependencies = task
+                .dependencies
+                .iter()
+                .flat_map(|dependency| {
+                    instance_ids
+                        .get(dependency)
+                        .cloned()
+                        .unwrap_or_else(|| vec![dependency.clone()])
+                })
+                .collect();
+        }
+
+        expanded
+    }
+
+    fn expand_item(&self, task: &ParsedTask, index: usize, item: &Value) -> ParsedTask {
+        let lookup = |name: &str| (name == "item").then(|| item.clone());
+
+        let mut vars = task.vars.clone();
+        vars.insert("item".to_string(), item.clone());
+
+        ParsedTask {
+            id: item_task_id(&task.id, index),
+            name: item_task_name(&task.name, &item_label(item)),
+            module: task.module.clone(),
+            args: task
+                .args
+                .iter()
+                .map(|(key, value)| (key.clone(), render_value(value, &lookup)))
+                .collect(),
+            dependencies: task.dependencies.clone(),
+            tags: task.tags.clone(),
+            when: task.when.clone(),
+            notify: task.notify.clone(),
+            vars,
+            loop_items: None,
+            changed_when: task.changed_when.clone(),
+            failed_when: task.failed_when.clone(),
+            ignore_errors: task.ignore_errors,
+            no_log: task.no_log,
+            delegate_to: task.delegate_to.as_deref().map(|host| {
+                match render_template(host, lookup) {
+                    Value::String(host) => host,
+                    other => other.to_string(),
+                }
+            }),
+        }
+    }
+}
+
+impl Default for LoopExpander {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn item_task_id(task_id: &str, index: usize) -> String {
+    format!("{task_id}_item_{index}")
+}
+
+/// Name of a loop item's task, as Ansible shows it
+pub(crate) fn item_task_name(name: &str, label: &str) -> String {
+    format!("{name} (item={label})")
+}
+
+pub(crate) fn item_label(item: &Value) -> String {
+    match item {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Items of a static loop. Mappings iterate as `{key, value}` pairs, as
+/// with `with_dict`.
+fn loop_item_values(loop_items: &Value) -> Vec<Value> {
+    match loop_items {
+        Value::Array(items) => items.clone(),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| serde_json::json!({"key": key, "value": value}))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn contains_template(value: &Value) -> bool {
+    match value {
+        Value::String(text) => text.contains("{{"),
+        Value::Array(items) => items.iter().any(contains_template),
+        Value::Object(map) => map.values().any(contains_template),
+        _ => false,
+    }
+}
+
+fn render_value<F>(value: &Value, lookup: &F) -> Value
+where
+    F: Fn(&str) -> Option<Value>,
+{
+    match value {
+        Value::String(text) if text.contains("{{") => render_template(text, lookup),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| render_value(v, lookup)).collect())
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, v)| (key.clone(), render_value(v, lookup)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn create_test_task(id: &str, loop_items: Option<Value>) -> ParsedTask {
+        let mut args = HashMap::new();
+        args.insert("path".to_string(), json!("/srv/app/{{ item }}"));
+        args.insert("mode".to_string(), json!("0750"));
+
+        ParsedTask {
+            id: id.to_string(),
+            name: "Create directory".to_string(),
+            module: "file".to_string(),
+            args,
+            dependencies: vec![],
+            tags: vec![],
+            when: None,
+            notify: vec![],
+            vars: HashMap::new(),
+            loop_items,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+        }
+    }
+
+    #[test]
+    fn test_static_loop_expands_per_item() {
+        let expander = LoopExpander::new();
+        let task = create_test_task("dirs", Some(json!(["config", "logs"])));
+
+        let expanded = expander.expand(&[task]);
+        assert_eq!(expanded.len(), 2);
+
+        let (first, expansion) = &expanded[0];
+        assert_eq!(first.id, "dirs_item_0");
+        assert_eq!(first.name, "Create directory (item=config)");
+        assert_eq!(first.args["path"], json!("/srv/app/config"));
+        assert_eq!(first.args["mode"], json!("0750"));
+        assert_eq!(first.vars["item"], json!("config"));
+        assert!(first.loop_items.is_none());
+        assert_eq!(
+            expansion,
+            &Some(LoopExpansion::Item {
+                parent_task_id: "dirs".to_string(),
+                index: 0,
+                item: json!("config"),
+            })
+        );
+        assert_eq!(expanded[1].0.args["path"], json!("/srv/app/logs"));
+    }
+
+    #[test]
+    fn test_structured_items_keep_types() {
+        let expander = LoopExpander::new();
+        let mut task = create_test_task(
+            "users",
+            Some(json!([{"name": "alice", "uid": 1001}, {"name": "bob", "uid": 1002}])),
+        );
+        task.args.insert("uid".to_string(), json!("{{ item.uid }}"));
+        task.args
+            .insert("name".to_string(), json!("{{ item.name | upper }}"));
+
+        let expanded = expander.expand(&[task]);
+        assert_eq!(expanded[1].0.args["uid"], json!(1002));
+        assert_eq!(expanded[1].0.args["name"], json!("BOB"));
+    }
+
+    #[test]
+    fn test_runtime_loops_stay_collapsed() {
+        let expander = LoopExpander::new();
+        let templated = create_test_task("templated", Some(json!("{{ directories }}")));
+        let mixed = create_test_task("mixed", Some(json!(["static", "{{ dynamic }}"])));
+        let plain = create_test_task("plain", None);
+
+        let expanded = expander.expand(&[templated, mixed, plain]);
+        assert_eq!(expanded.len(), 3);
+        assert_eq!(expanded[0].1, Some(LoopExpansion::Runtime));
+        assert_eq!(expanded[1].1, Some(LoopExpansion::Runtime));
+        assert_eq!(expanded[2].1, None);
+        assert_eq!(expanded[0].0.args["path"], json!("/srv/app/{{ item }}"));
+    }
+
+    #[test]
+    fn test_dependencies_rewired_to_all_items() {
+        let expander = LoopExpander::new();
+        let dirs = create_test_task("dirs", Some(json!(["a", "b", "c"])));
+        let mut deploy = create_test_task("deploy", None);
+        deploy.dependencies = vec!["dirs".to_string(), "other".to_string()];
+
+        let expanded = expander.expand(&[dirs, deploy]);
+        assert_eq!(
+            expanded[3].0.dependencies,
+            vec!["dirs_item_0", "dirs_item_1", "dirs_item_2", "other"]
+        );
+    }
+
+    #[test]
+    fn test_mapping_loop_items() {
+        let expander = LoopExpander::new();
+        let mut task = create_test_task("sysctl", Some(json!({"vm.swappiness": 10})));
+        task.args.insert(
+            "value".to_string(),
+            json!("{{ item.key }}={{ item.value }}"),
+        );
+
+        let expanded = expander.expand(&[task]);
+        assert_eq!(expanded.len(), 1);
+        assert_eq!(expanded[0].0.args["value"], json!("vm.swappiness=10"));
+    }
+}
@@ -1,23 +1,57 @@
 pub mod binary_deployment;
+pub mod binary_format;
 pub mod condition;
+pub mod critical_path;
 pub mod dependency;
+pub mod diff;
 pub mod error;
 pub mod estimation;
 pub mod execution_plan;
+pub mod expression;
+pub mod failure_policy;
+#[cfg(test)]
+mod fixtures;
 pub mod graph;
+pub mod handlers;
+pub mod host_order;
+pub mod host_pattern;
+pub mod integrity;
+pub mod inventory;
+pub mod loop_expansion;
 pub mod optimization;
+pub mod schema;
+pub mod secrets;
+pub mod signing;
 pub mod strategy;
 pub mod suitability;
+pub mod timeline;
 pub mod validation;
+pub mod visualization;
 
 pub use binary_deployment::*;
+pub use binary_format::*;
 pub use condition::*;
+pub use critical_path::*;
 pub use dependency::*;
+pub use diff::*;
 pub use error::*;
 pub use estimation::*;
 pub use execution_plan::*;
+pub use expression::*;
+pub use failure_policy::*;
 pub use graph::*;
+pub use handlers::*;
+pub use host_order::*;
+pub use host_pattern::*;
+pub use integrity::*;
+pub use inventory::*;
+pub use loop_expansion::*;
 pub use optimization::*;
+pub use schema::*;
+pub use secrets::*;
+pub use signing::*;
 pub use strategy::*;
 pub use suitability::*;
+pub use timeline::*;
 pub use validation::*;
+pub use visualization::*;
@@ -0,0 +1,183 @@
+use crate::planner::error::PlanError;
+use crate::types::*;
+use serde_json::{json, Value};
+
+/// JSON Schema for `ExecutionPlan`, for tools that read plans without this
+/// crate.
+pub fn execution_plan_schema() -> Value {
+    schemars::schema_for!(ExecutionPlan).to_value()
+}
+
+/// Steps that upgrade a plan by one schema version; the first upgrades
+/// version 1 to version 2.
+const MIGRATIONS: [fn(&mut Value); 1] = [serial_to_ramps];
+
+/// Upgrades plans written by older releases to the current `ExecutionPlan`
+/// layout.
+///
+/// Migrations work on the plan's JSON, so they can handle fields that no
+/// longer deserialize. An upgraded plan's `plan_digest` and signature still
+/// cover the original plan, so they no longer verify.
+pub struct PlanMigrator;
+
+impl PlanMigrator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// The schema version `plan` was written with
+    pub fn schema_version(&self, plan: &Value) -> Result<u32, PlanError> {
+        let metadata = plan
+            .get("metadata")
+            .and_then(Value::as_object)
+            .ok_or_else(|| invalid("missing metadata"))?;
+
+        match metadata.get("schema_version") {
+            None => Ok(1),
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .filter(|version| *version > 0)
+                .ok_or_else(|| invalid(&format!("invalid schema_version {version}"))),
+        }
+    }
+
+    /// Rewrites `plan` in the current layout, leaving current plans as they
+    /// are.
+    pub fn upgrade(&self, mut plan: Value) -> Result<Value, PlanError> {
+        let version = self.schema_version(&plan)?;
+        if version > PLAN_SCHEMA_VERSION {
+            return Err(PlanError::UnsupportedSchemaVersion {
+                version,
+                supported: PLAN_SCHEMA_VERSION,
+            });
+        }
+
+        for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+            migrate(&mut plan);
+            plan["metadata"]["schema_version"] = json!(from + 2);
+        }
+        Ok(plan)
+    }
+
+    pub fn load(&self, plan: Value) -> Result<ExecutionPlan, PlanError> {
+        Ok(serde_json::from_value(self.upgrade(plan)?)?)
+    }
+
+    pub fn load_json(&self, json: &str) -> Result<ExecutionPlan, PlanError> {
+        self.load(serde_json::from_str(json)?)
+    }
+}
+
+impl Default for PlanMigrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(reason: &str) -> PlanError {
+    PlanError::InvalidPlanFormat {
+        reason: reason.to_string(),
+    }
+}
+
+/// Version 2 made `serial` a ramp of batch sizes, and rolling strategies
+/// take `batch_sizes` instead of a single `batch_size`.
+fn serial_to_ramps(plan: &mut Value) {
+    serial_to_ramp(&mut plan["metadata"]["planning_options"]);
+    for play in plan["plays"].as_array_mut().into_iter().flatten() {
+        serial_to_ramp(play);
+    }
+}
+
+fn serial_to_ramp(scope: &mut Value) {
+    if let Some(size) = scope.get("serial").filter(|size| size.is_number()) {
+        scope["serial"] = json!([size]);
+    }
+
+    let rolling = scope
+        .get_mut("strategy")
+        .and_then(|strategy| strategy.get_mut("Rolling"))
+        .and_then(Value::as_object_mut);
+    if let Some(rolling) = rolling {
+        if let Some(size) = rolling.remove("batch_size") {
+            rolling.insert("batch_sizes".to_string(), json!([size]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_version() {
+        let migrator = PlanMigrator::new();
+        assert_eq!(
+            migrator.schema_version(&json!({ "metadata": {} })).unwrap(),
+            1
+        );
+        assert_eq!(
+            migrator
+                .schema_version(&json!({ "metadata": { "schema_version": 2 } }))
+                .unwrap(),
+            2
+        );
+        assert!(migrator.schema_version(&json!({ "plays": [] })).is_err());
+        assert!(migrator
+            .schema_version(&json!({ "metadata": { "schema_version": "2" } }))
+            .is_err());
+        assert!(migrator
+            .schema_version(&json!({ "metadata": { "schema_version": 0 } }))
+            .is_err());
+    }
+
+    #[test]
+    fn test_upgrades_serial_to_ramps() {
+        let plan = json!({
+            "metadata": {
+                "planning_options": {
+                    "serial": 5,
+                    "strategy": { "Rolling": { "batch_size": 5 } }
+                }
+            },
+            "plays": [
+                { "serial": null, "strategy": "Linear" },
+                { "serial": 2, "strategy": { "Rolling": { "batch_size": 2 } } }
+            ]
+        });
+
+        let upgraded = PlanMigrator::new().upgrade(plan).unwrap();
+        assert_eq!(upgraded["metadata"]["schema_version"], PLAN_SCHEMA_VERSION);
+        let options = &upgraded["metadata"]["planning_options"];
+        assert_eq!(options["serial"], json!([5]));
+        assert_eq!(
+            options["strategy"],
+            json!({ "Rolling": { "batch_sizes": [5] } })
+        );
+        assert_eq!(upgraded["plays"][0]["serial"], Value::Null);
+        assert_eq!(upgraded["plays"][0]["strategy"], "Linear");
+        assert_eq!(upgraded["plays"][1]["serial"], json!([2]));
+
+        // Current plans are left alone
+        let again = PlanMigrator::new().upgrade(upgraded.clone()).unwrap();
+        assert_eq!(again, upgraded);
+    }
+
+    #[test]
+    fn test_rejects_newer_schema_versions() {
+        let plan = json!({ "metadata": { "schema_version": PLAN_SCHEMA_VERSION + 1 } });
+        assert!(matches!(
+            PlanMigrator::new().upgrade(plan),
+            Err(PlanError::UnsupportedSchemaVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_execution_plan_schema() {
+        let schema = execution_plan_schema();
+        assert_eq!(schema["title"], "ExecutionPlan");
+        assert!(schema["properties"]["plays"].is_object());
+        assert!(schema["$defs"]["PlanMetadata"]["properties"]["schema_version"].is_object());
+    }
+}
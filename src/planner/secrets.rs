@@ -0,0 +1,410 @@
+use crate::planner::error::PlanError;
+use crate::planner::loop_expansion::{item_label, item_task_name};
+use crate::planner::signing::decode_key;
+use crate::types::*;
+use crypto_box::{PublicKey, SecretKey};
+use rand_core::OsRng;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Key name patterns whose values are always treated as secrets. They are
+/// matched case-insensitively anywhere in the key.
+pub const DEFAULT_REDACT_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_?key",
+    "private_?key",
+    "credential",
+];
+
+/// Prefix of the references that replace redacted values
+pub const SECRET_REF_PREFIX: &str = "secret:";
+
+const SEALED_BOX: &str = "x25519-xsalsa20poly1305";
+
+/// Replaces secret values in task and handler arguments with references
+/// like `secret:play-0/task-1/args/password`.
+///
+/// Values under a sensitive key are redacted wherever they are nested, and
+/// every argument, variable and loop item of a `no_log` task is. The
+/// originals are collected by reference, so the planner can seal them for
+/// the executor. Expanded loop items are redacted too, and their tasks
+/// renamed so the name doesn't repeat the item.
+pub struct SecretRedactor {
+    patterns: Vec<Regex>,
+}
+
+impl SecretRedactor {
+    pub fn new() -> Self {
+        Self {
+            patterns: DEFAULT_REDACT_KEYS
+                .iter()
+                .map(|pattern| compile(pattern).expect("built-in redaction pattern"))
+                .collect(),
+        }
+    }
+
+    /// Adds regex patterns for further sensitive key names
+    pub fn with_patterns(mut self, patterns: &[String]) -> Result<Self, PlanError> {
+        for pattern in patterns {
+            self.patterns.push(compile(pattern).map_err(|err| {
+                PlanError::InvalidRedactPattern {
+                    pattern: pattern.clone(),
+                    reason: err.to_string(),
+                }
+            })?);
+        }
+        Ok(self)
+    }
+
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(key))
+    }
+
+    pub fn redact_task(
+        &self,
+        scope: &str,
+        task: &mut TaskPlan,
+        secrets: &mut BTreeMap<String, Value>,
+    ) {
+        let prefix = format!("{scope}/{}", task.task_id);
+        self.redact_map(
+            &format!("{prefix}/args"),
+            &mut task.args,
+            task.no_log,
+            secrets,
+        );
+        self.redact_map(
+            &format!("{prefix}/vars"),
+            &mut task.vars,
+            task.no_log,
+            secrets,
+        );
+
+        // Templated loops name a variable rather than hold the items
+        if let (true, Some(Value::Array(items))) = (task.no_log, &mut task.loop_items) {
+            for (index, item) in items.iter_mut().enumerate() {
+                redact(format!("{prefix}/loop_items/{index}"), item, secrets);
+            }
+        }
+
+        if let Some(LoopExpansion::Item { index, item, .. }) = &mut task.loop_expansion {
+            let label = item_label(item);
+            let path = format!("{prefix}/loop_expansion/item");
+            let redacted_label = if task.no_log {
+                redact(path, item, secrets);
+                index.to_string()
+            } else {
+                self.redact_nested(&path, item, secrets);
+                item_label(item)
+            };
+
+            if redacted_label != label {
+                if let Some(name) = task.name.strip_suffix(&item_task_name("", &label)) {
+                    task.name = item_task_name(name, &redacted_label);
+                }
+            }
+        }
+    }
+
+    pub fn redact_handler(
+        &self,
+        scope: &str,
+        handler: &mut HandlerPlan,
+        secrets: &mut BTreeMap<String, Value>,
+    ) {
+        let prefix = format!("{scope}/{}/args", handler.handler_id);
+        self.redact_map(&prefix, &mut handler.args, handler.no_log, secrets);
+    }
+
+    fn redact_map(
+        &self,
+        prefix: &str,
+        map: &mut HashMap<String, Value>,
+        redact_all: bool,
+        secrets: &mut BTreeMap<String, Value>,
+    ) {
+        for (key, value) in map.iter_mut() {
+            let path = format!("{prefix}/{key}");
+            if redact_all || self.is_sensitive(key) {
+                redact(path, value, secrets);
+            } else {
+                self.redact_nested(&path, value, secrets);
+            }
+        }
+    }
+
+    fn redact_nested(&self, path: &str, value: &mut Value, secrets: &mut BTreeMap<String, Value>) {
+        match value {
+            Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    let path = format!("{path}/{key}");
+                    if self.is_sensitive(key) {
+                        redact(path, value, secrets);
+                    } else {
+                        self.redact_nested(&path, value, secrets);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    self.redact_nested(&format!("{path}/{index}"), item, secrets);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for SecretRedactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+fn redact(reference: String, value: &mut Value, secrets: &mut BTreeMap<String, Value>) {
+    let secret = std::mem::replace(
+        value,
+        Value::String(format!("{SECRET_REF_PREFIX}{reference}")),
+    );
+    secrets.insert(reference, secret);
+}
+
+/// Seals redacted secrets to the executor's X25519 public key.
+pub struct SecretSealer {
+    recipient: PublicKey,
+}
+
+impl SecretSealer {
+    /// A sealer for a hex-encoded public key, as written by
+    /// `SecretOpener::public_key_hex`
+    pub fn from_hex(public_key: &str) -> Result<Self, PlanError> {
+        Ok(Self {
+            recipient: PublicKey::from(decode_key(public_key)?),
+        })
+    }
+
+    /// Seals the secrets as a JSON object keyed by reference. Every seal uses
+    /// a fresh ephemeral key, so the ciphertext differs between runs even for
+    /// reproducible plans.
+    pub fn seal(&self, secrets: &BTreeMap<String, Value>) -> Result<SealedSecrets, PlanError> {
+        let plaintext = canonical_json(secrets)?;
+        let sealed = self
+            .recipient
+            .seal(&mut OsRng, plaintext.as_bytes())
+            .map_err(|err| PlanError::SecretSealing {
+                reason: err.to_string(),
+            })?;
+
+        Ok(SealedSecrets {
+            algorithm: SEALED_BOX.to_string(),
+            recipient: hex::encode(self.recipient.as_bytes()),
+            ciphertext: hex::encode(sealed),
+        })
+    }
+}
+
+/// Opens sealed secrets with the executor's X25519 secret key.
+pub struct SecretOpener {
+    key: SecretKey,
+}
+
+impl SecretOpener {
+    /// An opener with a freshly generated key
+    pub fn generate() -> Self {
+        Self {
+            key: SecretKey::generate(&mut OsRng),
+        }
+    }
+
+    pub fn from_hex(secret_key: &str) -> Result<Self, PlanError> {
+        Ok(Self {
+            key: SecretKey::from(decode_key(secret_key)?),
+        })
+    }
+
+    pub fn secret_key_hex(&self) -> String {
+        hex::encode(self.key.to_bytes())
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.key.public_key().as_bytes())
+    }
+
+    /// The secrets keyed by reference
+    pub fn open(&self, sealed: &SealedSecrets) -> Result<BTreeMap<String, Value>, PlanError> {
+        let failed = |reason: String| PlanError::SecretSealing { reason };
+        if sealed.algorithm != SEALED_BOX || sealed.recipient != self.public_key_hex() {
+            return Err(failed(format!(
+                "sealed with {} for {}, not for this key",
+                sealed.algorithm, sealed.recipient
+            )));
+        }
+
+        let ciphertext = hex::decode(&sealed.ciphertext).map_err(|err| failed(err.to_string()))?;
+        let plaintext = self
+            .key
+            .unseal(&ciphertext)
+            .map_err(|err| failed(err.to_string()))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::fixtures;
+    use serde_json::json;
+
+    fn create_task(no_log: bool) -> TaskPlan {
+        TaskPlan {
+            name: "Create user".to_string(),
+            module: "user".to_string(),
+            args: HashMap::from([
+                ("name".to_string(), json!("deploy")),
+                ("password".to_string(), json!("hunter2")),
+                (
+                    "auth".to_string(),
+                    json!({ "url": "https://vault", "API_Token": "s3cr3t" }),
+                ),
+            ]),
+            vars: HashMap::from([("db_user".to_string(), json!("app"))]),
+            loop_items: Some(json!(["a", "b"])),
+            no_log,
+            ..fixtures::task("task-1")
+        }
+    }
+
+    #[test]
+    fn test_redacts_sensitive_keys() {
+        let mut task = create_task(false);
+        let mut secrets = BTreeMap::new();
+        SecretRedactor::new().redact_task("play-0", &mut task, &mut secrets);
+
+        assert_eq!(task.args["name"], "deploy");
+        assert_eq!(task.args["password"], "secret:play-0/task-1/args/password");
+        assert_eq!(task.args["auth"]["url"], "https://vault");
+        assert_eq!(
+            task.args["auth"]["API_Token"],
+            "secret:play-0/task-1/args/auth/API_Token"
+        );
+        assert_eq!(task.vars["db_user"], "app");
+        assert_eq!(task.loop_items, Some(json!(["a", "b"])));
+
+        assert_eq!(
+            secrets.keys().collect::<Vec<_>>(),
+            vec![
+                "play-0/task-1/args/auth/API_Token",
+                "play-0/task-1/args/password"
+            ]
+        );
+        assert_eq!(secrets["play-0/task-1/args/password"], "hunter2");
+    }
+
+    #[test]
+    fn test_no_log_redacts_everything() {
+        let mut task = create_task(true);
+        let mut secrets = BTreeMap::new();
+        SecretRedactor::new().redact_task("play-0", &mut task, &mut secrets);
+
+        assert_eq!(task.args["name"], "secret:play-0/task-1/args/name");
+        assert_eq!(task.vars["db_user"], "secret:play-0/task-1/vars/db_user");
+        assert_eq!(
+            task.loop_items,
+            Some(json!([
+                "secret:play-0/task-1/loop_items/0",
+                "secret:play-0/task-1/loop_items/1"
+            ]))
+        );
+        assert_eq!(secrets.len(), 6);
+        assert_eq!(
+            secrets["play-0/task-1/args/auth"],
+            json!({ "url": "https://vault", "API_Token": "s3cr3t" })
+        );
+    }
+
+    #[test]
+    fn test_redacts_expanded_loop_items() {
+        let mut task = create_task(false);
+        task.name = "Create user (item={\"name\":\"alice\",\"password\":\"hunter2\"})".to_string();
+        task.loop_expansion = Some(LoopExpansion::Item {
+            parent_task_id: "task".to_string(),
+            index: 0,
+            item: json!({ "name": "alice", "password": "hunter2" }),
+        });
+        let mut secrets = BTreeMap::new();
+        SecretRedactor::new().redact_task("play-0", &mut task, &mut secrets);
+
+        let reference = "secret:play-0/task-1/loop_expansion/item/password";
+        assert_eq!(
+            task.loop_expansion,
+            Some(LoopExpansion::Item {
+                parent_task_id: "task".to_string(),
+                index: 0,
+                item: json!({ "name": "alice", "password": reference }),
+            })
+        );
+        assert!(!task.name.contains("hunter2"));
+        assert!(task.name.contains(reference));
+
+        // A no_log item is redacted whole and the task named by its index
+        let mut task = create_task(true);
+        task.name = "tok (item=SUPERSECRETTOKEN)".to_string();
+        task.loop_expansion = Some(LoopExpansion::Item {
+            parent_task_id: "task".to_string(),
+            index: 3,
+            item: json!("SUPERSECRETTOKEN"),
+        });
+        SecretRedactor::new().redact_task("play-0", &mut task, &mut secrets);
+
+        assert_eq!(task.name, "tok (item=3)");
+        assert_eq!(
+            secrets["play-0/task-1/loop_expansion/item"],
+            "SUPERSECRETTOKEN"
+        );
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let redactor = SecretRedactor::new()
+            .with_patterns(&["^db_".to_string()])
+            .unwrap();
+        assert!(redactor.is_sensitive("db_user"));
+        assert!(redactor.is_sensitive("GITHUB_TOKEN"));
+        assert!(!redactor.is_sensitive("user_db"));
+
+        let result = SecretRedactor::new().with_patterns(&["(".to_string()]);
+        assert!(matches!(
+            result,
+            Err(PlanError::InvalidRedactPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sealed_secrets_open_with_recipient_key() {
+        let opener = SecretOpener::generate();
+        let sealer = SecretSealer::from_hex(&opener.public_key_hex()).unwrap();
+        let secrets =
+            BTreeMap::from([("play-0/task-1/args/password".to_string(), json!("hunter2"))]);
+
+        let sealed = sealer.seal(&secrets).unwrap();
+        assert!(!sealed.ciphertext.contains(&hex::encode("hunter2")));
+        assert_eq!(opener.open(&sealed).unwrap(), secrets);
+
+        // Every seal uses a fresh ephemeral key, so equal secrets can't be
+        // confirmed by resealing a guess
+        let resealed = sealer.seal(&secrets).unwrap();
+        assert_ne!(sealed, resealed);
+        assert_eq!(opener.open(&resealed).unwrap(), secrets);
+
+        let other = SecretOpener::generate();
+        assert!(other.open(&sealed).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::planner::error::PlanError;
+use crate::planner::integrity::PlanIntegrity;
+use crate::types::*;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+const ALGORITHM: &str = "ed25519";
+
+/// Signs plans with an Ed25519 key, embedding the signature in
+/// `PlanMetadata.signature`.
+///
+/// The signature covers the canonical JSON of the whole plan, including
+/// `plan_digest`, with only the signature block left out.
+pub struct PlanSigner {
+    key: SigningKey,
+}
+
+impl PlanSigner {
+    /// A signer with a freshly generated key
+    pub fn generate() -> Self {
+        Self {
+            key: SigningKey::generate(&mut rand_core::OsRng),
+        }
+    }
+
+    /// A signer for a hex-encoded 32-byte secret key, as written by
+    /// `secret_key_hex`
+    pub fn from_hex(secret_key: &str) -> Result<Self, PlanError> {
+        Ok(Self {
+            key: SigningKey::from_bytes(&decode_key(secret_key)?),
+        })
+    }
+
+    pub fn secret_key_hex(&self) -> String {
+        hex::encode(self.key.to_bytes())
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.key.verifying_key().to_bytes())
+    }
+
+    /// Signs the plan, replacing any earlier signature. Plans whose digest
+    /// no longer matches their content are refused, so an edited plan can't
+    /// be signed as the one that was planned.
+    pub fn sign(&self, plan: &mut ExecutionPlan) -> Result<(), PlanError> {
+        PlanIntegrity::new().verify(plan)?;
+
+        let signature = self.key.sign(&signed_content(plan)?);
+        plan.metadata.signature = Some(PlanSignature {
+            algorithm: ALGORITHM.to_string(),
+            public_key: self.public_key_hex(),
+            signature: hex::encode(signature.to_bytes()),
+        });
+        Ok(())
+    }
+}
+
+/// Checks plan signatures against a trusted Ed25519 public key.
+pub struct PlanVerifier {
+    key: VerifyingKey,
+}
+
+impl PlanVerifier {
+    /// A verifier for a hex-encoded public key, as written by
+    /// `PlanSigner::public_key_hex`
+    pub fn from_hex(public_key: &str) -> Result<Self, PlanError> {
+        let key = VerifyingKey::from_bytes(&decode_key(public_key)?).map_err(|err| {
+            PlanError::InvalidKey {
+                reason: err.to_string(),
+            }
+        })?;
+        Ok(Self { key })
+    }
+
+    /// Succeeds only if the plan was signed by the trusted key and nothing
+    /// in it has changed since.
+    pub fn verify(&self, plan: &ExecutionPlan) -> Result<(), PlanError> {
+        let block = plan
+            .metadata
+            .signature
+            .as_ref()
+            .ok_or(PlanError::UnsignedPlan)?;
+
+        let trusted = hex::encode(self.key.to_bytes());
+        if block.algorithm != ALGORITHM || block.public_key != trusted {
+            return Err(PlanError::SignerMismatch {
+                expected: trusted,
+                actual: format!("{}:{}", block.algorithm, block.public_key),
+            });
+        }
+
+        let signature = hex::decode(&block.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or(PlanError::InvalidSignature)?;
+        self.key
+            .verify(&signed_content(plan)?, &signature)
+            .map_err(|_| PlanError::InvalidSignature)?;
+
+        // The signature covers the digest, so check it matches the content too
+        PlanIntegrity::new().verify(plan)
+    }
+}
+
+fn signed_content(plan: &ExecutionPlan) -> Result<Vec<u8>, PlanError> {
+    let mut value = serde_json::to_value(plan)?;
+    if let Some(metadata) = value["metadata"].as_object_mut() {
+        metadata.remove("signature");
+    }
+    Ok(canonical_json(&value)?.into_bytes())
+}
+
+pub(crate) fn decode_key(key: &str) -> Result<[u8; 32], PlanError> {
+    let bytes = hex::decode(key.trim()).map_err(|err| PlanError::InvalidKey {
+        reason: err.to_string(),
+    })?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| PlanError::InvalidKey {
+            reason: format!("expected 32 bytes, got {}", bytes.len()),
+        })
+}
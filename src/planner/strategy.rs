@@ -1,10 +1,42 @@
+use crate::planner::error::PlanError;
+use crate::planner::estimation::TaskEstimator;
+use crate::planner::graph::DependencyGraphBuilder;
 use crate::types::*;
+use std::collections::{HashMap, HashSet};
 
-pub struct StrategyPlanner;
+/// Turns the tasks of a play into execution batches for a strategy.
+pub struct StrategyPlanner {
+    forks: u32,
+    cohorts: HashMap<String, Vec<String>>,
+    failure_domains: HashMap<String, String>,
+    task_estimator: TaskEstimator,
+}
 
 impl StrategyPlanner {
     pub fn new() -> Self {
-        Self
+        Self {
+            forks: 50,
+            cohorts: HashMap::new(),
+            failure_domains: HashMap::new(),
+            task_estimator: TaskEstimator::new(),
+        }
+    }
+
+    pub fn with_forks(mut self, forks: u32) -> Self {
+        self.forks = forks;
+        self
+    }
+
+    /// Hosts of the inventory groups blue/green plays deploy to, by group name
+    pub fn with_cohorts(mut self, cohorts: HashMap<String, Vec<String>>) -> Self {
+        self.cohorts = cohorts;
+        self
+    }
+
+    /// Failure domain of each host, for rolling plays spread by topology
+    pub fn with_failure_domains(mut self, failure_domains: HashMap<String, String>) -> Self {
+        self.failure_domains = failure_domains;
+        self
     }
 
     pub fn plan_strategy(
@@ -12,149 +44,509 @@ impl StrategyPlanner {
         strategy: &ExecutionStrategy,
         tasks: &[TaskPlan],
         hosts: &[String],
-    ) -> Vec<ExecutionBatch> {
-        match strategy {
-            ExecutionStrategy::Linear => self.plan_linear(tasks, hosts),
-            ExecutionStrategy::Free => self.plan_free(tasks, hosts),
-            ExecutionStrategy::Rolling { batch_size } => {
-                self.plan_rolling(tasks, hosts, *batch_size)
+        dependency_graph: &DependencyGraph,
+        binary_deployments: &[BinaryDeployment],
+    ) -> Result<Vec<ExecutionBatch>, PlanError> {
+        let mut batches = match strategy {
+            ExecutionStrategy::Linear => self.plan_linear(tasks),
+            ExecutionStrategy::Free => self.plan_free(tasks, hosts, dependency_graph)?,
+            ExecutionStrategy::Rolling {
+                batch_sizes,
+                topology,
+            } => self.plan_rolling(tasks, hosts, batch_sizes, topology.as_ref()),
+            ExecutionStrategy::HostPinned => {
+                self.plan_host_pinned(tasks, hosts, dependency_graph)?
+            }
+            ExecutionStrategy::BinaryHybrid | ExecutionStrategy::BinaryOnly => {
+                self.plan_binary(tasks, hosts, dependency_graph, binary_deployments)?
             }
-            ExecutionStrategy::HostPinned => self.plan_host_pinned(tasks, hosts),
-            ExecutionStrategy::BinaryHybrid => self.plan_binary_hybrid(tasks, hosts),
-            ExecutionStrategy::BinaryOnly => self.plan_binary_only(tasks, hosts),
+            ExecutionStrategy::Canary {
+                canary_size,
+                batch_sizes,
+                topology,
+            } => self.plan_canary(tasks, hosts, canary_size, batch_sizes, topology.as_ref()),
+            ExecutionStrategy::BlueGreen { blue, green } => {
+                self.plan_blue_green(tasks, hosts, blue, green)?
+            }
+        };
+
+        for batch in &mut batches {
+            batch.estimated_duration = Some(self.task_estimator.estimate_batch_duration(batch)?);
         }
+
+        Ok(batches)
     }
 
-    fn plan_linear(&self, tasks: &[TaskPlan], hosts: &[String]) -> Vec<ExecutionBatch> {
+    /// All tasks in sequence, one batch per task
+    fn plan_linear(&self, tasks: &[TaskPlan]) -> Vec<ExecutionBatch> {
         tasks
             .iter()
             .enumerate()
             .map(|(index, task)| ExecutionBatch {
-                batch_id: format!("linear-batch-{index}"),
-                hosts: hosts.to_vec(),
-                tasks: vec![(*task).clone()],
+                batch_id: format!("batch-{index}"),
+                hosts: task.hosts.clone(),
+                tasks: vec![task.clone()],
                 parallel_groups: Vec::new(),
                 dependencies: if index > 0 {
-                    vec![format!("linear-batch-{}", index - 1)]
+                    vec![format!("batch-{}", index - 1)]
                 } else {
                     Vec::new()
                 },
-                estimated_duration: task.estimated_duration,
+                estimated_duration: None,
+                binary_deployment: None,
+                proceed_if: None,
+                stage_gate: None,
             })
             .collect()
     }
 
-    fn plan_free(&self, tasks: &[TaskPlan], hosts: &[String]) -> Vec<ExecutionBatch> {
-        let (parallel_tasks, sequential_tasks): (Vec<_>, Vec<_>) =
-            tasks.iter().partition(|task| task.can_run_parallel);
+    /// Builds one batch per topological layer of the dependency graph.
+    /// Independent tasks within a layer are grouped for parallel execution,
+    /// and each batch depends on the batches holding its tasks' predecessors.
+    fn plan_free(
+        &self,
+        tasks: &[TaskPlan],
+        hosts: &[String],
+        dependency_graph: &DependencyGraph,
+    ) -> Result<Vec<ExecutionBatch>, PlanError> {
+        let graph_builder = DependencyGraphBuilder::new();
+        let layers = graph_builder.topological_layers(tasks, dependency_graph)?;
+        let predecessors = predecessors(tasks, dependency_graph);
 
+        let mut batch_of_task: HashMap<String, usize> = HashMap::new();
         let mut batches = Vec::new();
 
-        if !parallel_tasks.is_empty() {
+        for (layer_index, layer_tasks) in layers.into_iter().enumerate() {
+            let dependencies =
+                batch_dependencies(layer_index, &layer_tasks, &predecessors, &batch_of_task);
+            for task in &layer_tasks {
+                batch_of_task.insert(task.task_id.clone(), layer_index);
+            }
+
             batches.push(ExecutionBatch {
-                batch_id: "free-parallel".to_string(),
-                hosts: hosts.to_vec(),
-                tasks: parallel_tasks.into_iter().cloned().collect(),
-                parallel_groups: Vec::new(),
-                dependencies: Vec::new(),
+                batch_id: format!("batch-{layer_index}"),
+                hosts: hosts_of(&layer_tasks, hosts),
+                parallel_groups: graph_builder.find_parallel_groups(&layer_tasks, dependency_graph),
+                tasks: layer_tasks,
+                dependencies,
                 estimated_duration: None,
+                binary_deployment: None,
+                proceed_if: None,
+                stage_gate: None,
             });
         }
 
-        for (index, task) in sequential_tasks.iter().enumerate() {
-            batches.push(ExecutionBatch {
-                batch_id: format!("free-sequential-{index}"),
-                hosts: hosts.to_vec(),
-                tasks: vec![(*task).clone()],
-                parallel_groups: Vec::new(),
-                dependencies: if index > 0 {
-                    vec![format!("free-sequential-{}", index - 1)]
-                } else if !batches.is_empty() {
-                    vec!["free-parallel".to_string()]
-                } else {
-                    Vec::new()
+        Ok(batches)
+    }
+
+    /// Runs every task on one group of hosts before moving to the next.
+    /// Group sizes follow the ramp in `batch_sizes`, repeating the last one.
+    fn plan_rolling(
+        &self,
+        tasks: &[TaskPlan],
+        hosts: &[String],
+        batch_sizes: &[BatchSize],
+        topology: Option<&TopologySpread>,
+    ) -> Vec<ExecutionBatch> {
+        let host_groups = match topology {
+            Some(topology) => self.spread_hosts(hosts, batch_sizes, topology),
+            None => chunk_hosts(hosts, batch_sizes),
+        };
+
+        host_groups
+            .into_iter()
+            .enumerate()
+            .map(|(batch_index, batch_hosts)| {
+                // Tasks only run on the batch hosts they were not filtered out for
+                let batch_tasks: Vec<TaskPlan> = tasks
+                    .iter()
+                    .filter_map(|task| {
+                        let mut task_clone = task.clone();
+                        task_clone.hosts = batch_hosts
+                            .iter()
+                            .filter(|host| task.hosts.contains(host))
+                            .cloned()
+                            .collect();
+                        (!task_clone.hosts.is_empty()).then_some(task_clone)
+                    })
+                    .collect();
+
+                ExecutionBatch {
+                    batch_id: format!("rolling-batch-{batch_index}"),
+                    hosts: batch_hosts,
+                    tasks: batch_tasks,
+                    parallel_groups: Vec::new(),
+                    dependencies: if batch_index > 0 {
+                        vec![format!("rolling-batch-{}", batch_index - 1)]
+                    } else {
+                        Vec::new()
+                    },
+                    estimated_duration: None,
+                    binary_deployment: None,
+                    proceed_if: None,
+                    stage_gate: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Groups hosts into rolling batches that take at most
+    /// `max_per_domain` hosts of any failure domain. Each batch draws from
+    /// the domains in turn, starting with those the previous batch left
+    /// alone, so consecutive batches hit different domains where possible.
+    fn spread_hosts(
+        &self,
+        hosts: &[String],
+        batch_sizes: &[BatchSize],
+        topology: &TopologySpread,
+    ) -> Vec<Vec<String>> {
+        // Domains in order of first appearance, each with its hosts in order
+        let mut domains: Vec<(&str, Vec<&String>)> = Vec::new();
+        for host in hosts {
+            let domain = self
+                .failure_domains
+                .get(host)
+                .map(String::as_str)
+                .unwrap_or_default();
+            match domains.iter_mut().find(|(name, _)| *name == domain) {
+                Some((_, domain_hosts)) => domain_hosts.push(host),
+                None => domains.push((domain, vec![host])),
+            }
+        }
+
+        // A limit of zero leaves the domain unrestricted
+        let limits: Vec<usize> = domains
+            .iter()
+            .map(
+                |(_, domain_hosts)| match topology.max_per_domain.resolve(domain_hosts.len()) {
+                    0 => usize::MAX,
+                    limit => limit,
                 },
-                estimated_duration: task.estimated_duration,
-            });
+            )
+            .collect();
+
+        let mut next: Vec<usize> = vec![0; domains.len()];
+        let mut remaining = hosts.len();
+        let mut previous: Vec<usize> = Vec::new();
+        let mut groups = Vec::new();
+
+        while remaining > 0 {
+            let size = batch_sizes
+                .get(groups.len())
+                .or(batch_sizes.last())
+                .map_or(0, |size| size.resolve(hosts.len()));
+            let size = if size == 0 {
+                remaining
+            } else {
+                size.min(remaining)
+            };
+
+            let order: Vec<usize> = (0..domains.len())
+                .filter(|index| !previous.contains(index))
+                .chain(previous.iter().copied())
+                .collect();
+
+            let mut taken = vec![0; domains.len()];
+            let mut group = Vec::new();
+            while group.len() < size {
+                let mut progressed = false;
+                for &index in &order {
+                    let domain_hosts = &domains[index].1;
+                    if group.len() < size
+                        && next[index] < domain_hosts.len()
+                        && taken[index] < limits[index]
+                    {
+                        group.push(domain_hosts[next[index]].clone());
+                        next[index] += 1;
+                        taken[index] += 1;
+                        progressed = true;
+                    }
+                }
+                if !progressed {
+                    break;
+                }
+            }
+
+            remaining -= group.len();
+            previous = order
+                .into_iter()
+                .filter(|&index| taken[index] > 0)
+                .collect();
+            groups.push(group);
         }
 
-        batches
+        groups
     }
 
-    fn plan_rolling(
+    /// Rolls out to the canary hosts first, then to the rest once a health
+    /// check on the canaries has passed. The remaining hosts follow
+    /// `batch_sizes`, with percentages taken of the hosts left after the canary.
+    fn plan_canary(
         &self,
         tasks: &[TaskPlan],
         hosts: &[String],
-        batch_size: u32,
+        canary_size: &BatchSize,
+        batch_sizes: &[BatchSize],
+        topology: Option<&TopologySpread>,
     ) -> Vec<ExecutionBatch> {
-        let host_chunks: Vec<Vec<String>> = hosts
-            .chunks(batch_size as usize)
-            .map(|chunk| chunk.to_vec())
-            .collect();
+        let canary_count = canary_size
+            .resolve(hosts.len())
+            .clamp(1, hosts.len().max(1));
+        let (canary_hosts, rest) = hosts.split_at(canary_count.min(hosts.len()));
 
         let mut batches = Vec::new();
+        for mut batch in self.plan_rolling(tasks, canary_hosts, &[BatchSize::Percent(100)], None) {
+            batch.batch_id = "canary".to_string();
+            batches.push(batch);
+        }
+        if rest.is_empty() || batches.is_empty() {
+            return batches;
+        }
 
-        for (chunk_index, host_chunk) in host_chunks.iter().enumerate() {
-            let batch_tasks: Vec<TaskPlan> = tasks
+        batches.push(stage_gate_batch(
+            "canary-health-check",
+            StageGate::HealthCheck,
+            canary_hosts,
+            "canary",
+        ));
+
+        let mut rollout = self.plan_rolling(tasks, rest, batch_sizes, topology);
+        if let Some(first) = rollout.first_mut() {
+            first.dependencies = vec!["canary-health-check".to_string()];
+        }
+        batches.extend(rollout);
+        batches
+    }
+
+    /// Deploys to the green cohort, checks its health and switches traffic to
+    /// it, then updates the blue cohort. Every play host must belong to
+    /// exactly one cohort.
+    fn plan_blue_green(
+        &self,
+        tasks: &[TaskPlan],
+        hosts: &[String],
+        blue: &str,
+        green: &str,
+    ) -> Result<Vec<ExecutionBatch>, PlanError> {
+        let cohort = |group: &str| {
+            self.cohorts
+                .get(group)
+                .ok_or_else(|| PlanError::InvalidCohort {
+                    group: group.to_string(),
+                    reason: "No such inventory group".to_string(),
+                })
+        };
+        let (blue_group, green_group) = (cohort(blue)?, cohort(green)?);
+
+        let mut blue_hosts = Vec::new();
+        let mut green_hosts = Vec::new();
+        for host in hosts {
+            match (blue_group.contains(host), green_group.contains(host)) {
+                (true, false) => blue_hosts.push(host.clone()),
+                (false, true) => green_hosts.push(host.clone()),
+                (true, true) => {
+                    return Err(PlanError::InvalidCohort {
+                        group: green.to_string(),
+                        reason: format!("Host '{host}' is also in '{blue}'"),
+                    })
+                }
+                (false, false) => {
+                    return Err(PlanError::InvalidCohort {
+                        group: green.to_string(),
+                        reason: format!("Host '{host}' is in neither '{blue}' nor '{green}'"),
+                    })
+                }
+            }
+        }
+
+        if green_hosts.is_empty() && !blue_hosts.is_empty() {
+            return Err(PlanError::InvalidCohort {
+                group: green.to_string(),
+                reason: format!(
+                    "None of the play's hosts are in '{green}', so traffic can't switch to it"
+                ),
+            });
+        }
+
+        let mut batches = Vec::new();
+        let mut previous: Option<String> = None;
+        for (id, cohort_hosts) in [("green", &green_hosts), ("blue", &blue_hosts)] {
+            for mut batch in
+                self.plan_rolling(tasks, cohort_hosts, &[BatchSize::Percent(100)], None)
+            {
+                batch.batch_id = id.to_string();
+                batch.dependencies = previous.iter().cloned().collect();
+                previous = Some(batch.batch_id.clone());
+                batches.push(batch);
+            }
+
+            if id == "green" {
+                if let Some(after) = previous.take() {
+                    batches.push(stage_gate_batch(
+                        "green-health-check",
+                        StageGate::HealthCheck,
+                        &green_hosts,
+                        &after,
+                    ));
+                    batches.push(stage_gate_batch(
+                        "switch-to-green",
+                        StageGate::TrafficSwitch,
+                        &green_hosts,
+                        "green-health-check",
+                    ));
+                    previous = Some("switch-to-green".to_string());
+                }
+            }
+        }
+
+        Ok(batches)
+    }
+
+    /// Gives each host its own stream of tasks in dependency order. Streams
+    /// run independently, but only `forks` of them at a time: a stream waits
+    /// for the one `forks` places ahead of it to finish.
+    fn plan_host_pinned(
+        &self,
+        tasks: &[TaskPlan],
+        hosts: &[String],
+        dependency_graph: &DependencyGraph,
+    ) -> Result<Vec<ExecutionBatch>, PlanError> {
+        let ordered: Vec<TaskPlan> = DependencyGraphBuilder::new()
+            .topological_layers(tasks, dependency_graph)?
+            .into_iter()
+            .flatten()
+            .collect();
+        let forks = self.forks.max(1) as usize;
+
+        let mut batches: Vec<ExecutionBatch> = Vec::new();
+        for host in hosts {
+            let host_tasks: Vec<TaskPlan> = ordered
                 .iter()
+                .filter(|task| task.hosts.contains(host))
                 .map(|task| {
                     let mut task_clone = task.clone();
-                    task_clone.hosts = host_chunk.clone();
+                    task_clone.hosts = vec![host.clone()];
                     task_clone
                 })
                 .collect();
+            if host_tasks.is_empty() {
+                continue;
+            }
 
+            let index = batches.len();
             batches.push(ExecutionBatch {
-                batch_id: format!("rolling-{chunk_index}"),
-                hosts: host_chunk.clone(),
-                tasks: batch_tasks,
+                batch_id: format!("host-{index}"),
+                hosts: vec![host.clone()],
+                tasks: host_tasks,
                 parallel_groups: Vec::new(),
-                dependencies: if chunk_index > 0 {
-                    vec![format!("rolling-{}", chunk_index - 1)]
+                dependencies: if index >= forks {
+                    vec![format!("host-{}", index - forks)]
                 } else {
                     Vec::new()
                 },
                 estimated_duration: None,
+                binary_deployment: None,
+                proceed_if: None,
+                stage_gate: None,
             });
         }
 
-        batches
+        Ok(batches)
     }
 
-    fn plan_host_pinned(&self, tasks: &[TaskPlan], hosts: &[String]) -> Vec<ExecutionBatch> {
-        hosts
+    /// Runs the tasks of each binary deployment as one batch, with the
+    /// remaining tasks executed over SSH in layered batches. A deployment is
+    /// split where one of its tasks has to wait for an SSH task that itself
+    /// depends on an earlier part of the deployment.
+    fn plan_binary(
+        &self,
+        tasks: &[TaskPlan],
+        hosts: &[String],
+        dependency_graph: &DependencyGraph,
+        binary_deployments: &[BinaryDeployment],
+    ) -> Result<Vec<ExecutionBatch>, PlanError> {
+        let graph_builder = DependencyGraphBuilder::new();
+        let ordered: Vec<TaskPlan> = graph_builder
+            .topological_layers(tasks, dependency_graph)?
+            .into_iter()
+            .flatten()
+            .collect();
+        let predecessors = predecessors(tasks, dependency_graph);
+
+        let deployment_of_task: HashMap<&str, &str> = binary_deployments
             .iter()
-            .enumerate()
-            .map(|(index, host)| {
-                let host_tasks: Vec<TaskPlan> = tasks
+            .flat_map(|deployment| {
+                deployment
+                    .tasks
                     .iter()
-                    .map(|task| {
-                        let mut task_clone = task.clone();
-                        task_clone.hosts = vec![host.clone()];
-                        task_clone
-                    })
-                    .collect();
-
-                ExecutionBatch {
-                    batch_id: format!("host-{index}"),
-                    hosts: vec![host.clone()],
-                    tasks: host_tasks,
-                    parallel_groups: Vec::new(),
-                    dependencies: Vec::new(),
-                    estimated_duration: None,
-                }
+                    .map(|task_id| (task_id.as_str(), deployment.deployment_id.as_str()))
             })
-            .collect()
-    }
+            .collect();
 
-    fn plan_binary_hybrid(&self, tasks: &[TaskPlan], hosts: &[String]) -> Vec<ExecutionBatch> {
-        // For now, use linear strategy - binary deployment is handled separately
-        self.plan_linear(tasks, hosts)
-    }
+        let mut batch_tasks: Vec<Vec<TaskPlan>> = Vec::new();
+        let mut batch_deployment: Vec<Option<String>> = Vec::new();
+        let mut batch_of_task: HashMap<String, usize> = HashMap::new();
+        let mut open_binary: HashMap<&str, usize> = HashMap::new();
+        let mut open_ssh: Option<usize> = None;
+
+        for task in ordered {
+            // The latest batch holding one of this task's predecessors
+            let ready_after = predecessors
+                .get(task.task_id.as_str())
+                .into_iter()
+                .flatten()
+                .filter_map(|pred| batch_of_task.get(pred).copied())
+                .max();
+
+            let index = match deployment_of_task.get(task.task_id.as_str()) {
+                // Tasks inside a binary run in order, so a predecessor in
+                // the same batch is fine
+                Some(&deployment_id) => match open_binary.get(deployment_id) {
+                    Some(&open) if ready_after.is_none_or(|after| after <= open) => open,
+                    _ => {
+                        batch_tasks.push(Vec::new());
+                        batch_deployment.push(Some(deployment_id.to_string()));
+                        open_binary.insert(deployment_id, batch_tasks.len() - 1);
+                        batch_tasks.len() - 1
+                    }
+                },
+                // SSH tasks in a batch run side by side, so a predecessor
+                // must be in an earlier batch
+                None => match open_ssh {
+                    Some(open) if ready_after.is_none_or(|after| after < open) => open,
+                    _ => {
+                        batch_tasks.push(Vec::new());
+                        batch_deployment.push(None);
+                        open_ssh = Some(batch_tasks.len() - 1);
+                        batch_tasks.len() - 1
+                    }
+                },
+            };
+
+            batch_of_task.insert(task.task_id.clone(), index);
+            batch_tasks[index].push(task);
+        }
 
-    fn plan_binary_only(&self, tasks: &[TaskPlan], hosts: &[String]) -> Vec<ExecutionBatch> {
-        // For now, use linear strategy - binary deployment is handled separately
-        self.plan_linear(tasks, hosts)
+        Ok(batch_tasks
+            .into_iter()
+            .zip(batch_deployment)
+            .enumerate()
+            .map(|(index, (tasks, deployment))| ExecutionBatch {
+                batch_id: format!("batch-{index}"),
+                hosts: hosts_of(&tasks, hosts),
+                parallel_groups: if deployment.is_none() {
+                    graph_builder.find_parallel_groups(&tasks, dependency_graph)
+                } else {
+                    Vec::new()
+                },
+                dependencies: batch_dependencies(index, &tasks, &predecessors, &batch_of_task),
+                tasks,
+                estimated_duration: None,
+                binary_deployment: deployment,
+                proceed_if: None,
+                stage_gate: None,
+            })
+            .collect())
     }
 }
 
@@ -164,6 +556,98 @@ impl Default for StrategyPlanner {
     }
 }
 
+/// Predecessors of each task among `tasks`, from both the dependency graph
+/// and the task's explicit dependencies.
+fn predecessors(
+    tasks: &[TaskPlan],
+    dependency_graph: &DependencyGraph,
+) -> HashMap<String, Vec<String>> {
+    let task_ids: HashSet<String> = tasks.iter().map(|t| t.task_id.clone()).collect();
+    tasks
+        .iter()
+        .map(|task| {
+            let mut preds = dependency_graph.predecessors_within(&task.task_id, &task_ids);
+            for dependency in &task.dependencies {
+                if task_ids.contains(dependency) && !preds.contains(dependency) {
+                    preds.push(dependency.clone());
+                }
+            }
+            (task.task_id.clone(), preds)
+        })
+        .collect()
+}
+
+/// IDs of the batches holding predecessors of `tasks`, other than the batch
+/// itself, in batch order.
+fn batch_dependencies(
+    index: usize,
+    tasks: &[TaskPlan],
+    predecessors: &HashMap<String, Vec<String>>,
+    batch_of_task: &HashMap<String, usize>,
+) -> Vec<String> {
+    let mut indices: Vec<usize> = tasks
+        .iter()
+        .flat_map(|task| predecessors.get(&task.task_id).into_iter().flatten())
+        .filter_map(|pred| batch_of_task.get(pred).copied())
+        .filter(|&pred_index| pred_index != index)
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+        .into_iter()
+        .map(|index| format!("batch-{index}"))
+        .collect()
+}
+
+/// A batch with no tasks that holds back the batches after it until an
+/// executor has acted on its gate
+fn stage_gate_batch(id: &str, gate: StageGate, hosts: &[String], after: &str) -> ExecutionBatch {
+    ExecutionBatch {
+        batch_id: id.to_string(),
+        hosts: hosts.to_vec(),
+        tasks: Vec::new(),
+        parallel_groups: Vec::new(),
+        dependencies: vec![after.to_string()],
+        estimated_duration: None,
+        binary_deployment: None,
+        proceed_if: None,
+        stage_gate: Some(gate),
+    }
+}
+
+/// Splits hosts into consecutive rolling batches following the ramp in
+/// `batch_sizes`. A size of zero takes every remaining host.
+fn chunk_hosts(hosts: &[String], batch_sizes: &[BatchSize]) -> Vec<Vec<String>> {
+    let mut groups = Vec::new();
+    let mut remaining = hosts;
+
+    while !remaining.is_empty() {
+        let size = batch_sizes
+            .get(groups.len())
+            .or(batch_sizes.last())
+            .map_or(0, |size| size.resolve(hosts.len()));
+        let size = if size == 0 {
+            remaining.len()
+        } else {
+            size.min(remaining.len())
+        };
+        let (group, rest) = remaining.split_at(size);
+        groups.push(group.to_vec());
+        remaining = rest;
+    }
+
+    groups
+}
+
+/// Play hosts that at least one of the tasks runs on, in play order
+fn hosts_of(tasks: &[TaskPlan], hosts: &[String]) -> Vec<String> {
+    hosts
+        .iter()
+        .filter(|host| tasks.iter().any(|task| task.hosts.contains(host)))
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,7 +659,7 @@ mod tests {
             name: format!("Test task {}", id),
             module: "shell".to_string(),
             args: std::collections::HashMap::new(),
-            hosts: vec!["host1".to_string()],
+            hosts: vec!["host1".to_string(), "host2".to_string()],
             dependencies: Vec::new(),
             conditions: Vec::new(),
             tags: Vec::new(),
@@ -184,16 +668,78 @@ mod tests {
             can_run_parallel: can_parallel,
             estimated_duration: Some(Duration::from_secs(1)),
             risk_level: RiskLevel::Low,
+            vars: std::collections::HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
+        }
+    }
+
+    fn with_dependencies(mut task: TaskPlan, dependencies: &[&str]) -> TaskPlan {
+        task.dependencies = dependencies.iter().map(|d| d.to_string()).collect();
+        task
+    }
+
+    fn create_deployment(id: &str, tasks: &[&str]) -> BinaryDeployment {
+        BinaryDeployment {
+            deployment_id: id.to_string(),
+            play_id: "play-0".to_string(),
+            target_hosts: vec!["host1".to_string(), "host2".to_string()],
+            binary_name: format!("rustle-{id}"),
+            tasks: tasks.iter().map(|t| t.to_string()).collect(),
+            modules: vec!["shell".to_string()],
+            embedded_data: BinaryEmbeddedData {
+                execution_plan: "{}".to_string(),
+                static_files: vec![],
+                variables: std::collections::HashMap::new(),
+                facts_required: vec![],
+            },
+            execution_mode: BinaryExecutionMode::Standalone,
+            estimated_size: 1024,
+            compilation_requirements: CompilationRequirements {
+                target_arch: "x86_64".to_string(),
+                target_os: "linux".to_string(),
+                rust_version: "1.70.0".to_string(),
+                cross_compilation: false,
+                static_linking: true,
+            },
         }
     }
 
+    fn hosts(count: usize) -> Vec<String> {
+        (1..=count).map(|i| format!("host{i}")).collect()
+    }
+
+    fn plan(
+        planner: &StrategyPlanner,
+        strategy: ExecutionStrategy,
+        tasks: &[TaskPlan],
+        hosts: &[String],
+        deployments: &[BinaryDeployment],
+    ) -> Vec<ExecutionBatch> {
+        let graph = DependencyGraphBuilder::new()
+            .build_from_tasks(tasks)
+            .unwrap();
+        planner
+            .plan_strategy(&strategy, tasks, hosts, &graph, deployments)
+            .unwrap()
+    }
+
+    fn task_ids(batch: &ExecutionBatch) -> Vec<&str> {
+        batch.tasks.iter().map(|t| t.task_id.as_str()).collect()
+    }
+
     #[test]
     fn test_new_and_default() {
         let planner1 = StrategyPlanner::new();
         let planner2 = StrategyPlanner::default();
 
-        // Both should create instances successfully
-        let _ = (planner1, planner2);
+        assert_eq!(planner1.forks, planner2.forks);
     }
 
     #[test]
@@ -204,26 +750,20 @@ mod tests {
             create_test_task("task2", true),
             create_test_task("task3", false),
         ];
-        let hosts = vec!["host1".to_string(), "host2".to_string()];
 
-        let batches = planner.plan_linear(&tasks, &hosts);
+        let batches = plan(&planner, ExecutionStrategy::Linear, &tasks, &hosts(2), &[]);
 
         assert_eq!(batches.len(), 3);
-        assert_eq!(batches[0].batch_id, "linear-batch-0");
-        assert_eq!(batches[1].batch_id, "linear-batch-1");
-        assert_eq!(batches[2].batch_id, "linear-batch-2");
-
-        // Each batch should have one task
         for (i, batch) in batches.iter().enumerate() {
-            assert_eq!(batch.tasks.len(), 1);
-            assert_eq!(batch.tasks[0].task_id, format!("task{}", i + 1));
-            assert_eq!(batch.hosts, hosts);
+            assert_eq!(batch.batch_id, format!("batch-{i}"));
+            assert_eq!(task_ids(batch), vec![format!("task{}", i + 1)]);
+            assert_eq!(batch.hosts, hosts(2));
+            assert_eq!(batch.estimated_duration, Some(Duration::from_secs(1)));
         }
 
-        // Check dependencies
         assert!(batches[0].dependencies.is_empty());
-        assert_eq!(batches[1].dependencies, vec!["linear-batch-0"]);
-        assert_eq!(batches[2].dependencies, vec!["linear-batch-1"]);
+        assert_eq!(batches[1].dependencies, vec!["batch-0"]);
+        assert_eq!(batches[2].dependencies, vec!["batch-1"]);
     }
 
     #[test]
@@ -232,158 +772,520 @@ mod tests {
         let tasks = vec![
             create_test_task("task1", true),
             create_test_task("task2", true),
-            create_test_task("task3", false),
-            create_test_task("task4", false),
+            with_dependencies(create_test_task("task3", false), &["task1"]),
         ];
-        let hosts = vec!["host1".to_string(), "host2".to_string()];
 
-        let batches = planner.plan_free(&tasks, &hosts);
+        let batches = plan(&planner, ExecutionStrategy::Free, &tasks, &hosts(2), &[]);
 
-        // Should have one parallel batch and two sequential batches
-        assert_eq!(batches.len(), 3);
-        assert_eq!(batches[0].batch_id, "free-parallel");
-        assert_eq!(batches[0].tasks.len(), 2); // Two parallel tasks
+        assert_eq!(batches.len(), 2);
+        assert_eq!(task_ids(&batches[0]), vec!["task1", "task2"]);
+        assert_eq!(batches[0].parallel_groups.len(), 1);
+        assert_eq!(task_ids(&batches[1]), vec!["task3"]);
+        assert_eq!(batches[1].dependencies, vec!["batch-0"]);
+    }
+
+    #[test]
+    fn test_plan_rolling_strategy() {
+        let planner = StrategyPlanner::new();
+        let mut task1 = create_test_task("task1", true);
+        task1.hosts = hosts(5);
+        // Filtered out everywhere but host1
+        let mut task2 = create_test_task("task2", true);
+        task2.hosts = vec!["host1".to_string()];
+        let tasks = vec![task1, task2];
 
-        assert_eq!(batches[1].batch_id, "free-sequential-0");
-        assert_eq!(batches[1].tasks.len(), 1);
-        assert_eq!(batches[1].dependencies, vec!["free-parallel"]);
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Rolling {
+                batch_sizes: vec![BatchSize::Hosts(2)],
+                topology: None,
+            },
+            &tasks,
+            &hosts(5),
+            &[],
+        );
 
-        assert_eq!(batches[2].batch_id, "free-sequential-1");
-        assert_eq!(batches[2].tasks.len(), 1);
-        assert_eq!(batches[2].dependencies, vec!["free-sequential-0"]);
+        // With 5 hosts and batch size 2, should have 3 batches
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].batch_id, "rolling-batch-0");
+        assert_eq!(batches[0].hosts, vec!["host1", "host2"]);
+        assert_eq!(task_ids(&batches[0]), vec!["task1", "task2"]);
+        assert_eq!(batches[0].tasks[1].hosts, vec!["host1"]);
+        assert!(batches[0].dependencies.is_empty());
+
+        assert_eq!(batches[1].hosts, vec!["host3", "host4"]);
+        assert_eq!(batches[1].dependencies, vec!["rolling-batch-0"]);
+        assert_eq!(batches[2].hosts, vec!["host5"]);
+        assert_eq!(batches[2].dependencies, vec!["rolling-batch-1"]);
     }
 
     #[test]
-    fn test_plan_free_strategy_only_sequential() {
+    fn test_rolling_ramp_follows_serial_list() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![
-            create_test_task("task1", false),
-            create_test_task("task2", false),
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(20);
+
+        let batch_sizes = vec![
+            BatchSize::Hosts(1),
+            BatchSize::Hosts(5),
+            BatchSize::Percent(25),
         ];
-        let hosts = vec!["host1".to_string()];
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Rolling {
+                batch_sizes,
+                topology: None,
+            },
+            &[task],
+            &hosts(20),
+            &[],
+        );
 
-        let batches = planner.plan_free(&tasks, &hosts);
+        // 1, 5, then 25% of 20 hosts until none are left
+        let sizes: Vec<usize> = batches.iter().map(|b| b.hosts.len()).collect();
+        assert_eq!(sizes, vec![1, 5, 5, 5, 4]);
+        assert_eq!(batches[1].hosts[0], "host2");
+    }
 
-        assert_eq!(batches.len(), 2);
-        assert_eq!(batches[0].batch_id, "free-sequential-0");
-        assert!(batches[0].dependencies.is_empty());
+    #[test]
+    fn test_rolling_percentages_round_down_to_at_least_one_host() {
+        let planner = StrategyPlanner::new();
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(3);
+
+        let batch_sizes = vec![BatchSize::Percent(10), BatchSize::Percent(100)];
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Rolling {
+                batch_sizes,
+                topology: None,
+            },
+            &[task],
+            &hosts(3),
+            &[],
+        );
+
+        let sizes: Vec<usize> = batches.iter().map(|b| b.hosts.len()).collect();
+        assert_eq!(sizes, vec![1, 2]);
+    }
 
-        assert_eq!(batches[1].batch_id, "free-sequential-1");
-        assert_eq!(batches[1].dependencies, vec!["free-sequential-0"]);
+    fn zoned_planner(zones: &[&str]) -> StrategyPlanner {
+        StrategyPlanner::new().with_failure_domains(
+            zones
+                .iter()
+                .enumerate()
+                .map(|(i, zone)| (format!("host{}", i + 1), zone.to_string()))
+                .collect(),
+        )
+    }
+
+    fn spread(max_per_domain: BatchSize) -> Option<TopologySpread> {
+        Some(TopologySpread {
+            domain_var: "zone".to_string(),
+            max_per_domain,
+        })
     }
 
     #[test]
-    fn test_plan_rolling_strategy() {
+    fn test_rolling_limits_hosts_per_failure_domain() {
+        let planner = zoned_planner(&["a", "a", "a", "b", "b", "c"]);
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(6);
+
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Rolling {
+                batch_sizes: vec![BatchSize::Hosts(3)],
+                topology: spread(BatchSize::Hosts(1)),
+            },
+            &[task],
+            &hosts(6),
+            &[],
+        );
+
+        let groups: Vec<&Vec<String>> = batches.iter().map(|b| &b.hosts).collect();
+        assert_eq!(
+            groups,
+            vec![
+                &vec![
+                    "host1".to_string(),
+                    "host4".to_string(),
+                    "host6".to_string()
+                ],
+                &vec!["host2".to_string(), "host5".to_string()],
+                &vec!["host3".to_string()],
+            ]
+        );
+        assert_eq!(batches[2].tasks[0].hosts, vec!["host3".to_string()]);
+    }
+
+    #[test]
+    fn test_rolling_interleaves_failure_domains() {
+        let planner = zoned_planner(&["a", "a", "b", "b"]);
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(4);
+
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Rolling {
+                batch_sizes: vec![BatchSize::Hosts(1)],
+                topology: spread(BatchSize::Percent(100)),
+            },
+            &[task],
+            &hosts(4),
+            &[],
+        );
+
+        // Consecutive batches alternate between zone a and zone b
+        let order: Vec<&str> = batches.iter().map(|b| b.hosts[0].as_str()).collect();
+        assert_eq!(order, vec!["host1", "host3", "host2", "host4"]);
+    }
+
+    #[test]
+    fn test_rolling_domain_percentage_limit() {
+        // host5 has no zone and falls into a domain of its own
+        let planner = zoned_planner(&["a", "a", "a", "a"]);
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(5);
+
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Rolling {
+                batch_sizes: vec![BatchSize::Percent(100)],
+                topology: spread(BatchSize::Percent(50)),
+            },
+            &[task],
+            &hosts(5),
+            &[],
+        );
+
+        let sizes: Vec<usize> = batches.iter().map(|b| b.hosts.len()).collect();
+        assert_eq!(sizes, vec![3, 2]);
+        assert!(batches[0].hosts.contains(&"host5".to_string()));
+    }
+
+    #[test]
+    fn test_plan_canary_strategy() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![
-            create_test_task("task1", true),
-            create_test_task("task2", true),
-        ];
-        let hosts = vec![
-            "host1".to_string(),
-            "host2".to_string(),
-            "host3".to_string(),
-            "host4".to_string(),
-            "host5".to_string(),
-        ];
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(5);
 
-        let batches = planner.plan_rolling(&tasks, &hosts, 2);
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Canary {
+                canary_size: BatchSize::Hosts(1),
+                batch_sizes: vec![BatchSize::Percent(50)],
+                topology: None,
+            },
+            &[task],
+            &hosts(5),
+            &[],
+        );
 
-        // With 5 hosts and batch size 2, should have 3 batches
-        assert_eq!(batches.len(), 3);
+        let ids: Vec<&str> = batches.iter().map(|b| b.batch_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "canary",
+                "canary-health-check",
+                "rolling-batch-0",
+                "rolling-batch-1"
+            ]
+        );
+        assert_eq!(batches[0].hosts, vec!["host1".to_string()]);
 
-        // First batch should have 2 hosts
-        assert_eq!(batches[0].batch_id, "rolling-0");
-        assert_eq!(batches[0].hosts, vec!["host1", "host2"]);
-        assert_eq!(batches[0].tasks.len(), 2);
-        assert!(batches[0].dependencies.is_empty());
+        let gate = &batches[1];
+        assert_eq!(gate.stage_gate, Some(StageGate::HealthCheck));
+        assert!(gate.tasks.is_empty());
+        assert_eq!(gate.hosts, vec!["host1".to_string()]);
+        assert_eq!(gate.dependencies, vec!["canary".to_string()]);
 
-        // Second batch should have 2 hosts
-        assert_eq!(batches[1].batch_id, "rolling-1");
-        assert_eq!(batches[1].hosts, vec!["host3", "host4"]);
-        assert_eq!(batches[1].dependencies, vec!["rolling-0"]);
+        // The rollout waits for the health check, in halves of the other hosts
+        assert_eq!(
+            batches[2].dependencies,
+            vec!["canary-health-check".to_string()]
+        );
+        assert_eq!(batches[2].hosts.len(), 2);
+        assert_eq!(batches[3].hosts.len(), 2);
+    }
 
-        // Third batch should have 1 host
-        assert_eq!(batches[2].batch_id, "rolling-2");
-        assert_eq!(batches[2].hosts, vec!["host5"]);
-        assert_eq!(batches[2].dependencies, vec!["rolling-1"]);
+    #[test]
+    fn test_canary_rollout_spreads_failure_domains() {
+        let planner = zoned_planner(&["a", "a", "a", "b", "b"]);
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(5);
+
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Canary {
+                canary_size: BatchSize::Hosts(1),
+                batch_sizes: vec![BatchSize::Hosts(2)],
+                topology: spread(BatchSize::Hosts(1)),
+            },
+            &[task],
+            &hosts(5),
+            &[],
+        );
+
+        let rollout: Vec<&Vec<String>> = batches[2..].iter().map(|b| &b.hosts).collect();
+        assert_eq!(
+            rollout,
+            vec![
+                &vec!["host2".to_string(), "host4".to_string()],
+                &vec!["host3".to_string(), "host5".to_string()],
+            ]
+        );
     }
 
     #[test]
-    fn test_plan_host_pinned_strategy() {
+    fn test_canary_covering_all_hosts_has_no_gate() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![
-            create_test_task("task1", true),
-            create_test_task("task2", false),
-        ];
-        let hosts = vec![
-            "host1".to_string(),
-            "host2".to_string(),
-            "host3".to_string(),
-        ];
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::Canary {
+                canary_size: BatchSize::Percent(100),
+                batch_sizes: vec![BatchSize::Percent(100)],
+                topology: None,
+            },
+            &[create_test_task("task1", true)],
+            &hosts(2),
+            &[],
+        );
 
-        let batches = planner.plan_host_pinned(&tasks, &hosts);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].batch_id, "canary");
+    }
 
-        // Should have one batch per host
-        assert_eq!(batches.len(), 3);
+    fn cohorts() -> HashMap<String, Vec<String>> {
+        HashMap::from([
+            (
+                "blue".to_string(),
+                vec!["host1".to_string(), "host2".to_string()],
+            ),
+            (
+                "green".to_string(),
+                vec!["host3".to_string(), "host4".to_string()],
+            ),
+        ])
+    }
 
+    fn blue_green() -> ExecutionStrategy {
+        ExecutionStrategy::BlueGreen {
+            blue: "blue".to_string(),
+            green: "green".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plan_blue_green_strategy() {
+        let planner = StrategyPlanner::new().with_cohorts(cohorts());
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(4);
+
+        let batches = plan(&planner, blue_green(), &[task], &hosts(4), &[]);
+
+        let ids: Vec<&str> = batches.iter().map(|b| b.batch_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["green", "green-health-check", "switch-to-green", "blue"]
+        );
+        assert_eq!(batches[0].hosts, vec!["host3", "host4"]);
+        assert_eq!(batches[2].stage_gate, Some(StageGate::TrafficSwitch));
+        assert_eq!(batches[2].hosts, vec!["host3", "host4"]);
+        assert_eq!(batches[3].hosts, vec!["host1", "host2"]);
+        assert_eq!(batches[3].tasks[0].hosts, vec!["host1", "host2"]);
+        assert_eq!(batches[3].dependencies, vec!["switch-to-green".to_string()]);
+    }
+
+    #[test]
+    fn test_blue_green_rejects_hosts_outside_cohorts() {
+        let planner = StrategyPlanner::new().with_cohorts(cohorts());
+        let task = create_test_task("task1", true);
+        let graph = DependencyGraphBuilder::new()
+            .build_from_tasks(std::slice::from_ref(&task))
+            .unwrap();
+
+        let result = planner.plan_strategy(&blue_green(), &[task], &hosts(5), &graph, &[]);
+        assert!(matches!(
+            result,
+            Err(PlanError::InvalidCohort { reason, .. }) if reason.contains("host5")
+        ));
+    }
+
+    #[test]
+    fn test_blue_green_rejects_empty_green_cohort() {
+        // As with --limit blue: only blue hosts are left to plan
+        let planner = StrategyPlanner::new().with_cohorts(cohorts());
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(2);
+        let graph = DependencyGraphBuilder::new()
+            .build_from_tasks(std::slice::from_ref(&task))
+            .unwrap();
+
+        let result = planner.plan_strategy(&blue_green(), &[task], &hosts(2), &graph, &[]);
+        assert!(matches!(
+            result,
+            Err(PlanError::InvalidCohort { group, .. }) if group == "green"
+        ));
+    }
+
+    #[test]
+    fn test_blue_green_requires_known_groups() {
+        let planner = StrategyPlanner::new();
+        let task = create_test_task("task1", true);
+        let graph = DependencyGraphBuilder::new()
+            .build_from_tasks(std::slice::from_ref(&task))
+            .unwrap();
+
+        let result = planner.plan_strategy(&blue_green(), &[task], &hosts(2), &graph, &[]);
+        assert!(matches!(
+            result,
+            Err(PlanError::InvalidCohort { group, .. }) if group == "blue"
+        ));
+    }
+
+    #[test]
+    fn test_plan_host_pinned_strategy() {
+        let planner = StrategyPlanner::new().with_forks(2);
+        let mut task1 = create_test_task("task1", true);
+        task1.hosts = hosts(3);
+        let mut task2 = with_dependencies(create_test_task("task2", false), &["task1"]);
+        task2.hosts = vec!["host1".to_string(), "host3".to_string()];
+        // Listed out of order: streams follow dependency order
+        let tasks = vec![task2, task1];
+
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::HostPinned,
+            &tasks,
+            &hosts(3),
+            &[],
+        );
+
+        assert_eq!(batches.len(), 3);
         for (i, batch) in batches.iter().enumerate() {
-            assert_eq!(batch.batch_id, format!("host-{}", i));
+            assert_eq!(batch.batch_id, format!("host-{i}"));
             assert_eq!(batch.hosts, vec![format!("host{}", i + 1)]);
-            assert_eq!(batch.tasks.len(), 2); // Each host gets all tasks
-            assert!(batch.dependencies.is_empty()); // Host-pinned runs in parallel
-
-            // Verify tasks are assigned to correct host
             for task in &batch.tasks {
-                assert_eq!(task.hosts, vec![format!("host{}", i + 1)]);
+                assert_eq!(task.hosts, batch.hosts);
             }
         }
+        assert_eq!(task_ids(&batches[0]), vec!["task1", "task2"]);
+        assert_eq!(task_ids(&batches[1]), vec!["task1"]);
+
+        // Two streams run at once; the third waits for the first
+        assert!(batches[0].dependencies.is_empty());
+        assert!(batches[1].dependencies.is_empty());
+        assert_eq!(batches[2].dependencies, vec!["host-0"]);
     }
 
     #[test]
     fn test_plan_binary_hybrid_strategy() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![create_test_task("task1", true)];
-        let hosts = vec!["host1".to_string()];
+        let tasks = vec![
+            create_test_task("a", true),
+            create_test_task("b", true),
+            create_test_task("ssh", true),
+        ];
+        let deployments = vec![create_deployment("group_0", &["a", "b"])];
 
-        // Binary hybrid should currently use linear strategy
-        let batches = planner.plan_binary_hybrid(&tasks, &hosts);
-        assert_eq!(batches.len(), 1);
-        assert_eq!(batches[0].batch_id, "linear-batch-0");
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::BinaryHybrid,
+            &tasks,
+            &hosts(2),
+            &deployments,
+        );
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].binary_deployment.as_deref(), Some("group_0"));
+        assert_eq!(task_ids(&batches[0]), vec!["a", "b"]);
+        assert!(batches[0].parallel_groups.is_empty());
+        assert_eq!(batches[1].binary_deployment, None);
+        assert_eq!(task_ids(&batches[1]), vec!["ssh"]);
+    }
+
+    #[test]
+    fn test_binary_deployment_split_around_ssh_task() {
+        let planner = StrategyPlanner::new();
+        let tasks = vec![
+            create_test_task("a", true),
+            with_dependencies(create_test_task("ssh", true), &["a"]),
+            with_dependencies(create_test_task("b", true), &["ssh"]),
+            create_test_task("c", true),
+        ];
+        let deployments = vec![create_deployment("group_0", &["a", "b", "c"])];
+
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::BinaryOnly,
+            &tasks,
+            &hosts(2),
+            &deployments,
+        );
+
+        assert_eq!(batches.len(), 3);
+        assert_eq!(task_ids(&batches[0]), vec!["a", "c"]);
+        assert_eq!(batches[0].binary_deployment.as_deref(), Some("group_0"));
+        assert_eq!(task_ids(&batches[1]), vec!["ssh"]);
+        assert_eq!(batches[1].dependencies, vec!["batch-0"]);
+        assert_eq!(task_ids(&batches[2]), vec!["b"]);
+        assert_eq!(batches[2].binary_deployment.as_deref(), Some("group_0"));
+        assert_eq!(batches[2].dependencies, vec!["batch-1"]);
     }
 
     #[test]
-    fn test_plan_binary_only_strategy() {
+    fn test_binary_strategy_without_deployments_runs_over_ssh() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![create_test_task("task1", true)];
-        let hosts = vec!["host1".to_string()];
+        let tasks = vec![
+            create_test_task("task1", true),
+            with_dependencies(create_test_task("task2", true), &["task1"]),
+        ];
 
-        // Binary only should currently use linear strategy
-        let batches = planner.plan_binary_only(&tasks, &hosts);
-        assert_eq!(batches.len(), 1);
-        assert_eq!(batches[0].batch_id, "linear-batch-0");
+        let batches = plan(
+            &planner,
+            ExecutionStrategy::BinaryHybrid,
+            &tasks,
+            &hosts(2),
+            &[],
+        );
+
+        assert_eq!(batches.len(), 2);
+        assert!(batches.iter().all(|b| b.binary_deployment.is_none()));
+        assert_eq!(batches[1].dependencies, vec!["batch-0"]);
     }
 
     #[test]
     fn test_plan_strategy_all_variants() {
-        let planner = StrategyPlanner::new();
+        let planner = StrategyPlanner::new().with_cohorts(HashMap::from([
+            ("blue".to_string(), vec!["host1".to_string()]),
+            ("green".to_string(), vec!["host2".to_string()]),
+        ]));
         let tasks = vec![
             create_test_task("task1", true),
             create_test_task("task2", false),
         ];
-        let hosts = vec!["host1".to_string(), "host2".to_string()];
 
         let strategies = vec![
             ExecutionStrategy::Linear,
             ExecutionStrategy::Free,
-            ExecutionStrategy::Rolling { batch_size: 1 },
+            ExecutionStrategy::Rolling {
+                batch_sizes: vec![BatchSize::Hosts(1)],
+                topology: None,
+            },
             ExecutionStrategy::HostPinned,
             ExecutionStrategy::BinaryHybrid,
             ExecutionStrategy::BinaryOnly,
+            ExecutionStrategy::Canary {
+                canary_size: BatchSize::Hosts(1),
+                batch_sizes: vec![BatchSize::Percent(100)],
+                topology: None,
+            },
+            blue_green(),
         ];
 
         for strategy in strategies {
-            let batches = planner.plan_strategy(&strategy, &tasks, &hosts);
+            let batches = plan(&planner, strategy.clone(), &tasks, &hosts(2), &[]);
             assert!(
                 !batches.is_empty(),
                 "Strategy {:?} produced no batches",
@@ -395,23 +1297,20 @@ mod tests {
     #[test]
     fn test_empty_tasks() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![];
-        let hosts = vec!["host1".to_string()];
 
-        let batches = planner.plan_linear(&tasks, &hosts);
-        assert!(batches.is_empty());
-
-        let batches = planner.plan_free(&tasks, &hosts);
-        assert!(batches.is_empty());
+        for strategy in [ExecutionStrategy::Linear, ExecutionStrategy::Free] {
+            let batches = plan(&planner, strategy, &[], &hosts(1), &[]);
+            assert!(batches.is_empty());
+        }
     }
 
     #[test]
     fn test_single_host() {
         let planner = StrategyPlanner::new();
-        let tasks = vec![create_test_task("task1", true)];
-        let hosts = vec!["host1".to_string()];
+        let mut task = create_test_task("task1", true);
+        task.hosts = hosts(1);
 
-        let batches = planner.plan_strategy(&ExecutionStrategy::Linear, &tasks, &hosts);
+        let batches = plan(&planner, ExecutionStrategy::Linear, &[task], &hosts(1), &[]);
         assert_eq!(batches.len(), 1);
         assert_eq!(batches[0].hosts, vec!["host1"]);
     }
@@ -103,7 +103,12 @@ impl BinarySuitabilityAnalyzer {
 
     fn has_unsuitable_arguments(&self, task: &TaskPlan) -> bool {
         // Check for arguments that require runtime host-specific resolution
-        if task.args.contains_key("delegate_to") {
+        if task.args.contains_key("delegate_to") || task.delegate_to.is_some() {
+            return true;
+        }
+
+        // Loops over a template expression are only known at runtime
+        if matches!(task.loop_items, Some(serde_json::Value::String(_))) {
             return true;
         }
 
@@ -116,6 +121,14 @@ impl BinarySuitabilityAnalyzer {
             return true;
         }
 
+        if [&task.changed_when, &task.failed_when]
+            .into_iter()
+            .flatten()
+            .any(|expression| expression.contains("hostvars") || expression.contains("{{"))
+        {
+            return true;
+        }
+
         false
     }
 
@@ -274,6 +287,15 @@ mod tests {
             can_run_parallel: true,
             estimated_duration: Some(Duration::from_secs(5)),
             risk_level: RiskLevel::Low,
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
         }
     }
 
@@ -424,6 +446,46 @@ mod tests {
         assert!(analyzer.has_unsuitable_arguments(&task));
     }
 
+    #[test]
+    fn test_has_unsuitable_arguments_delegate_to_field() {
+        let analyzer = BinarySuitabilityAnalyzer::new();
+        let mut task = create_test_task("test", "shell");
+        task.delegate_to = Some("bastion".to_string());
+
+        assert!(analyzer.has_unsuitable_arguments(&task));
+    }
+
+    #[test]
+    fn test_has_unsuitable_arguments_runtime_loop() {
+        let analyzer = BinarySuitabilityAnalyzer::new();
+        let mut task = create_test_task("test", "file");
+        task.loop_items = Some(serde_json::json!(["a", "b"]));
+        assert!(!analyzer.has_unsuitable_arguments(&task));
+
+        task.loop_items = Some(serde_json::json!("{{ directories }}"));
+        assert!(analyzer.has_unsuitable_arguments(&task));
+    }
+
+    #[test]
+    fn test_has_unsuitable_arguments_hostvars_failed_when() {
+        let analyzer = BinarySuitabilityAnalyzer::new();
+        let mut task = create_test_task("test", "shell");
+        task.failed_when = Some("result.rc != hostvars['db1'].expected_rc".to_string());
+
+        assert!(analyzer.has_unsuitable_arguments(&task));
+    }
+
+    #[test]
+    fn test_has_unsuitable_arguments_templated_changed_when() {
+        let analyzer = BinarySuitabilityAnalyzer::new();
+        let mut task = create_test_task("test", "shell");
+        task.changed_when = Some("result.rc == 2".to_string());
+        assert!(!analyzer.has_unsuitable_arguments(&task));
+
+        task.changed_when = Some("result.stdout != '{{ expected }}'".to_string());
+        assert!(analyzer.has_unsuitable_arguments(&task));
+    }
+
     #[test]
     fn test_has_unsuitable_arguments_local_action() {
         let analyzer = BinarySuitabilityAnalyzer::new();
@@ -0,0 +1,308 @@
+use crate::types::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Lays out when each task runs on each host, given the batch layout, the
+/// play strategy and a pool of `forks` workers shared by all hosts.
+///
+/// Plays run one after another. Within a batch, tasks in parallel groups
+/// start together and the remaining tasks run one after another, as in
+/// `TaskEstimator::estimate_batch_duration`. With the free strategy each
+/// host moves on as soon as it is done; otherwise every host waits for the
+/// slowest one before the next step starts.
+pub struct TimelineBuilder {
+    forks: usize,
+}
+
+impl TimelineBuilder {
+    pub fn new(forks: u32) -> Self {
+        Self {
+            forks: forks.max(1) as usize,
+        }
+    }
+
+    pub fn build(&self, plays: &[PlayPlan]) -> Vec<HostTimeline> {
+        let mut host_order: Vec<String> = Vec::new();
+        let mut entries: HashMap<String, Vec<TimelineEntry>> = HashMap::new();
+        let mut play_start = Duration::ZERO;
+
+        for play in plays {
+            let play_end = self.schedule_play(play, play_start, &mut |host, entry| {
+                if !entries.contains_key(host) {
+                    host_order.push(host.to_string());
+                }
+                entries.entry(host.to_string()).or_default().push(entry);
+            });
+            play_start = play_end;
+        }
+
+        host_order
+            .into_iter()
+            .map(|host| {
+                let mut host_entries = entries.remove(&host).unwrap_or_default();
+                host_entries.sort_by_key(|entry| entry.start);
+                let finish = host_entries
+                    .iter()
+                    .map(|entry| entry.end)
+                    .max()
+                    .unwrap_or_default();
+                HostTimeline {
+                    host,
+                    entries: host_entries,
+                    finish,
+                }
+            })
+            .collect()
+    }
+
+    /// Schedules every task run of a play and returns when the play ends.
+    fn schedule_play(
+        &self,
+        play: &PlayPlan,
+        play_start: Duration,
+        record: &mut dyn FnMut(&str, TimelineEntry),
+    ) -> Duration {
+        let per_host = play.strategy == ExecutionStrategy::Free;
+        let mut workers = vec![play_start; self.forks];
+        let mut host_ready: HashMap<&str, Duration> = HashMap::new();
+        let mut batch_end: HashMap<&str, Duration> = HashMap::new();
+        let mut play_end = play_start;
+
+        for batch in &play.batches {
+            let batch_ready = batch
+                .dependencies
+                .iter()
+                .filter_map(|id| batch_end.get(id.as_str()).copied())
+                .max()
+                .unwrap_or(play_start);
+
+            let mut step_ready = batch_ready;
+            let mut end_of_batch = batch_ready;
+
+            for step in steps(batch) {
+                let mut step_end = step_ready;
+                let mut host_step_end: HashMap<&str, Duration> = HashMap::new();
+
+                for task in step {
+                    let duration = task.estimated_duration.unwrap_or_default();
+                    for host in &task.hosts {
+                        let ready = if per_host {
+                            host_ready.get(host.as_str()).copied().unwrap_or(play_start)
+                        } else {
+                            step_ready
+                        };
+
+                        // Take the fork that frees up first
+                        let worker = (0..workers.len())
+                            .min_by_key(|&index| workers[index])
+                            .unwrap_or_default();
+                        let start = ready.max(workers[worker]);
+                        let end = start + duration;
+                        workers[worker] = end;
+
+                        record(
+                            host,
+                            TimelineEntry {
+                                play_id: play.play_id.clone(),
+                                batch_id: batch.batch_id.clone(),
+                                task_id: task.task_id.clone(),
+                                task_name: task.name.clone(),
+                                start,
+                                end,
+                                fork_wait: start - ready,
+                            },
+                        );
+
+                        let host_end = host_step_end.entry(host.as_str()).or_insert(end);
+                        *host_end = (*host_end).max(end);
+                        step_end = step_end.max(end);
+                    }
+                }
+
+                // A host finishes a step once all of its parallel tasks are done
+                for (host, end) in host_step_end {
+                    host_ready.insert(host, end);
+                }
+                step_ready = step_end;
+                end_of_batch = end_of_batch.max(step_end);
+            }
+
+            batch_end.insert(batch.batch_id.as_str(), end_of_batch);
+            play_end = play_end.max(end_of_batch);
+        }
+
+        play_end
+    }
+}
+
+/// Splits a batch into steps: the tasks of its parallel groups first, then
+/// each remaining task on its own.
+fn steps(batch: &ExecutionBatch) -> Vec<Vec<&TaskPlan>> {
+    let in_group = |task: &TaskPlan| {
+        batch
+            .parallel_groups
+            .iter()
+            .any(|group| group.tasks.contains(&task.task_id))
+    };
+
+    let parallel: Vec<&TaskPlan> = batch.tasks.iter().filter(|task| in_group(task)).collect();
+    let mut steps = Vec::new();
+    if !parallel.is_empty() {
+        steps.push(parallel);
+    }
+    steps.extend(
+        batch
+            .tasks
+            .iter()
+            .filter(|task| !in_group(task))
+            .map(|task| vec![task]),
+    );
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::fixtures;
+
+    fn create_test_task(id: &str, secs: u64, hosts: &[&str]) -> TaskPlan {
+        TaskPlan {
+            hosts: fixtures::strings(hosts),
+            estimated_duration: Some(Duration::from_secs(secs)),
+            ..fixtures::task(id)
+        }
+    }
+
+    fn create_batch(id: &str, tasks: Vec<TaskPlan>, dependencies: &[&str]) -> ExecutionBatch {
+        ExecutionBatch {
+            dependencies: fixtures::strings(dependencies),
+            ..fixtures::batch(id, tasks)
+        }
+    }
+
+    fn create_play(
+        id: &str,
+        strategy: ExecutionStrategy,
+        batches: Vec<ExecutionBatch>,
+    ) -> PlayPlan {
+        PlayPlan {
+            strategy,
+            ..fixtures::play(id, batches)
+        }
+    }
+
+    fn timeline<'a>(timelines: &'a [HostTimeline], host: &str) -> &'a HostTimeline {
+        timelines.iter().find(|t| t.host == host).unwrap()
+    }
+
+    #[test]
+    fn test_fork_limit_delays_hosts() {
+        let play = create_play(
+            "play-0",
+            ExecutionStrategy::Linear,
+            vec![create_batch(
+                "batch-0",
+                vec![create_test_task("a", 10, &["host1", "host2", "host3"])],
+                &[],
+            )],
+        );
+
+        let timelines = TimelineBuilder::new(2).build(&[play]);
+
+        assert_eq!(timelines.len(), 3);
+        let host3 = timeline(&timelines, "host3");
+        assert_eq!(host3.entries[0].start, Duration::from_secs(10));
+        assert_eq!(host3.entries[0].fork_wait, Duration::from_secs(10));
+        assert_eq!(host3.finish, Duration::from_secs(20));
+        assert_eq!(
+            timeline(&timelines, "host1").entries[0].fork_wait,
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_linear_waits_for_all_hosts() {
+        let play = create_play(
+            "play-0",
+            ExecutionStrategy::Linear,
+            vec![
+                create_batch(
+                    "batch-0",
+                    vec![
+                        create_test_task("a", 10, &["host1"]),
+                        create_test_task("b", 1, &["host2"]),
+                    ],
+                    &[],
+                ),
+                create_batch(
+                    "batch-1",
+                    vec![create_test_task("c", 1, &["host1", "host2"])],
+                    &["batch-0"],
+                ),
+            ],
+        );
+
+        let timelines = TimelineBuilder::new(50).build(&[play]);
+
+        // b waits for a, the previous step, even though it runs on another host
+        let host2 = timeline(&timelines, "host2");
+        assert_eq!(host2.entries[0].start, Duration::from_secs(10));
+        assert_eq!(host2.entries[1].start, Duration::from_secs(11));
+    }
+
+    #[test]
+    fn test_free_strategy_runs_hosts_independently() {
+        let play = create_play(
+            "play-0",
+            ExecutionStrategy::Free,
+            vec![
+                create_batch("batch-0", vec![create_test_task("a", 10, &["host1"])], &[]),
+                create_batch(
+                    "batch-1",
+                    vec![create_test_task("b", 1, &["host1", "host2"])],
+                    &[],
+                ),
+            ],
+        );
+
+        let timelines = TimelineBuilder::new(50).build(&[play]);
+
+        assert_eq!(
+            timeline(&timelines, "host2").entries[0].start,
+            Duration::ZERO
+        );
+        assert_eq!(
+            timeline(&timelines, "host1").entries[1].start,
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn test_plays_run_in_sequence() {
+        let first = create_play(
+            "play-0",
+            ExecutionStrategy::Linear,
+            vec![create_batch(
+                "batch-0",
+                vec![create_test_task("a", 5, &["host1"])],
+                &[],
+            )],
+        );
+        let second = create_play(
+            "play-1",
+            ExecutionStrategy::Linear,
+            vec![create_batch(
+                "batch-0",
+                vec![create_test_task("b", 2, &["host2"])],
+                &[],
+            )],
+        );
+
+        let timelines = TimelineBuilder::new(50).build(&[first, second]);
+
+        let host2 = timeline(&timelines, "host2");
+        assert_eq!(host2.entries[0].play_id, "play-1");
+        assert_eq!(host2.entries[0].start, Duration::from_secs(5));
+        assert_eq!(host2.finish, Duration::from_secs(7));
+    }
+}
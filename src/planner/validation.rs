@@ -1,5 +1,7 @@
 use crate::planner::error::PlanError;
+use crate::planner::handlers::HandlerScheduler;
 use crate::types::*;
+use std::collections::HashSet;
 
 pub struct PlanValidator;
 
@@ -26,6 +28,13 @@ impl PlanValidator {
             self.validate_play(play, &mut errors, &mut warnings);
         }
 
+        if !plan.redacted_secrets.is_empty() && plan.sealed_secrets.is_none() {
+            warnings.push(format!(
+                "Plan references {} redacted secrets but carries no sealed secrets for the executor",
+                plan.redacted_secrets.len()
+            ));
+        }
+
         // Validate binary deployments
         for deployment in &plan.binary_deployments {
             self.validate_binary_deployment(deployment, &mut errors, &mut warnings);
@@ -43,9 +52,37 @@ impl PlanValidator {
             warnings.push(format!("Play '{}' has no execution batches", play.name));
         }
 
+        if let Some(percent) = play.max_fail_percentage.filter(|&percent| percent > 100) {
+            errors.push(format!(
+                "Play '{}' has max_fail_percentage {} above 100",
+                play.name, percent
+            ));
+        }
+
         for batch in &play.batches {
             self.validate_batch(batch, errors, warnings);
         }
+
+        self.validate_notify_targets(play, errors);
+    }
+
+    fn validate_notify_targets(&self, play: &PlayPlan, errors: &mut Vec<String>) {
+        let scheduler = HandlerScheduler::new();
+        let mut reported = HashSet::new();
+
+        // Rolling batches repeat tasks, so each target is reported once per task
+        for task in play.batches.iter().flat_map(|batch| &batch.tasks) {
+            for target in &task.notify {
+                if scheduler.resolve(target, &play.handlers).is_empty()
+                    && reported.insert((task.task_id.as_str(), target.as_str()))
+                {
+                    errors.push(format!(
+                        "Task '{}' notifies '{}' which matches no handler in play '{}'",
+                        task.task_id, target, play.name
+                    ));
+                }
+            }
+        }
     }
 
     fn validate_batch(
@@ -54,7 +91,8 @@ impl PlanValidator {
         errors: &mut Vec<String>,
         warnings: &mut Vec<String>,
     ) {
-        if batch.tasks.is_empty() {
+        // Stage gates run no tasks by design
+        if batch.tasks.is_empty() && batch.stage_gate.is_none() {
             warnings.push(format!("Batch '{}' has no tasks", batch.batch_id));
         }
 
@@ -102,6 +140,15 @@ impl PlanValidator {
             ));
         }
 
+        for file in &deployment.embedded_data.static_files {
+            if file.checksum.is_none() {
+                warnings.push(format!(
+                    "Binary deployment '{}' embeds '{}' which was not found, so it has no checksum",
+                    deployment.deployment_id, file.src_path
+                ));
+            }
+        }
+
         // Validate compilation requirements
         let req = &deployment.compilation_requirements;
         if req.rust_version.is_empty() {
@@ -131,6 +178,7 @@ mod tests {
             metadata: PlanMetadata {
                 created_at: Utc::now(),
                 rustle_plan_version: "1.0.0".to_string(),
+                schema_version: PLAN_SCHEMA_VERSION,
                 playbook_hash: "abc123".to_string(),
                 inventory_hash: "def456".to_string(),
                 planning_options: PlanningOptions {
@@ -141,11 +189,18 @@ mod tests {
                     diff_mode: false,
                     forks: 5,
                     serial: None,
+                    max_fail_percentage: None,
+                    any_errors_fatal: false,
+                    host_order: HostOrder::Inventory,
+                    redact_keys: vec![],
                     strategy: ExecutionStrategy::Linear,
                     binary_threshold: 10,
                     force_binary: false,
                     force_ssh: false,
+                    reproducible: false,
                 },
+                host_order: HostOrder::Inventory,
+                signature: None,
             },
             plays: vec![],
             binary_deployments: vec![],
@@ -155,6 +210,11 @@ mod tests {
             parallelism_score: 0.8,
             network_efficiency_score: 0.9,
             hosts: vec!["host1".to_string(), "host2".to_string()],
+            filtered_tasks: vec![],
+            host_timelines: vec![],
+            redacted_secrets: vec![],
+            sealed_secrets: None,
+            plan_digest: None,
         }
     }
 
@@ -164,10 +224,14 @@ mod tests {
             name: "Test Play".to_string(),
             strategy: ExecutionStrategy::Linear,
             serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            host_order: HostOrder::Inventory,
             hosts: vec!["host1".to_string()],
             batches: vec![],
             handlers: vec![],
             estimated_duration: Some(Duration::from_secs(30)),
+            critical_path: None,
         }
     }
 
@@ -179,6 +243,9 @@ mod tests {
             parallel_groups: vec![],
             dependencies: vec![],
             estimated_duration: Some(Duration::from_secs(10)),
+            binary_deployment: None,
+            proceed_if: None,
+            stage_gate: None,
         }
     }
 
@@ -197,12 +264,22 @@ mod tests {
             can_run_parallel: true,
             estimated_duration: Some(Duration::from_secs(5)),
             risk_level: RiskLevel::Low,
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
         }
     }
 
     fn create_test_binary_deployment() -> BinaryDeployment {
         BinaryDeployment {
             deployment_id: "deploy-1".to_string(),
+            play_id: "play-0".to_string(),
             target_hosts: vec!["host1".to_string()],
             binary_name: "test-binary".to_string(),
             tasks: vec!["task-1".to_string()],
@@ -284,6 +361,22 @@ mod tests {
             .any(|w| w.contains("has no execution batches")));
     }
 
+    #[test]
+    fn test_validate_max_fail_percentage_above_100() {
+        let validator = PlanValidator::new();
+        let mut plan = create_test_plan();
+        let mut play = create_test_play();
+        play.max_fail_percentage = Some(150);
+        plan.plays.push(play);
+
+        let result = validator.validate(&plan).unwrap();
+        assert!(!result.is_valid);
+        assert!(result
+            .errors
+            .iter()
+            .any(|e| e.contains("max_fail_percentage 150 above 100")));
+    }
+
     #[test]
     fn test_validate_batch_no_tasks() {
         let validator = PlanValidator::new();
@@ -297,6 +390,20 @@ mod tests {
         assert!(result.warnings.iter().any(|w| w.contains("has no tasks")));
     }
 
+    #[test]
+    fn test_validate_stage_gate_without_tasks() {
+        let validator = PlanValidator::new();
+        let mut plan = create_test_plan();
+        let mut play = create_test_play();
+        let mut batch = create_test_batch();
+        batch.stage_gate = Some(StageGate::HealthCheck);
+        play.batches.push(batch);
+        plan.plays.push(play);
+
+        let result = validator.validate(&plan).unwrap();
+        assert!(!result.warnings.iter().any(|w| w.contains("has no tasks")));
+    }
+
     #[test]
     fn test_validate_batch_no_hosts() {
         let validator = PlanValidator::new();
@@ -364,6 +471,64 @@ mod tests {
             .any(|w| w.contains("not in the same batch")));
     }
 
+    fn create_test_handler() -> HandlerPlan {
+        HandlerPlan {
+            handler_id: "handler-1".to_string(),
+            name: "restart nginx".to_string(),
+            module: "service".to_string(),
+            args: HashMap::new(),
+            conditions: vec![],
+            execution_order: 0,
+            listen: vec!["web config changed".to_string()],
+            no_log: false,
+            notified_by: vec![],
+            flushes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_validate_notify_resolves_by_name_or_topic() {
+        let validator = PlanValidator::new();
+        let mut plan = create_test_plan();
+        let mut play = create_test_play();
+        let mut batch = create_test_batch();
+
+        let mut task = create_test_task();
+        task.notify = vec![
+            "restart nginx".to_string(),
+            "web config changed".to_string(),
+        ];
+        batch.tasks = vec![task];
+        play.batches.push(batch);
+        play.handlers.push(create_test_handler());
+        plan.plays.push(play);
+
+        let result = validator.validate(&plan).unwrap();
+        assert!(result.is_valid);
+    }
+
+    #[test]
+    fn test_validate_unresolved_notify() {
+        let validator = PlanValidator::new();
+        let mut plan = create_test_plan();
+        let mut play = create_test_play();
+
+        let mut task = create_test_task();
+        task.notify = vec!["restart ngnix".to_string()];
+        for _ in 0..2 {
+            let mut batch = create_test_batch();
+            batch.tasks = vec![task.clone()];
+            play.batches.push(batch);
+        }
+        play.handlers.push(create_test_handler());
+        plan.plays.push(play);
+
+        let result = validator.validate(&plan).unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("notifies 'restart ngnix'"));
+    }
+
     #[test]
     fn test_validate_binary_deployment_valid() {
         let validator = PlanValidator::new();
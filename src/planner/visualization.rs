@@ -0,0 +1,451 @@
+use crate::types::*;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Renders execution plans as diagrams: Graphviz DOT, Mermaid flowcharts
+/// for PR comments, PlantUML, and a single-file HTML report.
+///
+/// Tasks are drawn once per batch they run in, so rolling plays show each
+/// batch's copy, and dependency edges point at the latest copy of the task
+/// depended on.
+pub struct PlanVisualizer;
+
+impl PlanVisualizer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn to_dot(&self, plan: &ExecutionPlan) -> String {
+        let mut out = String::new();
+        out.push_str("digraph execution_plan {\n");
+        out.push_str("  rankdir=TB;\n");
+        out.push_str("  node [shape=box];\n");
+
+        for (play_idx, play) in plan.plays.iter().enumerate() {
+            let _ = writeln!(out, "  subgraph cluster_{play_idx} {{");
+            let _ = writeln!(out, "    label=\"{}\";", dot_escape(&play.name));
+
+            for batch in &play.batches {
+                for task in &batch.tasks {
+                    let _ = writeln!(
+                        out,
+                        "    \"{}\" [label=\"{}\"];",
+                        dot_escape(&task.task_id),
+                        dot_escape(&task.name)
+                    );
+
+                    for dep in &task.dependencies {
+                        let _ = writeln!(
+                            out,
+                            "    \"{}\" -> \"{}\";",
+                            dot_escape(dep),
+                            dot_escape(&task.task_id)
+                        );
+                    }
+                }
+            }
+
+            out.push_str("  }\n");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_mermaid(&self, plan: &ExecutionPlan) -> String {
+        let mut out = String::from("flowchart TD\n");
+        let mut edges = Vec::new();
+
+        for (play_idx, play) in plan.plays.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  subgraph p{play_idx}[\"{}\"]",
+                mermaid_escape(&play.name)
+            );
+            let mut latest = HashMap::new();
+            for (batch_idx, batch) in play.batches.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "    subgraph p{play_idx}b{batch_idx}[\"{}\"]",
+                    mermaid_escape(&batch_label(batch))
+                );
+                for (task_idx, task) in batch.tasks.iter().enumerate() {
+                    let node = format!("p{play_idx}b{batch_idx}t{task_idx}");
+                    let _ = writeln!(
+                        out,
+                        "      {node}[\"{}<br/>{}\"]:::{}",
+                        mermaid_escape(&task.name),
+                        mermaid_escape(&task.module),
+                        risk_class(&task.risk_level)
+                    );
+                    edges.extend(dependency_edges(task, &latest, &node));
+                    latest.insert(task.task_id.as_str(), node);
+                }
+                out.push_str("    end\n");
+            }
+            out.push_str("  end\n");
+        }
+
+        for (from, to) in edges {
+            let _ = writeln!(out, "  {from} --> {to}");
+        }
+        for (risk, fill, stroke) in RISK_COLORS {
+            let _ = writeln!(out, "  classDef {risk} fill:{fill},stroke:{stroke}");
+        }
+        out
+    }
+
+    pub fn to_plantuml(&self, plan: &ExecutionPlan) -> String {
+        let mut out = String::from("@startuml\nskinparam rectangle {\n");
+        for (risk, fill, stroke) in RISK_COLORS {
+            let _ = writeln!(out, "  BackgroundColor<<{risk}>> {fill}");
+            let _ = writeln!(out, "  BorderColor<<{risk}>> {stroke}");
+        }
+        out.push_str("}\n");
+        let mut edges = Vec::new();
+
+        for (play_idx, play) in plan.plays.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "rectangle \"{}\" as p{play_idx} {{",
+                plantuml_escape(&play.name)
+            );
+            let mut latest = HashMap::new();
+            for (batch_idx, batch) in play.batches.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "  rectangle \"{}\" as p{play_idx}b{batch_idx} {{",
+                    plantuml_escape(&batch_label(batch))
+                );
+                for (task_idx, task) in batch.tasks.iter().enumerate() {
+                    let node = format!("p{play_idx}b{batch_idx}t{task_idx}");
+                    let _ = writeln!(
+                        out,
+                        "    rectangle \"{}\\n{}\" <<{}>> as {node}",
+                        plantuml_escape(&task.name),
+                        plantuml_escape(&task.module),
+                        risk_class(&task.risk_level)
+                    );
+                    edges.extend(dependency_edges(task, &latest, &node));
+                    latest.insert(task.task_id.as_str(), node);
+                }
+                out.push_str("  }\n");
+            }
+            out.push_str("}\n");
+        }
+
+        for (from, to) in edges {
+            let _ = writeln!(out, "{from} --> {to}");
+        }
+        out.push_str("@enduml\n");
+        out
+    }
+
+    /// A self-contained HTML report with inline styles and no scripts, so
+    /// it opens offline and can be attached to a review as one file
+    pub fn to_html(&self, plan: &ExecutionPlan) -> String {
+        let colors = host_set_colors(plan);
+        let mut out = String::new();
+        out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        out.push_str("<title>Execution plan</title>\n<style>\n");
+        out.push_str(HTML_STYLE);
+        for (risk, fill, stroke) in RISK_COLORS {
+            let _ = writeln!(
+                out,
+                ".risk-{risk} {{ background: {fill}; border-color: {stroke}; }}"
+            );
+        }
+        out.push_str("</style>\n</head>\n<body>\n");
+
+        out.push_str("<h1>Execution plan</h1>\n<dl class=\"summary\">\n");
+        let strategy = format!("{:?}", plan.metadata.planning_options.strategy);
+        for (term, value) in [
+            ("Created", plan.metadata.created_at.to_rfc3339()),
+            ("Strategy", strategy),
+            ("Plays", plan.plays.len().to_string()),
+            ("Tasks", plan.total_tasks.to_string()),
+            ("Hosts", plan.hosts.len().to_string()),
+            (
+                "Binary deployments",
+                plan.binary_deployments.len().to_string(),
+            ),
+            ("Estimated duration", show_duration(plan.estimated_duration)),
+        ] {
+            let _ = writeln!(
+                out,
+                "<dt>{}</dt><dd>{}</dd>",
+                html_escape(term),
+                html_escape(&value)
+            );
+        }
+        out.push_str("</dl>\n");
+
+        out.push_str("<p class=\"legend\">Risk:");
+        for (risk, _, _) in RISK_COLORS {
+            let _ = write!(out, " <span class=\"badge risk-{risk}\">{risk}</span>");
+        }
+        out.push_str("</p>\n");
+
+        for play in &plan.plays {
+            self.html_play(&mut out, play, plan, &colors);
+        }
+
+        if !plan.binary_deployments.is_empty() {
+            out.push_str("<h2>Binary deployments</h2>\n<table>\n");
+            out.push_str(
+                "<tr><th>Play</th><th>Deployment</th><th>Binary</th><th>Hosts</th><th>Tasks</th><th>Size</th></tr>\n",
+            );
+            for deployment in &plan.binary_deployments {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td><span class=\"swatch\" style=\"background: {}\"></span>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{} bytes</td></tr>",
+                    html_escape(&deployment.play_id),
+                    colors[&host_set(&deployment.target_hosts)],
+                    html_escape(&deployment.deployment_id),
+                    html_escape(&deployment.binary_name),
+                    html_escape(&deployment.target_hosts.join(", ")),
+                    html_escape(&deployment.tasks.join(", ")),
+                    deployment.estimated_size
+                );
+            }
+            out.push_str("</table>\n");
+        }
+
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn html_play(
+        &self,
+        out: &mut String,
+        play: &PlayPlan,
+        plan: &ExecutionPlan,
+        colors: &HashMap<String, String>,
+    ) {
+        let _ = writeln!(
+            out,
+            "<section class=\"play\">\n<h2>{}</h2>\n<p class=\"meta\">{} &middot; {} hosts &middot; {}</p>",
+            html_escape(&play.name),
+            html_escape(&format!("{:?}", play.strategy)),
+            play.hosts.len(),
+            html_escape(&show_duration(play.estimated_duration))
+        );
+
+        for batch in &play.batches {
+            // Deployment IDs repeat across plays; plans from before
+            // deployments recorded their play can only match by ID
+            let deployment = batch.binary_deployment.as_ref().and_then(|id| {
+                plan.binary_deployments.iter().find(|deployment| {
+                    &deployment.deployment_id == id
+                        && (deployment.play_id == play.play_id || deployment.play_id.is_empty())
+                })
+            });
+            let style = deployment.map_or_else(String::new, |deployment| {
+                format!(
+                    " style=\"border-left-color: {}\"",
+                    colors[&host_set(&deployment.target_hosts)]
+                )
+            });
+            let _ = writeln!(
+                out,
+                "<div class=\"batch\"{style}>\n<h3>{}</h3>\n<p class=\"meta\">{} &middot; {}</p>",
+                html_escape(&batch_label(batch)),
+                html_escape(&batch.hosts.join(", ")),
+                html_escape(&show_duration(batch.estimated_duration))
+            );
+
+            let mut shown = BTreeSet::new();
+            for task in &batch.tasks {
+                if shown.contains(task.task_id.as_str()) {
+                    continue;
+                }
+                let group = batch
+                    .parallel_groups
+                    .iter()
+                    .find(|group| group.tasks.contains(&task.task_id));
+                match group {
+                    Some(group) => {
+                        let _ = writeln!(
+                            out,
+                            "<div class=\"group\"><p class=\"meta\">Parallel group {} &middot; up to {} at once</p>",
+                            html_escape(&group.group_id),
+                            group.max_parallelism
+                        );
+                        for member in batch
+                            .tasks
+                            .iter()
+                            .filter(|member| group.tasks.contains(&member.task_id))
+                        {
+                            html_task(out, member);
+                            shown.insert(member.task_id.as_str());
+                        }
+                        out.push_str("</div>\n");
+                    }
+                    None => {
+                        html_task(out, task);
+                        shown.insert(task.task_id.as_str());
+                    }
+                }
+            }
+            out.push_str("</div>\n");
+        }
+        out.push_str("</section>\n");
+    }
+}
+
+impl Default for PlanVisualizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Class name, fill and border color of each risk level
+const RISK_COLORS: [(&str, &str, &str); 4] = [
+    ("low", "#d4edda", "#28a745"),
+    ("medium", "#fff3cd", "#d39e00"),
+    ("high", "#ffe0b2", "#e8590c"),
+    ("critical", "#f8d7da", "#c82333"),
+];
+
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+h1, h2, h3 { margin: 0.5rem 0; }
+h3 { font-size: 1rem; }
+.summary { display: grid; grid-template-columns: max-content auto; gap: 0.25rem 1rem; }
+.summary dt { font-weight: bold; }
+.summary dd { margin: 0; }
+.meta { color: #666; font-size: 0.85rem; margin: 0.25rem 0; }
+.play { margin: 1.5rem 0; }
+.batch { border: 1px solid #ccc; border-left: 6px solid #ccc; border-radius: 4px; padding: 0.5rem 1rem; margin: 0.75rem 0; }
+.group { border: 1px dashed #999; border-radius: 4px; padding: 0.25rem 0.5rem; margin: 0.5rem 0; }
+.task { display: flex; gap: 0.75rem; align-items: baseline; border: 1px solid; border-radius: 4px; padding: 0.25rem 0.5rem; margin: 0.25rem 0; }
+.task code { color: #444; }
+.badge { border: 1px solid; border-radius: 3px; padding: 0 0.3rem; font-size: 0.8rem; }
+.swatch { display: inline-block; width: 0.8rem; height: 0.8rem; margin-right: 0.4rem; border-radius: 2px; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.25rem 0.5rem; text-align: left; }
+";
+
+fn html_task(out: &mut String, task: &TaskPlan) {
+    let risk = risk_class(&task.risk_level);
+    let _ = writeln!(
+        out,
+        "<div class=\"task risk-{risk}\"><strong>{}</strong><code>{}</code><span class=\"meta\">{} &middot; {} hosts &middot; {}</span><span class=\"badge risk-{risk}\">{risk}</span></div>",
+        html_escape(&task.name),
+        html_escape(&task.module),
+        html_escape(&task.task_id),
+        task.hosts.len(),
+        html_escape(&show_duration(task.estimated_duration))
+    );
+}
+
+/// One color per distinct set of target hosts, so deployments to the same
+/// hosts share a color
+fn host_set_colors(plan: &ExecutionPlan) -> HashMap<String, String> {
+    let mut colors = HashMap::new();
+    for deployment in &plan.binary_deployments {
+        let next = colors.len();
+        colors
+            .entry(host_set(&deployment.target_hosts))
+            // Golden-angle hue steps keep neighbouring colors apart
+            .or_insert_with(|| format!("hsl({}, 65%, 55%)", next * 137 % 360));
+    }
+    colors
+}
+
+fn host_set(hosts: &[String]) -> String {
+    hosts
+        .iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn batch_label(batch: &ExecutionBatch) -> String {
+    let mut label = batch.batch_id.clone();
+    if let Some(deployment) = &batch.binary_deployment {
+        let _ = write!(label, " (binary {deployment})");
+    }
+    if let Some(gate) = &batch.stage_gate {
+        let _ = write!(label, " ({gate:?})");
+    }
+    label
+}
+
+/// Edges from the latest drawn copy of each task `task` depends on
+fn dependency_edges(
+    task: &TaskPlan,
+    latest: &HashMap<&str, String>,
+    node: &str,
+) -> Vec<(String, String)> {
+    task.dependencies
+        .iter()
+        .filter_map(|dep| latest.get(dep.as_str()))
+        .map(|from| (from.clone(), node.to_string()))
+        .collect()
+}
+
+fn risk_class(risk: &RiskLevel) -> &'static str {
+    match risk {
+        RiskLevel::Low => "low",
+        RiskLevel::Medium => "medium",
+        RiskLevel::High => "high",
+        RiskLevel::Critical => "critical",
+    }
+}
+
+fn show_duration(duration: Option<Duration>) -> String {
+    duration.map_or_else(
+        || "no estimate".to_string(),
+        |duration| format!("{duration:?}"),
+    )
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+fn plantuml_escape(text: &str) -> String {
+    text.replace('"', "'")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(dot_escape(r#"say "hi" \o/"#), r#"say \"hi\" \\o/"#);
+        assert_eq!(mermaid_escape(r#"a "b" <c>"#), "a #quot;b#quot; #lt;c#gt;");
+        assert_eq!(plantuml_escape(r#"a "b""#), "a 'b'");
+        assert_eq!(
+            html_escape(r#"<a href="x">&</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_host_set_ignores_order() {
+        let hosts = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            host_set(&hosts(&["web2", "web1"])),
+            host_set(&hosts(&["web1", "web2", "web1"]))
+        );
+    }
+}
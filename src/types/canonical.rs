@@ -0,0 +1,80 @@
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// Serializes `value` as compact JSON with object keys sorted, so equal
+/// values always produce the same bytes whatever their maps' iteration order.
+pub fn canonical_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    // serde_json::Value keeps its object keys in a BTreeMap
+    serde_json::to_string(&serde_json::to_value(value)?)
+}
+
+/// SHA-256 of `data`, written as `sha256:` followed by the lowercase hex
+/// digest, so the algorithm can change without breaking stored plans.
+pub fn sha256_digest(data: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(data)))
+}
+
+/// `serialize_with` helper that writes a HashMap with its keys sorted.
+pub fn sorted_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Args {
+        #[serde(serialize_with = "sorted_map")]
+        args: HashMap<String, serde_json::Value>,
+    }
+
+    fn create_map(keys: &[&str]) -> HashMap<String, serde_json::Value> {
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), json!({ "z": i, "a": [key] })))
+            .collect()
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        let keys = ["src", "dest", "mode", "owner", "group", "backup"];
+        let forward = create_map(&keys);
+        let mut reversed = HashMap::new();
+        for key in keys.iter().rev() {
+            reversed.insert(key.to_string(), forward[*key].clone());
+        }
+
+        let canonical = canonical_json(&forward).unwrap();
+        assert_eq!(canonical, canonical_json(&reversed).unwrap());
+        assert!(canonical.starts_with(r#"{"backup":{"a":["backup"],"z":5},"dest""#));
+    }
+
+    #[test]
+    fn test_sha256_digest() {
+        assert_eq!(
+            sha256_digest(b"abc"),
+            "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sorted_map_serializes_in_key_order() {
+        let args = Args {
+            args: create_map(&["name", "state", "enabled"]),
+        };
+
+        let json = serde_json::to_string(&args).unwrap();
+        let enabled = json.find("\"enabled\"").unwrap();
+        let name = json.find("\"name\"").unwrap();
+        let state = json.find("\"state\"").unwrap();
+        assert!(enabled < name && name < state);
+    }
+}
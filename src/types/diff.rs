@@ -0,0 +1,101 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::plan::RiskLevel;
+
+/// How one execution plan differs from another. Tasks, batches and binary
+/// deployments are matched by ID; metadata such as `created_at` and the
+/// plan digest is left out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct PlanDiff {
+    pub hosts_added: Vec<String>,
+    pub hosts_removed: Vec<String>,
+    pub estimated_duration: Option<Change<Option<Duration>>>,
+    pub tasks: Vec<TaskDiff>,
+    pub batches: Vec<BatchDiff>,
+    pub binary_deployments: Vec<DeploymentDiff>,
+}
+
+impl PlanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.hosts_added.is_empty()
+            && self.hosts_removed.is_empty()
+            && self.estimated_duration.is_none()
+            && self.tasks.is_empty()
+            && self.batches.is_empty()
+            && self.binary_deployments.is_empty()
+    }
+}
+
+/// A value in the old plan and in the new one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A task of a play, with the hosts it gained or lost. Added and removed
+/// tasks list all their hosts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct TaskDiff {
+    pub play_id: String,
+    pub task_id: String,
+    pub name: String,
+    pub kind: DiffKind,
+    pub hosts_added: Vec<String>,
+    pub hosts_removed: Vec<String>,
+    pub module: Option<Change<String>>,
+    pub args: Vec<ArgChange>,
+    pub risk_level: Option<Change<RiskLevel>>,
+    pub estimated_duration: Option<Change<Option<Duration>>>,
+    /// The batches running the task, when it moved between batches
+    pub batches: Option<Change<Vec<String>>>,
+}
+
+/// A task argument that was added (`old` is `None`), removed (`new` is
+/// `None`) or given a new value
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ArgChange {
+    pub key: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BatchDiff {
+    pub play_id: String,
+    pub batch_id: String,
+    pub kind: DiffKind,
+    /// Position among the play's batches found in both plans, when the
+    /// batch was reordered
+    pub position: Option<Change<usize>>,
+    pub tasks_added: Vec<String>,
+    pub tasks_removed: Vec<String>,
+    pub hosts_added: Vec<String>,
+    pub hosts_removed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct DeploymentDiff {
+    pub play_id: String,
+    pub deployment_id: String,
+    pub kind: DiffKind,
+    pub tasks_added: Vec<String>,
+    pub tasks_removed: Vec<String>,
+    pub hosts_added: Vec<String>,
+    pub hosts_removed: Vec<String>,
+    /// New deployments that now run this deployment's tasks, when there
+    /// are several
+    pub split_into: Vec<String>,
+    /// Old deployments whose tasks this deployment now runs, when there
+    /// were several
+    pub merged_from: Vec<String>,
+}
@@ -1,5 +1,9 @@
+pub mod canonical;
+pub mod diff;
 pub mod plan;
 pub mod strategy;
 
+pub use canonical::*;
+pub use diff::*;
 pub use plan::*;
 pub use strategy::*;
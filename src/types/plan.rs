@@ -1,12 +1,14 @@
 use chrono::{DateTime, Utc};
 use petgraph::graph::NodeIndex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
 
-use super::strategy::ExecutionStrategy;
+use super::canonical::sorted_map;
+use super::strategy::{BatchSize, ExecutionStrategy, HostOrder};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExecutionPlan {
     pub metadata: PlanMetadata,
     pub plays: Vec<PlayPlan>,
@@ -17,30 +19,104 @@ pub struct ExecutionPlan {
     pub parallelism_score: f32,
     pub network_efficiency_score: f32,
     pub hosts: Vec<String>,
+    #[serde(default)]
+    pub filtered_tasks: Vec<FilteredTask>,
+    #[serde(default)]
+    pub host_timelines: Vec<HostTimeline>,
+    /// References that replaced secret values, in order
+    #[serde(default)]
+    pub redacted_secrets: Vec<String>,
+    /// The redacted values, encrypted for the executor
+    #[serde(default)]
+    pub sealed_secrets: Option<SealedSecrets>,
+    /// SHA-256 over the canonical JSON of the rest of the plan, so tools
+    /// running the plan can detect tampering or a stale copy
+    #[serde(default)]
+    pub plan_digest: Option<String>,
+}
+
+/// Secret values keyed by their reference, as JSON sealed to the executor's
+/// X25519 public key with a libsodium-compatible sealed box.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct SealedSecrets {
+    pub algorithm: String,
+    /// Hex-encoded public key the secrets are sealed to
+    pub recipient: String,
+    /// Hex-encoded sealed box
+    pub ciphertext: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Version of the `ExecutionPlan` layout written by this release. It goes up
+/// whenever a change to the plan types would stop older plans from loading.
+pub const PLAN_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PlanMetadata {
     pub created_at: DateTime<Utc>,
     pub rustle_plan_version: String,
+    /// Plans from before schema versioning are version 1
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub playbook_hash: String,
     pub inventory_hash: String,
     pub planning_options: PlanningOptions,
+    /// Host order for plays without their own `order`, with the seed a
+    /// shuffle was planned with
+    #[serde(default)]
+    pub host_order: HostOrder,
+    /// Signature over the rest of the plan, added by `rustle-plan sign`
+    #[serde(default)]
+    pub signature: Option<PlanSignature>,
+}
+
+fn legacy_schema_version() -> u32 {
+    1
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An Ed25519 signature over the canonical JSON of a plan without its
+/// signature block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct PlanSignature {
+    pub algorithm: String,
+    /// Hex-encoded public key of the signer
+    pub public_key: String,
+    /// Hex-encoded signature
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PlayPlan {
     pub play_id: String,
     pub name: String,
     pub strategy: ExecutionStrategy,
-    pub serial: Option<u32>,
+    /// The rolling batch sizes this play was planned with
+    pub serial: Option<Vec<BatchSize>>,
+    /// Percentage of a batch's hosts that may fail before the play aborts
+    #[serde(default)]
+    pub max_fail_percentage: Option<u32>,
+    /// Abort the play as soon as any host fails
+    #[serde(default)]
+    pub any_errors_fatal: bool,
+    /// The order `hosts` are in
+    #[serde(default)]
+    pub host_order: HostOrder,
     pub hosts: Vec<String>,
     pub batches: Vec<ExecutionBatch>,
     pub handlers: Vec<HandlerPlan>,
     pub estimated_duration: Option<Duration>,
+    #[serde(default)]
+    pub critical_path: Option<CriticalPath>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The longest chain of dependent tasks through a play. Shortening any task
+/// on it shortens the play.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct CriticalPath {
+    pub tasks: Vec<String>,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExecutionBatch {
     pub batch_id: String,
     pub hosts: Vec<String>,
@@ -48,13 +124,45 @@ pub struct ExecutionBatch {
     pub parallel_groups: Vec<ParallelGroup>,
     pub dependencies: Vec<String>,
     pub estimated_duration: Option<Duration>,
+    /// The binary deployment that runs this batch's tasks, if not run over SSH
+    #[serde(default)]
+    pub binary_deployment: Option<String>,
+    /// Failure check an executor makes before starting the batches that
+    /// depend on this one
+    #[serde(default)]
+    pub proceed_if: Option<ProceedGate>,
+    /// Set on batches that run no tasks and only mark a point in a staged
+    /// rollout where an executor must act on the batch's hosts
+    #[serde(default)]
+    pub stage_gate: Option<StageGate>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub enum StageGate {
+    /// Check the hosts are healthy before any later batch starts
+    HealthCheck,
+    /// Move traffic onto the hosts
+    TrafficSwitch,
+}
+
+/// The failure policy of a batch. The play proceeds while fewer than
+/// `abort_threshold` of `hosts` have failed and aborts once that many have.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ProceedGate {
+    /// Hosts whose failures count towards the threshold: the batch's hosts
+    /// for rolling and staged plays, every host of the play otherwise
+    pub hosts: Vec<String>,
+    pub abort_threshold: usize,
+    pub max_fail_percentage: Option<u32>,
+    pub any_errors_fatal: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TaskPlan {
     pub task_id: String,
     pub name: String,
     pub module: String,
+    #[serde(serialize_with = "sorted_map")]
     pub args: HashMap<String, serde_json::Value>,
     pub hosts: Vec<String>,
     pub dependencies: Vec<String>,
@@ -65,9 +173,83 @@ pub struct TaskPlan {
     pub can_run_parallel: bool,
     pub estimated_duration: Option<Duration>,
     pub risk_level: RiskLevel,
+    #[serde(default, serialize_with = "sorted_map")]
+    pub vars: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub loop_items: Option<serde_json::Value>,
+    #[serde(default)]
+    pub changed_when: Option<String>,
+    #[serde(default)]
+    pub failed_when: Option<String>,
+    #[serde(default)]
+    pub ignore_errors: bool,
+    /// Keep the task's arguments and variables out of the plan
+    #[serde(default)]
+    pub no_log: bool,
+    #[serde(default)]
+    pub delegate_to: Option<String>,
+    #[serde(default)]
+    pub loop_expansion: Option<LoopExpansion>,
+    #[serde(default)]
+    pub schedule: Option<TaskSchedule>,
+}
+
+/// When a task can run relative to the start of its play.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct TaskSchedule {
+    pub earliest_start: Duration,
+    pub latest_start: Duration,
+    /// How long the task can be delayed without delaying the play
+    pub slack: Duration,
+    pub on_critical_path: bool,
+}
+
+/// How a looped task was planned.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum LoopExpansion {
+    /// One item of a static loop, planned as its own task
+    Item {
+        parent_task_id: String,
+        index: usize,
+        item: serde_json::Value,
+    },
+    /// A loop over data only known at runtime, planned as a single task
+    Runtime,
+}
+
+/// A task that was dropped for a host because its condition is false for
+/// that host's facts.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FilteredTask {
+    pub task_id: String,
+    pub task_name: String,
+    pub play_id: String,
+    pub host: String,
+    pub condition: String,
+    pub reason: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// When each task runs on one host, as offsets from the start of the plan.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct HostTimeline {
+    pub host: String,
+    pub entries: Vec<TimelineEntry>,
+    pub finish: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct TimelineEntry {
+    pub play_id: String,
+    pub batch_id: String,
+    pub task_id: String,
+    pub task_name: String,
+    pub start: Duration,
+    pub end: Duration,
+    /// Time spent waiting for a free fork once the task was ready to run
+    pub fork_wait: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParallelGroup {
     pub group_id: String,
     pub tasks: Vec<String>,
@@ -75,17 +257,38 @@ pub struct ParallelGroup {
     pub shared_resources: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HandlerPlan {
     pub handler_id: String,
     pub name: String,
     pub module: String,
+    #[serde(serialize_with = "sorted_map")]
     pub args: HashMap<String, serde_json::Value>,
     pub conditions: Vec<ExecutionCondition>,
     pub execution_order: u32,
+    #[serde(default)]
+    pub listen: Vec<String>,
+    /// Keep the handler's arguments out of the plan
+    #[serde(default)]
+    pub no_log: bool,
+    /// Tasks whose `notify` resolves to this handler, by name or topic
+    #[serde(default)]
+    pub notified_by: Vec<String>,
+    #[serde(default)]
+    pub flushes: Vec<HandlerFlush>,
+}
+
+/// One run of a handler at a flush point, covering the notifications
+/// received since the previous flush.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct HandlerFlush {
+    /// The `meta: flush_handlers` task, or `None` at the end of the play
+    pub flush_task: Option<String>,
+    pub hosts: Vec<String>,
+    pub notified_by: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum ExecutionCondition {
     When { expression: String },
     Tag { tags: Vec<String> },
@@ -94,7 +297,7 @@ pub enum ExecutionCondition {
     CheckMode { enabled: bool },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub enum RiskLevel {
     #[default]
     Low, // Read-only operations
@@ -103,9 +306,13 @@ pub enum RiskLevel {
     Critical, // Destructive operations
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BinaryDeployment {
+    /// Unique within its play only, as each play numbers its deployments
     pub deployment_id: String,
+    /// Play whose tasks the deployment runs
+    #[serde(default)]
+    pub play_id: String,
     pub target_hosts: Vec<String>,
     pub binary_name: String,
     pub tasks: Vec<String>,
@@ -116,30 +323,33 @@ pub struct BinaryDeployment {
     pub compilation_requirements: CompilationRequirements,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BinaryEmbeddedData {
     pub execution_plan: String, // Subset of execution plan for this binary
     pub static_files: Vec<EmbeddedFile>,
+    #[serde(serialize_with = "sorted_map")]
     pub variables: HashMap<String, serde_json::Value>,
     pub facts_required: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EmbeddedFile {
     pub src_path: String,
     pub dest_path: String,
-    pub checksum: String,
+    /// SHA-256 of the source file, or `None` when it could not be found
+    #[serde(default)]
+    pub checksum: Option<String>,
     pub size: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum BinaryExecutionMode {
     Standalone, // Binary runs independently
     Controller, // Binary reports back to controller
     Hybrid,     // Binary handles some tasks, SSH for others
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CompilationRequirements {
     pub target_arch: String,
     pub target_os: String,
@@ -148,7 +358,7 @@ pub struct CompilationRequirements {
     pub static_linking: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PlanningOptions {
     pub limit: Option<String>,
     pub tags: Vec<String>,
@@ -156,31 +366,56 @@ pub struct PlanningOptions {
     pub check_mode: bool,
     pub diff_mode: bool,
     pub forks: u32,
-    pub serial: Option<u32>,
+    /// Rolling batch sizes for plays that do not set their own `serial`
+    pub serial: Option<Vec<BatchSize>>,
+    /// Failure percentage for plays that do not set their own
+    #[serde(default)]
+    pub max_fail_percentage: Option<u32>,
+    /// Make any host failure fatal in every play
+    #[serde(default)]
+    pub any_errors_fatal: bool,
+    /// Host order for plays that do not set their own `order`
+    #[serde(default)]
+    pub host_order: HostOrder,
+    /// Key name patterns, beyond the built-in ones, whose values are redacted
+    #[serde(default)]
+    pub redact_keys: Vec<String>,
     pub strategy: ExecutionStrategy,
     pub binary_threshold: u32,
     pub force_binary: bool,
     pub force_ssh: bool,
+    /// Pin the timestamp and shuffle seeds so the same input always gives
+    /// byte-identical plans
+    #[serde(default)]
+    pub reproducible: bool,
 }
 
 // Input data structures (from rustle-parse)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParsedPlaybook {
     pub name: String,
     pub plays: Vec<ParsedPlay>,
     pub vars: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParsedPlay {
     pub name: String,
     pub hosts: Vec<String>,
     pub tasks: Vec<ParsedTask>,
     pub handlers: Vec<ParsedHandler>,
     pub vars: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub serial: Option<Vec<BatchSize>>,
+    #[serde(default)]
+    pub max_fail_percentage: Option<u32>,
+    #[serde(default)]
+    pub any_errors_fatal: bool,
+    #[serde(default)]
+    pub order: Option<HostOrder>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParsedTask {
     pub id: String,
     pub name: String,
@@ -190,24 +425,53 @@ pub struct ParsedTask {
     pub tags: Vec<String>,
     pub when: Option<String>,
     pub notify: Vec<String>,
+    #[serde(default)]
+    pub vars: HashMap<String, serde_json::Value>,
+    /// Items from `loop`/`with_*`: a list, or a template string resolved at runtime
+    #[serde(default)]
+    pub loop_items: Option<serde_json::Value>,
+    #[serde(default)]
+    pub changed_when: Option<String>,
+    #[serde(default)]
+    pub failed_when: Option<String>,
+    #[serde(default)]
+    pub ignore_errors: bool,
+    /// Keep the task's arguments and variables out of the plan
+    #[serde(default)]
+    pub no_log: bool,
+    #[serde(default)]
+    pub delegate_to: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParsedHandler {
     pub id: String,
     pub name: String,
     pub module: String,
     pub args: HashMap<String, serde_json::Value>,
     pub when: Option<String>,
+    #[serde(default)]
+    pub listen: Vec<String>,
+    #[serde(default)]
+    pub no_log: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParsedInventory {
     pub hosts: Vec<String>,
+    /// Hosts listed directly under each group, excluding those inherited
+    /// from child groups
     pub groups: HashMap<String, Vec<String>>,
     pub vars: HashMap<String, serde_json::Value>,
     #[serde(default)]
     pub host_facts: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// Child groups of each group, as declared with `children:`
+    #[serde(default)]
+    pub group_children: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub group_vars: HashMap<String, HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    pub host_vars: HashMap<String, HashMap<String, serde_json::Value>>,
 }
 
 // Analysis structures
@@ -280,4 +544,43 @@ impl DependencyGraph {
             false
         }
     }
+
+    /// Nearest predecessors of a task that are in `task_ids`. Predecessors
+    /// outside the set are looked through, so ordering is kept across tasks
+    /// that have been removed from the plan.
+    pub fn predecessors_within(
+        &self,
+        task_id: &str,
+        task_ids: &std::collections::HashSet<String>,
+    ) -> Vec<String> {
+        let Some(&start) = self.task_nodes.get(task_id) else {
+            return Vec::new();
+        };
+
+        let mut predecessors = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        let mut stack: Vec<NodeIndex> = self
+            .graph
+            .neighbors_directed(start, petgraph::Direction::Incoming)
+            .collect();
+
+        while let Some(node) = stack.pop() {
+            if !visited.insert(node) {
+                continue;
+            }
+            let id = &self.graph[node];
+            if task_ids.contains(id) {
+                if !predecessors.contains(id) {
+                    predecessors.push(id.clone());
+                }
+            } else {
+                stack.extend(
+                    self.graph
+                        .neighbors_directed(node, petgraph::Direction::Incoming),
+                );
+            }
+        }
+
+        predecessors
+    }
 }
@@ -1,14 +1,208 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default, JsonSchema)]
 pub enum ExecutionStrategy {
     #[default]
     Linear,
+    /// Runs the play on successive groups of hosts. Sizes follow
+    /// `batch_sizes` in order, and the last size repeats until every host
+    /// has had its turn. With `topology`, batches are also spread across
+    /// failure domains.
     Rolling {
-        batch_size: u32,
+        batch_sizes: Vec<BatchSize>,
+        #[serde(default)]
+        topology: Option<TopologySpread>,
     },
     Free,
     HostPinned,
     BinaryHybrid, // Mix of binary deployment and SSH execution
     BinaryOnly,   // Force binary deployment where possible
+    /// Runs the play on `canary_size` hosts, waits for a health check, then
+    /// rolls out to the remaining hosts in `batch_sizes` batches, spread
+    /// across failure domains with `topology`.
+    Canary {
+        canary_size: BatchSize,
+        batch_sizes: Vec<BatchSize>,
+        #[serde(default)]
+        topology: Option<TopologySpread>,
+    },
+    /// Deploys to the `green` inventory group, checks its health and
+    /// switches traffic to it, then brings the `blue` group up to date.
+    BlueGreen {
+        blue: String,
+        green: String,
+    },
+}
+
+/// Keeps a rolling batch from taking out too much of one failure domain.
+/// Each host's domain is the value of `domain_var` in its inventory
+/// variables; hosts without it share one domain.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct TopologySpread {
+    /// Host variable naming the failure domain, such as `zone` or `rack`
+    pub domain_var: String,
+    /// Most hosts of one domain in a batch, as a number or a percentage of
+    /// the domain's hosts
+    pub max_per_domain: BatchSize,
+}
+
+/// One step of an Ansible `serial` ramp: a number of hosts, or a percentage
+/// of the play's hosts. Serialized as `5` or `"25%"`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(try_from = "RawBatchSize", into = "RawBatchSize")]
+pub enum BatchSize {
+    Hosts(u32),
+    Percent(u32),
+}
+
+impl BatchSize {
+    /// Number of hosts in a batch for a play with `host_count` hosts.
+    /// Percentages round down but never below one host; zero means all
+    /// remaining hosts, as in Ansible.
+    pub fn resolve(&self, host_count: usize) -> usize {
+        match *self {
+            BatchSize::Hosts(hosts) => hosts as usize,
+            BatchSize::Percent(percent) => (host_count * percent as usize / 100).max(1),
+        }
+    }
+}
+
+impl FromStr for BatchSize {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if let Some(percent) = value.strip_suffix('%') {
+            percent
+                .trim()
+                .parse()
+                .map(BatchSize::Percent)
+                .map_err(|_| format!("invalid percentage '{value}'"))
+        } else {
+            value
+                .parse()
+                .map(BatchSize::Hosts)
+                .map_err(|_| format!("invalid batch size '{value}'"))
+        }
+    }
+}
+
+impl fmt::Display for BatchSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchSize::Hosts(hosts) => write!(f, "{hosts}"),
+            BatchSize::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum RawBatchSize {
+    Number(u32),
+    Text(String),
+}
+
+impl TryFrom<RawBatchSize> for BatchSize {
+    type Error = String;
+
+    fn try_from(raw: RawBatchSize) -> Result<Self, Self::Error> {
+        match raw {
+            RawBatchSize::Number(hosts) => Ok(BatchSize::Hosts(hosts)),
+            RawBatchSize::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<BatchSize> for RawBatchSize {
+    fn from(size: BatchSize) -> Self {
+        match size {
+            BatchSize::Hosts(hosts) => RawBatchSize::Number(hosts),
+            BatchSize::Percent(_) => RawBatchSize::Text(size.to_string()),
+        }
+    }
+}
+
+/// The order a play works through its hosts, as in Ansible's play `order`.
+/// Serialized as `"inventory"`, `"reverse_inventory"`, `"sorted"`,
+/// `"reverse_sorted"`, `"shuffle"`, `"shuffle:<seed>"` or `"host_priority"`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, JsonSchema)]
+#[serde(try_from = "String", into = "String")]
+pub enum HostOrder {
+    #[default]
+    Inventory,
+    ReverseInventory,
+    Sorted,
+    ReverseSorted,
+    /// Random order; the same seed always gives the same order
+    Shuffle {
+        seed: Option<u64>,
+    },
+    /// Ascending `host_priority` host variable, so the most critical hosts
+    /// go last. Hosts without it have priority 0.
+    HostPriority,
+}
+
+impl HostOrder {
+    /// Gives an unseeded shuffle the seed, so the order can be reproduced.
+    pub fn with_seed(self, seed: u64) -> Self {
+        match self {
+            HostOrder::Shuffle { seed: None } => HostOrder::Shuffle { seed: Some(seed) },
+            order => order,
+        }
+    }
+}
+
+impl FromStr for HostOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "inventory" => Ok(HostOrder::Inventory),
+            "reverse_inventory" => Ok(HostOrder::ReverseInventory),
+            "sorted" => Ok(HostOrder::Sorted),
+            "reverse_sorted" => Ok(HostOrder::ReverseSorted),
+            "shuffle" => Ok(HostOrder::Shuffle { seed: None }),
+            "host_priority" => Ok(HostOrder::HostPriority),
+            other => match other.strip_prefix("shuffle:") {
+                Some(seed) => seed
+                    .trim()
+                    .parse()
+                    .map(|seed| HostOrder::Shuffle { seed: Some(seed) })
+                    .map_err(|_| format!("invalid shuffle seed '{seed}'")),
+                None => Err(format!("invalid host order '{other}'")),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HostOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostOrder::Inventory => write!(f, "inventory"),
+            HostOrder::ReverseInventory => write!(f, "reverse_inventory"),
+            HostOrder::Sorted => write!(f, "sorted"),
+            HostOrder::ReverseSorted => write!(f, "reverse_sorted"),
+            HostOrder::Shuffle { seed: None } => write!(f, "shuffle"),
+            HostOrder::Shuffle { seed: Some(seed) } => write!(f, "shuffle:{seed}"),
+            HostOrder::HostPriority => write!(f, "host_priority"),
+        }
+    }
+}
+
+impl TryFrom<String> for HostOrder {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<HostOrder> for String {
+    fn from(order: HostOrder) -> Self {
+        order.to_string()
+    }
 }
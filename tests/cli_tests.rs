@@ -104,6 +104,57 @@ fn test_list_tasks() {
         .stdout(predicate::str::contains("Test task"));
 }
 
+#[test]
+fn test_list_tasks_reports_filtered_tasks() {
+    let input = r#"{
+        "metadata": {
+            "file_path": "/tmp/test.yml",
+            "created_at": "2024-01-01T00:00:00Z",
+            "checksum": "abc123"
+        },
+        "plays": [
+            {
+                "name": "Test Play",
+                "hosts": ["host1", "host2"],
+                "tasks": [
+                    {
+                        "id": "task1",
+                        "name": "Install with apt",
+                        "module": "apt",
+                        "args": {"name": "git"},
+                        "dependencies": [],
+                        "tags": [],
+                        "when": "ansible_os_family == \"Debian\"",
+                        "notify": []
+                    }
+                ],
+                "handlers": [],
+                "vars": {}
+            }
+        ],
+        "variables": {},
+        "inventory": {
+            "hosts": ["host1", "host2"],
+            "groups": {},
+            "vars": {},
+            "host_facts": {
+                "host1": {"ansible_os_family": "Debian"},
+                "host2": {"ansible_os_family": "RedHat"}
+            }
+        }
+    }"#;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--list-tasks")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Filtered tasks:"))
+        .stdout(predicate::str::contains(
+            "Install with apt (task1) on host2",
+        ));
+}
+
 #[test]
 fn test_list_hosts() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
@@ -127,6 +178,90 @@ fn test_list_binaries() {
         .stdout(predicate::str::contains("Binary deployments:"));
 }
 
+#[test]
+fn test_critical_path_report() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--critical-path")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Critical path:"))
+        .stdout(predicate::str::contains("* Test task (task1)"))
+        .stdout(predicate::str::contains("slack 0ns"));
+}
+
+#[test]
+fn test_output_includes_task_schedule() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let play = &plan["plays"][0];
+    assert_eq!(play["critical_path"]["tasks"], serde_json::json!(["task1"]));
+    let task = &play["batches"][0]["tasks"][0];
+    assert_eq!(task["schedule"]["on_critical_path"], true);
+}
+
+#[test]
+fn test_handlers_resolved_from_listen_topics() {
+    let input = create_test_rustle_output()
+        .replace(r#""notify": []"#, r#""notify": ["web changed"]"#)
+        .replace(
+            r#""handlers": []"#,
+            r#""handlers": [
+                    {
+                        "id": "handler1",
+                        "name": "restart nginx",
+                        "module": "service",
+                        "args": {"name": "nginx", "state": "restarted"},
+                        "when": null,
+                        "listen": "web changed"
+                    }
+                ]"#,
+        );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.write_stdin(input).assert().success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let handler = &plan["plays"][0]["handlers"][0];
+    assert_eq!(handler["listen"], serde_json::json!(["web changed"]));
+    assert_eq!(handler["notified_by"], serde_json::json!(["task1"]));
+    assert_eq!(
+        handler["flushes"][0]["hosts"],
+        serde_json::json!(["host1", "host2"])
+    );
+}
+
+#[test]
+fn test_timeline_shows_fork_waits() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.args(["--timeline", "--forks", "1", "--strategy", "linear"])
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Timeline (forks: 1"))
+        .stdout(predicate::str::contains("host2 (finishes at"))
+        .stdout(predicate::str::contains("Fork limit delayed 1 task runs"));
+}
+
+#[test]
+fn test_output_includes_host_timelines() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let timelines = plan["host_timelines"].as_array().unwrap();
+    assert_eq!(timelines.len(), 2);
+    assert_eq!(timelines[0]["entries"][0]["task_id"], "task1");
+}
+
 #[test]
 fn test_dry_run() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
@@ -160,102 +295,693 @@ fn test_json_output_format() {
 #[test]
 fn test_binary_output_format() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--output")
+    let output = cmd
+        .arg("--output")
         .arg("binary")
         .write_stdin(create_test_rustle_output())
         .assert()
         .success();
+
+    let bytes = &output.get_output().stdout;
+    assert!(bytes.starts_with(b"RPLN\x01\x00\x00"));
+    let plan = rustle_plan::BinaryPlanFormat::new().decode(bytes).unwrap();
+    assert_eq!(plan.total_tasks, 1);
+}
+
+#[test]
+fn test_compressed_binary_plans_can_be_signed() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let key = temp_dir.path().join("plan.key");
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("keygen").arg("--key").arg(&key).assert().success();
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("--output")
+        .arg("binary")
+        .arg("--compress")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let bytes = &output.get_output().stdout;
+    assert!(bytes.starts_with(b"RPLN\x01\x00\x01"));
+    let plan_path = temp_dir.path().join("plan.bin");
+    fs::write(&plan_path, bytes)?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("sign")
+        .arg(&plan_path)
+        .arg("--key")
+        .arg(&key)
+        .assert()
+        .success();
+    let signed: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(signed["metadata"]["signature"]["algorithm"], "ed25519");
+    Ok(())
+}
+
+#[test]
+fn test_dot_output_requires_visualize() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--output")
+        .arg("dot")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("DOT output requires --visualize"));
+}
+
+#[test]
+fn test_dot_visualization() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--output")
+        .arg("dot")
+        .arg("--visualize")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("digraph execution_plan"));
+}
+
+#[test]
+fn test_diagram_output_formats() {
+    for (format, start) in [
+        ("mermaid", "flowchart TD\n"),
+        ("plantuml", "@startuml\n"),
+        ("html", "<!DOCTYPE html>\n"),
+    ] {
+        let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+        cmd.arg("--output")
+            .arg(format)
+            .write_stdin(create_test_rustle_output())
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with(start))
+            .stdout(predicate::str::contains("Test task"));
+    }
+}
+
+#[test]
+fn test_limit_hosts() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--limit")
+        .arg("host1")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_nested_inventory_groups() {
+    let input = |children: &str| {
+        format!(
+            r#"{{
+        "metadata": {{
+            "file_path": "/tmp/test.yml",
+            "created_at": "2024-01-01T00:00:00Z",
+            "checksum": "abc123"
+        }},
+        "plays": [
+            {{
+                "name": "Test Play",
+                "hosts": ["production"],
+                "tasks": [],
+                "handlers": [],
+                "vars": {{}}
+            }}
+        ],
+        "variables": {{}},
+        "inventory": {{
+            "hosts": ["web1", "db1", "dev1"],
+            "groups": {{
+                "production": {{"name": "production", "children": ["webservers", "dbservers"]}},
+                "webservers": {{"name": "webservers", "hosts": ["web1"], "children": {children}}},
+                "dbservers": {{"name": "dbservers", "hosts": ["db1"]}},
+                "dev": {{"name": "dev", "hosts": ["dev1"]}}
+            }},
+            "vars": {{}}
+        }}
+    }}"#
+        )
+    };
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.write_stdin(input("[]")).assert().success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(
+        plan["plays"][0]["hosts"],
+        serde_json::json!(["web1", "db1"])
+    );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.write_stdin(input(r#"["production"]"#))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "production -> webservers -> production",
+        ));
+}
+
+#[test]
+fn test_task_execution_fields_preserved() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("file_operations.json");
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.arg(&fixture).assert().success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let tasks: Vec<&Value> = plan["plays"][0]["batches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|batch| batch["tasks"].as_array().unwrap())
+        .collect();
+    let looped = tasks
+        .iter()
+        .find(|task| task["task_id"] == "task_1")
+        .expect("looped task should be planned");
+
+    assert_eq!(
+        looped["loop_items"],
+        serde_json::json!(["config", "data", "logs", "backups"])
+    );
+    assert_eq!(looped["ignore_errors"], false);
+    assert!(looped["delegate_to"].is_null());
+    // Four iterations of a one-second file task
+    assert!(looped["estimated_duration"]["secs"].as_u64().unwrap() >= 4);
+}
+
+#[test]
+fn test_expand_loops() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("file_operations.json");
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.arg(&fixture).arg("--expand-loops").assert().success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let task_ids: Vec<&str> = plan["plays"][0]["batches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|batch| batch["tasks"].as_array().unwrap())
+        .map(|task| task["task_id"].as_str().unwrap())
+        .collect();
+
+    assert!(!task_ids.contains(&"task_1"));
+    for index in 0..4 {
+        assert!(task_ids.contains(&format!("task_1_item_{index}").as_str()));
+    }
+}
+
+#[test]
+fn test_tags_filter() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--tags")
+        .arg("test")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_skip_tags() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--skip-tags")
+        .arg("skip")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_check_mode() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--check")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_diff_mode() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--diff")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_forks_option() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--forks")
+        .arg("10")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_serial_option() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--serial")
+        .arg("2")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_serial_ramp_option() {
+    let input = create_test_rustle_output()
+        .replace(
+            r#""hosts": ["host1", "host2"],"#,
+            r#""hosts": ["host1", "host2", "host3", "host4"],"#,
+        )
+        .replace(
+            r#""hosts": ["host1", "host2"]"#,
+            r#""hosts": ["host1", "host2", "host3", "host4"]"#,
+        );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("--strategy")
+        .arg("rolling")
+        .arg("--serial")
+        .arg("1,50%")
+        .write_stdin(input)
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let play = &plan["plays"][0];
+    assert_eq!(play["serial"], serde_json::json!([1, "50%"]));
+    let sizes: Vec<usize> = play["batches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|batch| batch["hosts"].as_array().unwrap().len())
+        .collect();
+    assert_eq!(sizes, vec![1, 2, 1]);
+}
+
+#[test]
+fn test_play_serial_makes_play_rolling() {
+    let input = create_test_rustle_output().replace(
+        r#""vars": {}
+            }"#,
+        r#""vars": {},
+                "serial": 1
+            }"#,
+    );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("--strategy")
+        .arg("linear")
+        .write_stdin(input)
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let play = &plan["plays"][0];
+    assert_eq!(play["serial"], serde_json::json!([1]));
+    assert_eq!(play["batches"].as_array().unwrap().len(), 2);
+    assert_eq!(play["batches"][1]["batch_id"], "rolling-batch-1");
+}
+
+#[test]
+fn test_play_serial_applies_to_every_strategy() {
+    let input = create_test_rustle_output()
+        .replace(
+            r#""hosts": ["host1", "host2"],"#,
+            r#""hosts": ["host1", "host2", "host3", "host4"],"#,
+        )
+        .replace(
+            r#""vars": {}
+            }"#,
+            r#""vars": {},
+                "serial": [1, "50%"]
+            }"#,
+        );
+
+    for strategy in [None, Some("free"), Some("host-pinned"), Some("binary-only")] {
+        let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+        if let Some(strategy) = strategy {
+            cmd.arg("--strategy").arg(strategy);
+        }
+        let output = cmd.write_stdin(input.clone()).assert().success();
+        let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+        let batch_sizes: Vec<usize> = plan["plays"][0]["batches"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|batch| batch["hosts"].as_array().unwrap().len())
+            .collect();
+        assert_eq!(batch_sizes, vec![1, 2, 1], "strategy {strategy:?}");
+    }
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("--strategy")
+        .arg("blue-green")
+        .write_stdin(input)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("sets serial"));
+}
+
+#[test]
+fn test_rolling_spread_across_zones() {
+    let input = create_test_rustle_output()
+        .replace(
+            r#""hosts": ["host1", "host2"],"#,
+            r#""hosts": ["host1", "host2", "host3", "host4"],"#,
+        )
+        .replace(
+            r#""groups": {}"#,
+            r#""host_vars": {
+                "host1": {"zone": "east"},
+                "host2": {"zone": "east"},
+                "host3": {"zone": "west"},
+                "host4": {"zone": "west"}
+            },
+            "groups": {}"#,
+        );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args(["--strategy", "rolling", "--serial", "2"])
+        .args(["--spread-by", "zone", "--max-per-domain", "1"])
+        .write_stdin(input)
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let play = &plan["plays"][0];
+    assert_eq!(
+        play["strategy"]["Rolling"]["topology"]["domain_var"],
+        "zone"
+    );
+    assert_eq!(
+        play["batches"][0]["hosts"],
+        serde_json::json!(["host1", "host3"])
+    );
+    assert_eq!(
+        play["batches"][1]["hosts"],
+        serde_json::json!(["host2", "host4"])
+    );
+}
+
+#[test]
+fn test_spread_by_requires_rolling_or_canary() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args(["--strategy", "canary", "--spread-by", "zone"])
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(
+        plan["plays"][0]["strategy"]["Canary"]["topology"]["domain_var"],
+        "zone"
+    );
+
+    for strategy in ["linear", "free", "binary-hybrid", "blue-green"] {
+        let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+        cmd.args(["--strategy", strategy, "--spread-by", "zone"])
+            .write_stdin(create_test_rustle_output())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--spread-by"));
+    }
+}
+
+#[test]
+fn test_max_per_domain_requires_spread_by() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.args(["--strategy", "rolling", "--max-per-domain", "1"])
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_host_order_option() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args([
+            "--order",
+            "reverse_sorted",
+            "--strategy",
+            "rolling",
+            "--serial",
+            "1",
+        ])
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    assert_eq!(plan["metadata"]["host_order"], "reverse_sorted");
+    let play = &plan["plays"][0];
+    assert_eq!(play["host_order"], "reverse_sorted");
+    assert_eq!(play["hosts"], serde_json::json!(["host2", "host1"]));
+    assert_eq!(play["batches"][0]["hosts"], serde_json::json!(["host2"]));
+}
+
+#[test]
+fn test_inventory_order_follows_file() {
+    let input = create_test_rustle_output()
+        .replacen(r#""hosts": ["host1", "host2"],"#, r#""hosts": ["all"],"#, 1)
+        .replace(
+            r#""hosts": ["host1", "host2"],"#,
+            r#""hosts": {
+                "web3": {"name": "web3"},
+                "web1": {"name": "web1"},
+                "web2": {"name": "web2"}
+            },"#,
+        );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args(["--order", "inventory"])
+        .write_stdin(input)
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    assert_eq!(
+        plan["plays"][0]["hosts"],
+        serde_json::json!(["web3", "web1", "web2"])
+    );
+}
+
+#[test]
+fn test_shuffle_records_seed() {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args(["--order", "shuffle"])
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let recorded = plan["metadata"]["host_order"].as_str().unwrap();
+    assert!(recorded.starts_with("shuffle:"));
+    assert_eq!(plan["plays"][0]["host_order"], recorded);
+
+    // Replaying the recorded seed gives the same host order
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args(["--order", recorded])
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let replay: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+    assert_eq!(replay["plays"][0]["hosts"], plan["plays"][0]["hosts"]);
+}
+
+#[test]
+fn test_play_order_overrides_option() {
+    let input = create_test_rustle_output().replace(
+        r#""vars": {}
+            }"#,
+        r#""vars": {},
+                "order": "reverse_inventory"
+            }"#,
+    );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .args(["--order", "sorted"])
+        .write_stdin(input)
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    assert_eq!(plan["metadata"]["host_order"], "sorted");
+    assert_eq!(plan["plays"][0]["host_order"], "reverse_inventory");
+    assert_eq!(
+        plan["plays"][0]["hosts"],
+        serde_json::json!(["host2", "host1"])
+    );
 }
 
 #[test]
-fn test_dot_output_requires_visualize() {
+fn test_invalid_host_order_rejected() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--output")
-        .arg("dot")
+    cmd.args(["--order", "random"])
         .write_stdin(create_test_rustle_output())
         .assert()
         .failure()
-        .stdout(predicate::str::contains("DOT output requires --visualize"));
+        .stderr(predicate::str::contains("invalid host order 'random'"));
 }
 
 #[test]
-fn test_dot_visualization() {
+fn test_batches_carry_proceed_gate() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--output")
-        .arg("dot")
-        .arg("--visualize")
+    let output = cmd
+        .args(["--strategy", "rolling", "--serial", "1"])
+        .args(["--max-fail-percentage", "0"])
         .write_stdin(create_test_rustle_output())
         .assert()
-        .success()
-        .stdout(predicate::str::contains("digraph execution_plan"));
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let play = &plan["plays"][0];
+    assert_eq!(play["max_fail_percentage"], 0);
+    let gate = &play["batches"][1]["proceed_if"];
+    assert_eq!(gate["hosts"], serde_json::json!(["host2"]));
+    assert_eq!(gate["abort_threshold"], 1);
+    assert_eq!(gate["any_errors_fatal"], false);
 }
 
 #[test]
-fn test_limit_hosts() {
+fn test_failure_impact_report() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--limit")
-        .arg("host1")
+    cmd.args(["--failure-impact", "--strategy", "rolling", "--serial", "1"])
         .write_stdin(create_test_rustle_output())
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains(
+            "Play 1: Test Play (abort when all hosts fail)",
+        ))
+        .stdout(predicate::str::contains(
+            "rolling-batch-0: aborts at 1 of 1 failed hosts",
+        ))
+        .stdout(predicate::str::contains(
+            "Worst case: 1 of 2 hosts fail before the plan aborts",
+        ));
 }
 
 #[test]
-fn test_tags_filter() {
+fn test_play_any_errors_fatal() {
+    let input = create_test_rustle_output().replace(
+        r#""vars": {}
+            }"#,
+        r#""vars": {},
+                "any_errors_fatal": true
+            }"#,
+    );
+
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--tags")
-        .arg("test")
-        .write_stdin(create_test_rustle_output())
+    cmd.args(["--failure-impact", "--strategy", "linear"])
+        .write_stdin(input)
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains("(any errors fatal)"))
+        .stdout(predicate::str::contains("aborts at 1 of 2 failed hosts"));
 }
 
 #[test]
-fn test_skip_tags() {
+fn test_max_fail_percentage_out_of_range() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--skip-tags")
-        .arg("skip")
+    cmd.args(["--max-fail-percentage", "120"])
         .write_stdin(create_test_rustle_output())
         .assert()
-        .success();
+        .failure();
 }
 
 #[test]
-fn test_check_mode() {
+fn test_canary_strategy_gates_rollout() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--check")
+    let output = cmd
+        .args(["--strategy", "canary", "--canary-size", "50%"])
         .write_stdin(create_test_rustle_output())
         .assert()
         .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let batches = &plan["plays"][0]["batches"];
+    assert_eq!(batches[0]["batch_id"], "canary");
+    assert_eq!(batches[0]["hosts"], serde_json::json!(["host1"]));
+    assert_eq!(batches[1]["stage_gate"], "HealthCheck");
+    assert_eq!(batches[2]["hosts"], serde_json::json!(["host2"]));
+    assert_eq!(
+        batches[2]["dependencies"],
+        serde_json::json!(["canary-health-check"])
+    );
 }
 
 #[test]
-fn test_diff_mode() {
+fn test_blue_green_strategy_switches_cohorts() {
+    let input = create_test_rustle_output().replace(
+        r#""groups": {}"#,
+        r#""groups": {"live": ["host1"], "standby": ["host2"]}"#,
+    );
+
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--diff")
-        .write_stdin(create_test_rustle_output())
+    let output = cmd
+        .args(["--strategy", "blue-green"])
+        .args(["--blue-group", "live", "--green-group", "standby"])
+        .write_stdin(input)
         .assert()
         .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    let batches = plan["plays"][0]["batches"].as_array().unwrap();
+    let ids: Vec<&str> = batches
+        .iter()
+        .map(|batch| batch["batch_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        vec!["green", "green-health-check", "switch-to-green", "blue"]
+    );
+    assert_eq!(batches[0]["hosts"], serde_json::json!(["host2"]));
+    assert_eq!(batches[2]["stage_gate"], "TrafficSwitch");
+    assert_eq!(batches[3]["hosts"], serde_json::json!(["host1"]));
 }
 
 #[test]
-fn test_forks_option() {
+fn test_blue_green_requires_cohort_groups() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
-    cmd.arg("--forks")
-        .arg("10")
+    cmd.args(["--strategy", "blue-green"])
         .write_stdin(create_test_rustle_output())
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid blue/green cohort 'blue': No such inventory group",
+        ));
 }
 
 #[test]
-fn test_serial_option() {
+fn test_invalid_serial_rejected() {
     let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
     cmd.arg("--serial")
-        .arg("2")
+        .arg("ten")
         .write_stdin(create_test_rustle_output())
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains("invalid batch size 'ten'"));
 }
 
 #[test]
@@ -267,6 +993,7 @@ fn test_all_strategies() {
         "host-pinned",
         "binary-hybrid",
         "binary-only",
+        "canary",
     ];
 
     for strategy in &strategies {
@@ -458,3 +1185,472 @@ fn test_estimate_time_in_json_output() {
         .assert()
         .success();
 }
+
+#[test]
+fn test_reproducible_plans_are_byte_identical() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("file_operations.json");
+
+    let run = || {
+        let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+        let output = cmd
+            .arg(&fixture)
+            .args(["--reproducible", "--order", "shuffle"])
+            .assert()
+            .success();
+        output.get_output().stdout.clone()
+    };
+
+    let first = run();
+    assert_eq!(first, run());
+
+    let plan: Value = serde_json::from_slice(&first).unwrap();
+    assert_eq!(plan["metadata"]["created_at"], "1970-01-01T00:00:00Z");
+    assert_eq!(plan["metadata"]["planning_options"]["reproducible"], true);
+    assert!(plan["metadata"]["host_order"]
+        .as_str()
+        .unwrap()
+        .starts_with("shuffle:"));
+}
+
+#[test]
+fn test_embedded_files_carry_content_digest() -> Result<()> {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("file_operations.json");
+    let temp_dir = TempDir::new()?;
+    let source_dir = temp_dir.path().join("tests/fixtures/files/test_files");
+    fs::create_dir_all(&source_dir)?;
+    fs::write(source_dir.join("sample.conf"), "listen 8080\n")?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg(&fixture)
+        .arg("--playbook-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout)?;
+
+    let file = &plan["binary_deployments"][0]["embedded_data"]["static_files"][0];
+    assert_eq!(
+        file["checksum"],
+        rustle_plan::sha256_digest(b"listen 8080\n")
+    );
+    assert_eq!(file["size"], 12);
+    assert!(plan["metadata"]["playbook_hash"]
+        .as_str()
+        .unwrap()
+        .starts_with("sha256:"));
+    assert!(plan["plan_digest"].as_str().unwrap().starts_with("sha256:"));
+
+    Ok(())
+}
+
+#[test]
+fn test_sign_and_verify_plan() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let key = temp_dir.path().join("keys").join("plan.key");
+    let public_key = temp_dir.path().join("keys").join("plan.key.pub");
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("keygen").arg("--key").arg(&key).assert().success();
+    assert!(public_key.exists());
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan_path = temp_dir.path().join("plan.json");
+    fs::write(&plan_path, &output.get_output().stdout)?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("sign")
+        .arg(&plan_path)
+        .arg("--key")
+        .arg(&key)
+        .assert()
+        .success();
+    let signed: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(signed["metadata"]["signature"]["algorithm"], "ed25519");
+    assert_eq!(
+        signed["metadata"]["signature"]["public_key"],
+        fs::read_to_string(&public_key)?.trim()
+    );
+
+    let signed_path = temp_dir.path().join("signed.json");
+    fs::write(&signed_path, serde_json::to_string(&signed)?)?;
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("verify")
+        .arg(&signed_path)
+        .arg("--public-key")
+        .arg(&public_key)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Plan verified"));
+
+    // Adding a host after signing fails verification
+    let mut tampered = signed.clone();
+    tampered["hosts"]
+        .as_array_mut()
+        .unwrap()
+        .push(Value::from("host3"));
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("verify")
+        .arg("--public-key")
+        .arg(&public_key)
+        .write_stdin(serde_json::to_string(&tampered)?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("changed after it was signed"));
+
+    // An unsigned plan fails too
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("verify")
+        .arg(&plan_path)
+        .arg("--public-key")
+        .arg(&public_key)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Plan is not signed"));
+
+    // keygen won't replace an existing key without --force
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("keygen").arg("--key").arg(&key).assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_secrets_redacted_and_sealed() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let key = temp_dir.path().join("executor.key");
+    let public_key = temp_dir.path().join("executor.key.pub");
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.args(["keygen", "--sealing", "--key"])
+        .arg(&key)
+        .assert()
+        .success();
+
+    let input = create_test_rustle_output()
+        .replace(
+            r#""cmd": "echo hello""#,
+            r#""cmd": "echo hello", "login_password": "hunter2", "db_host": "db1""#,
+        )
+        .replace(r#""notify": []"#, r#""notify": [], "no_log": true"#);
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("--seal-secrets-to")
+        .arg(&public_key)
+        .args(["--redact-key", "^db_"])
+        .write_stdin(input)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(output.get_output().stdout.clone())?;
+    assert!(!stdout.contains("hunter2"));
+    assert!(!stdout.contains("echo hello"));
+
+    let plan: rustle_plan::ExecutionPlan = serde_json::from_str(&stdout)?;
+    let task = &plan.plays[0].batches[0].tasks[0];
+    assert!(task.no_log);
+    assert_eq!(
+        task.args["login_password"],
+        "secret:play-0/task1/args/login_password"
+    );
+    assert_eq!(plan.redacted_secrets.len(), 3);
+
+    let opener = rustle_plan::SecretOpener::from_hex(&fs::read_to_string(&key)?)?;
+    let secrets = opener.open(plan.sealed_secrets.as_ref().unwrap())?;
+    assert_eq!(secrets["play-0/task1/args/login_password"], "hunter2");
+    assert_eq!(secrets["play-0/task1/args/db_host"], "db1");
+
+    Ok(())
+}
+
+#[test]
+fn test_schema_command() -> Result<()> {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.arg("schema").assert().success();
+    let schema: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(schema["title"], "ExecutionPlan");
+    assert!(schema["$defs"]["PlanMetadata"]["properties"]["schema_version"].is_object());
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.arg("schema").arg("input").assert().success();
+    let schema: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(schema["title"], "RustleParseOutput");
+    assert_eq!(
+        schema["required"],
+        serde_json::json!(["metadata", "plays", "variables"])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_plans_record_schema_version() -> Result<()> {
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(
+        plan["metadata"]["schema_version"],
+        rustle_plan::PLAN_SCHEMA_VERSION
+    );
+    Ok(())
+}
+
+#[test]
+fn test_inspect_saved_plan() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("--output")
+        .arg("binary")
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let plan_path = temp_dir.path().join("plan.bin");
+    fs::write(&plan_path, &output.get_output().stdout)?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("inspect")
+        .arg(&plan_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tasks: 1"))
+        .stdout(predicate::str::contains("Hosts: 2"));
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("inspect")
+        .arg(&plan_path)
+        .arg("--list-tasks")
+        .arg("--list-hosts")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Planned tasks:"))
+        .stdout(predicate::str::contains("Test task (task1)"))
+        .stdout(predicate::str::contains("Target hosts:"))
+        .stdout(predicate::str::contains("Tasks: 1").not());
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("inspect")
+        .arg("--dot")
+        .write_stdin(fs::read(&plan_path)?)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph execution_plan {"));
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("inspect")
+        .arg(&plan_path)
+        .arg("--html")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("<!DOCTYPE html>"))
+        .stdout(predicate::str::contains("Test task"))
+        .stdout(predicate::str::contains("Tasks: 1").not());
+    Ok(())
+}
+
+#[test]
+fn test_validate_saved_plan() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let mut plan: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    let plan_path = temp_dir.path().join("plan.json");
+    fs::write(&plan_path, serde_json::to_string(&plan)?)?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("validate")
+        .arg(&plan_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Plan is valid"));
+
+    // Edits are caught by the digest as well as the validator
+    plan["plays"][0]["batches"][0]["hosts"] = serde_json::json!([]);
+    fs::write(&plan_path, serde_json::to_string(&plan)?)?;
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("validate")
+        .arg(&plan_path)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("has no target hosts"))
+        .stdout(predicate::str::contains("Plan digest mismatch"));
+
+    // Without a digest, the plan is only checked for consistency
+    plan["plays"][0]["batches"][0]["hosts"] = serde_json::json!(["host1", "host2"]);
+    plan.as_object_mut().unwrap().remove("plan_digest");
+    fs::write(&plan_path, serde_json::to_string(&plan)?)?;
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("validate")
+        .arg(&plan_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Warning: Plan has no digest"));
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("validate")
+        .arg(&plan_path)
+        .arg("--strict")
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn test_diff_plans() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let old_path = temp_dir.path().join("old.json");
+    let new_path = temp_dir.path().join("new.json");
+    let changed_input = create_test_rustle_output().replace("echo hello", "echo goodbye");
+    for (input, path) in [
+        (create_test_rustle_output(), &old_path),
+        (changed_input, &new_path),
+    ] {
+        let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+        let output = cmd.write_stdin(input).assert().success();
+        fs::write(path, &output.get_output().stdout)?;
+    }
+
+    // Plans made at different times from the same input don't differ
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .write_stdin(create_test_rustle_output())
+        .assert()
+        .success();
+    let again_path = temp_dir.path().join("again.json");
+    fs::write(&again_path, &output.get_output().stdout)?;
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("diff")
+        .arg(&old_path)
+        .arg(&again_path)
+        .assert()
+        .success()
+        .stdout("No differences\n");
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("diff")
+        .arg(&old_path)
+        .arg(&new_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("~ play-0/task1 Test task"))
+        .stdout(predicate::str::contains(
+            r#"args.cmd: "echo hello" -> "echo goodbye""#,
+        ));
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg("diff")
+        .arg(&old_path)
+        .arg(&new_path)
+        .arg("--output")
+        .arg("json")
+        .assert()
+        .success();
+    let diff: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    assert_eq!(diff["tasks"][0]["kind"], "Changed");
+    assert_eq!(diff["tasks"][0]["args"][0]["new"], "echo goodbye");
+    assert_eq!(diff["hosts_added"], serde_json::json!([]));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_keygen_force_restricts_existing_key_file() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new()?;
+    let key = temp_dir.path().join("plan.key");
+    fs::write(&key, "old key\n")?;
+    fs::set_permissions(&key, fs::Permissions::from_mode(0o644))?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    cmd.arg("keygen")
+        .arg("--force")
+        .arg("--key")
+        .arg(&key)
+        .assert()
+        .success();
+
+    assert_eq!(fs::metadata(&key)?.permissions().mode() & 0o777, 0o600);
+    assert_ne!(fs::read_to_string(&key)?, "old key\n");
+    Ok(())
+}
+
+#[test]
+fn test_unreadable_embedded_file_has_no_checksum() -> Result<()> {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("file_operations.json");
+    let temp_dir = TempDir::new()?;
+    // A file where the source's directory should be makes the read fail
+    // with something other than NotFound
+    let source_parent = temp_dir.path().join("tests/fixtures/files");
+    fs::create_dir_all(&source_parent)?;
+    fs::write(source_parent.join("test_files"), "not a directory")?;
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd
+        .arg(&fixture)
+        .arg("--playbook-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout)?;
+
+    let file = &plan["binary_deployments"][0]["embedded_data"]["static_files"][0];
+    assert_eq!(
+        file["src_path"],
+        "tests/fixtures/files/test_files/sample.conf"
+    );
+    assert_eq!(file["checksum"], Value::Null);
+
+    Ok(())
+}
+
+#[test]
+fn test_remote_src_copy_is_not_embedded() -> Result<()> {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("file_operations.json");
+    let input = fs::read_to_string(fixture)?.replace(
+        r#""src": "tests/fixtures/files/test_files/sample.conf","#,
+        r#""src": "tests/fixtures/files/test_files/sample.conf",
+            "remote_src": "yes","#,
+    );
+
+    let mut cmd = Command::cargo_bin("rustle-plan").unwrap();
+    let output = cmd.write_stdin(input).assert().success();
+    let plan: Value = serde_json::from_slice(&output.get_output().stdout)?;
+
+    for deployment in plan["binary_deployments"].as_array().unwrap() {
+        for file in deployment["embedded_data"]["static_files"]
+            .as_array()
+            .unwrap()
+        {
+            assert_ne!(
+                file["src_path"],
+                "tests/fixtures/files/test_files/sample.conf"
+            );
+        }
+    }
+
+    Ok(())
+}
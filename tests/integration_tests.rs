@@ -32,6 +32,13 @@ fn test_basic_execution_planning() {
                     tags: vec!["install".to_string()],
                     when: None,
                     notify: vec!["restart nginx".to_string()],
+                    vars: HashMap::new(),
+                    loop_items: None,
+                    changed_when: None,
+                    failed_when: None,
+                    ignore_errors: false,
+                    no_log: false,
+                    delegate_to: None,
                 },
                 ParsedTask {
                     id: "task-2".to_string(),
@@ -53,10 +60,21 @@ fn test_basic_execution_planning() {
                     tags: vec!["service".to_string()],
                     when: None,
                     notify: vec![],
+                    vars: HashMap::new(),
+                    loop_items: None,
+                    changed_when: None,
+                    failed_when: None,
+                    ignore_errors: false,
+                    no_log: false,
+                    delegate_to: None,
                 },
             ],
             handlers: vec![],
             vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
         }],
         vars: HashMap::new(),
     };
@@ -67,6 +85,9 @@ fn test_basic_execution_planning() {
         groups: HashMap::new(),
         vars: HashMap::new(),
         host_facts: HashMap::new(),
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
     };
 
     // Create planning options
@@ -78,10 +99,15 @@ fn test_basic_execution_planning() {
         diff_mode: false,
         forks: 50,
         serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
         strategy: ExecutionStrategy::Linear,
         binary_threshold: 5,
         force_binary: false,
         force_ssh: false,
+        reproducible: false,
     };
 
     // Plan execution
@@ -129,6 +155,15 @@ fn test_binary_deployment_planning() {
             can_run_parallel: true,
             estimated_duration: Some(std::time::Duration::from_secs(2)),
             risk_level: RiskLevel::Medium,
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
         },
         TaskPlan {
             task_id: "task-2".to_string(),
@@ -144,6 +179,15 @@ fn test_binary_deployment_planning() {
             can_run_parallel: true,
             estimated_duration: Some(std::time::Duration::from_secs(3)),
             risk_level: RiskLevel::Medium,
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
+            loop_expansion: None,
+            schedule: None,
         },
     ];
 
@@ -170,6 +214,13 @@ fn test_dependency_analysis() {
             tags: vec![],
             when: None,
             notify: vec![],
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
         },
         ParsedTask {
             id: "task-2".to_string(),
@@ -180,6 +231,13 @@ fn test_dependency_analysis() {
             tags: vec![],
             when: None,
             notify: vec![],
+            vars: HashMap::new(),
+            loop_items: None,
+            changed_when: None,
+            failed_when: None,
+            ignore_errors: false,
+            no_log: false,
+            delegate_to: None,
         },
     ];
 
@@ -212,6 +270,13 @@ fn test_task_estimation() {
         tags: vec![],
         when: None,
         notify: vec![],
+        vars: HashMap::new(),
+        loop_items: None,
+        changed_when: None,
+        failed_when: None,
+        ignore_errors: false,
+        no_log: false,
+        delegate_to: None,
     };
 
     let duration = estimator.estimate_task_duration(&task);
@@ -226,3 +291,948 @@ fn test_task_estimation() {
         "Package operations should take at least 10 seconds"
     );
 }
+
+#[test]
+fn test_task_execution_fields_affect_planning() {
+    let planner = ExecutionPlanner::new();
+
+    let shell_task = |id: &str| ParsedTask {
+        id: id.to_string(),
+        name: format!("Run {id}"),
+        module: "shell".to_string(),
+        args: HashMap::new(),
+        dependencies: vec![],
+        tags: vec![],
+        when: None,
+        notify: vec![],
+        vars: HashMap::new(),
+        loop_items: None,
+        changed_when: None,
+        failed_when: None,
+        ignore_errors: false,
+        no_log: false,
+        delegate_to: None,
+    };
+
+    let mut query = shell_task("query");
+    query.changed_when = Some("false".to_string());
+    query
+        .vars
+        .insert("threshold".to_string(), serde_json::json!(5));
+
+    let mut tolerant = shell_task("tolerant");
+    tolerant.ignore_errors = true;
+
+    let mut delegated = shell_task("notify-lb");
+    delegated.module = "uri".to_string();
+    delegated.delegate_to = Some("lb1".to_string());
+
+    let mut looped = shell_task("looped");
+    looped.module = "file".to_string();
+    looped.loop_items = Some(serde_json::json!(["a", "b", "c"]));
+
+    let parsed_playbook = ParsedPlaybook {
+        name: "fields-playbook".to_string(),
+        plays: vec![ParsedPlay {
+            name: "Fields Play".to_string(),
+            hosts: vec!["all".to_string()],
+            tasks: vec![query, tolerant, delegated, looped, shell_task("plain")],
+            handlers: vec![],
+            vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
+        }],
+        vars: HashMap::new(),
+    };
+
+    let parsed_inventory = ParsedInventory {
+        hosts: vec!["web1".to_string(), "lb1".to_string()],
+        groups: HashMap::new(),
+        vars: HashMap::new(),
+        host_facts: HashMap::new(),
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
+    };
+
+    let planning_options = PlanningOptions {
+        limit: None,
+        tags: vec![],
+        skip_tags: vec![],
+        check_mode: false,
+        diff_mode: false,
+        forks: 50,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
+        strategy: ExecutionStrategy::Linear,
+        binary_threshold: 5,
+        force_binary: false,
+        force_ssh: true,
+        reproducible: false,
+    };
+
+    let execution_plan = planner
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .expect("Planning should succeed");
+
+    let tasks: HashMap<&str, &TaskPlan> = execution_plan.plays[0]
+        .batches
+        .iter()
+        .flat_map(|batch| &batch.tasks)
+        .map(|task| (task.task_id.as_str(), task))
+        .collect();
+
+    let query = tasks["query"];
+    assert_eq!(query.risk_level, RiskLevel::Medium);
+    assert_eq!(query.changed_when.as_deref(), Some("false"));
+    assert_eq!(query.vars["threshold"], serde_json::json!(5));
+    assert_eq!(tasks["plain"].risk_level, RiskLevel::Critical);
+    assert!(tasks["tolerant"].ignore_errors);
+    assert_eq!(tasks["tolerant"].risk_level, RiskLevel::High);
+
+    let delegated = tasks["notify-lb"];
+    assert_eq!(delegated.delegate_to.as_deref(), Some("lb1"));
+    assert_eq!(delegated.risk_level, RiskLevel::High);
+    assert!(!delegated.can_run_parallel);
+
+    let looped = tasks["looped"];
+    assert_eq!(
+        looped.estimated_duration,
+        Some(std::time::Duration::from_secs(3))
+    );
+}
+
+#[test]
+fn test_layered_batches_follow_dependency_graph() {
+    let planner = ExecutionPlanner::new();
+
+    let task = |id: &str, module: &str, dependencies: &[&str], args: &[(&str, &str)]| ParsedTask {
+        id: id.to_string(),
+        name: format!("Task {id}"),
+        module: module.to_string(),
+        args: args
+            .iter()
+            .map(|(k, v)| (k.to_string(), serde_json::json!(v)))
+            .collect(),
+        dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        tags: vec![],
+        when: None,
+        notify: vec![],
+        vars: HashMap::new(),
+        loop_items: None,
+        changed_when: None,
+        failed_when: None,
+        ignore_errors: false,
+        no_log: false,
+        delegate_to: None,
+    };
+
+    let parsed_playbook = ParsedPlaybook {
+        name: "layered-playbook".to_string(),
+        plays: vec![ParsedPlay {
+            name: "Layered Play".to_string(),
+            hosts: vec!["all".to_string()],
+            tasks: vec![
+                task(
+                    "dir",
+                    "file",
+                    &[],
+                    &[("path", "/srv/app"), ("state", "directory")],
+                ),
+                task("user", "user", &[], &[("name", "app")]),
+                task("config", "copy", &["dir"], &[("dest", "/srv/app/app.conf")]),
+                task(
+                    "template",
+                    "template",
+                    &["dir"],
+                    &[("dest", "/srv/app/app.conf")],
+                ),
+                task("check", "debug", &["config", "user"], &[]),
+            ],
+            handlers: vec![],
+            vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
+        }],
+        vars: HashMap::new(),
+    };
+
+    let parsed_inventory = ParsedInventory {
+        hosts: vec!["web1".to_string(), "web2".to_string()],
+        groups: HashMap::new(),
+        vars: HashMap::new(),
+        host_facts: HashMap::new(),
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
+    };
+
+    let planning_options = PlanningOptions {
+        limit: None,
+        tags: vec![],
+        skip_tags: vec![],
+        check_mode: false,
+        diff_mode: false,
+        forks: 50,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
+        strategy: ExecutionStrategy::Free,
+        binary_threshold: 5,
+        force_binary: false,
+        force_ssh: true,
+        reproducible: false,
+    };
+
+    let execution_plan = planner
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .expect("Planning should succeed");
+    let batches = &execution_plan.plays[0].batches;
+
+    let layer_ids: Vec<Vec<&str>> = batches
+        .iter()
+        .map(|batch| batch.tasks.iter().map(|t| t.task_id.as_str()).collect())
+        .collect();
+    assert_eq!(
+        layer_ids,
+        vec![
+            vec!["dir", "user"],
+            vec!["config", "template"],
+            vec!["check"]
+        ]
+    );
+
+    assert!(batches[0].dependencies.is_empty());
+    assert_eq!(batches[1].dependencies, vec!["batch-0"]);
+    assert_eq!(batches[2].dependencies, vec!["batch-0", "batch-1"]);
+    assert_eq!(batches[0].hosts, vec!["web1", "web2"]);
+
+    // Independent tasks run together; tasks writing the same file do not
+    assert_eq!(batches[0].parallel_groups.len(), 1);
+    assert_eq!(batches[0].parallel_groups[0].tasks, vec!["dir", "user"]);
+    assert!(batches[1].parallel_groups.is_empty());
+    assert!(batches.iter().all(|b| b.estimated_duration.is_some()));
+}
+
+#[test]
+fn test_tasks_filtered_by_host_facts() {
+    let planner = ExecutionPlanner::new();
+
+    let task = |id: &str, when: Option<&str>| ParsedTask {
+        id: id.to_string(),
+        name: format!("Task {id}"),
+        module: "debug".to_string(),
+        args: HashMap::new(),
+        dependencies: vec![],
+        tags: vec![],
+        when: when.map(str::to_string),
+        notify: vec![],
+        vars: HashMap::new(),
+        loop_items: None,
+        changed_when: None,
+        failed_when: None,
+        ignore_errors: false,
+        no_log: false,
+        delegate_to: None,
+    };
+
+    let parsed_playbook = ParsedPlaybook {
+        name: "facts-playbook".to_string(),
+        plays: vec![ParsedPlay {
+            name: "Facts Play".to_string(),
+            hosts: vec!["all".to_string()],
+            tasks: vec![
+                task("apt-task", Some("ansible_os_family == 'Debian'")),
+                task("yum-task", Some("ansible_facts.os_family == 'RedHat'")),
+                task("windows-task", Some("ansible_system == 'Windows'")),
+                task("runtime-task", Some("result.rc == 0")),
+            ],
+            handlers: vec![],
+            vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
+        }],
+        vars: HashMap::new(),
+    };
+
+    let facts = |os_family: &str| {
+        let mut facts = HashMap::new();
+        facts.insert(
+            "ansible_os_family".to_string(),
+            serde_json::Value::String(os_family.to_string()),
+        );
+        facts.insert(
+            "ansible_system".to_string(),
+            serde_json::Value::String("Linux".to_string()),
+        );
+        facts
+    };
+
+    let mut host_facts = HashMap::new();
+    host_facts.insert("debian1".to_string(), facts("Debian"));
+    host_facts.insert("centos1".to_string(), facts("RedHat"));
+
+    let parsed_inventory = ParsedInventory {
+        hosts: vec![
+            "debian1".to_string(),
+            "centos1".to_string(),
+            "unknown1".to_string(),
+        ],
+        groups: HashMap::new(),
+        vars: HashMap::new(),
+        host_facts,
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
+    };
+
+    let planning_options = PlanningOptions {
+        limit: None,
+        tags: vec![],
+        skip_tags: vec![],
+        check_mode: false,
+        diff_mode: false,
+        forks: 50,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
+        strategy: ExecutionStrategy::Linear,
+        binary_threshold: 5,
+        force_binary: false,
+        force_ssh: true,
+        reproducible: false,
+    };
+
+    let execution_plan = planner
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .expect("Planning should succeed");
+
+    let task_hosts = |task_id: &str| -> Option<Vec<String>> {
+        execution_plan.plays[0]
+            .batches
+            .iter()
+            .flat_map(|batch| batch.tasks.iter())
+            .find(|task| task.task_id == task_id)
+            .map(|task| task.hosts.clone())
+    };
+
+    // Hosts without facts are never pruned
+    assert_eq!(task_hosts("apt-task").unwrap(), vec!["debian1", "unknown1"]);
+    assert_eq!(task_hosts("yum-task").unwrap(), vec!["centos1", "unknown1"]);
+    assert_eq!(task_hosts("windows-task").unwrap(), vec!["unknown1"]);
+    assert_eq!(task_hosts("runtime-task").unwrap().len(), 3);
+
+    assert_eq!(execution_plan.filtered_tasks.len(), 4);
+    let filtered = execution_plan
+        .filtered_tasks
+        .iter()
+        .find(|f| f.task_id == "apt-task")
+        .unwrap();
+    assert_eq!(filtered.host, "centos1");
+    assert_eq!(filtered.play_id, "play-0");
+    assert!(filtered.reason.contains("ansible_os_family=\"RedHat\""));
+}
+
+#[test]
+fn test_expanded_loop_items_are_redacted() {
+    let planner = ExecutionPlanner::new().with_loop_expansion(true);
+
+    let looped = |id: &str, items: serde_json::Value| ParsedTask {
+        id: id.to_string(),
+        name: id.to_string(),
+        module: "debug".to_string(),
+        args: HashMap::new(),
+        dependencies: vec![],
+        tags: vec![],
+        when: None,
+        notify: vec![],
+        vars: HashMap::new(),
+        loop_items: Some(items),
+        changed_when: None,
+        failed_when: None,
+        ignore_errors: false,
+        no_log: false,
+        delegate_to: None,
+    };
+
+    let mut token = looped("tok", serde_json::json!(["SUPERSECRETTOKEN"]));
+    token.no_log = true;
+    let mut users = looped(
+        "users",
+        serde_json::json!([{ "name": "alice", "password": "hunter2" }]),
+    );
+    // Skipped on debian1, so the user task also shows up as filtered
+    users.when = Some("ansible_os_family == 'RedHat'".to_string());
+
+    let parsed_playbook = ParsedPlaybook {
+        name: "loop-playbook".to_string(),
+        plays: vec![ParsedPlay {
+            name: "Loop Play".to_string(),
+            hosts: vec!["all".to_string()],
+            tasks: vec![token, users],
+            handlers: vec![],
+            vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
+        }],
+        vars: HashMap::new(),
+    };
+
+    let parsed_inventory = ParsedInventory {
+        hosts: vec!["debian1".to_string()],
+        groups: HashMap::new(),
+        vars: HashMap::new(),
+        host_facts: HashMap::from([(
+            "debian1".to_string(),
+            HashMap::from([("ansible_os_family".to_string(), serde_json::json!("Debian"))]),
+        )]),
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
+    };
+
+    let planning_options = PlanningOptions {
+        limit: None,
+        tags: vec![],
+        skip_tags: vec![],
+        check_mode: false,
+        diff_mode: false,
+        forks: 50,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
+        strategy: ExecutionStrategy::Linear,
+        binary_threshold: 5,
+        force_binary: false,
+        force_ssh: true,
+        reproducible: false,
+    };
+
+    let execution_plan = planner
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .expect("Planning should succeed");
+
+    let json = serde_json::to_string(&execution_plan).unwrap();
+    assert!(!json.contains("SUPERSECRETTOKEN"));
+    assert!(!json.contains("hunter2"));
+
+    let token = &execution_plan.plays[0].batches[0].tasks[0];
+    assert_eq!(token.name, "tok (item=0)");
+    assert_eq!(execution_plan.filtered_tasks.len(), 1);
+    assert!(execution_plan.filtered_tasks[0]
+        .task_name
+        .contains("secret:play-0/users_item_0/loop_expansion/item/password"));
+    assert!(execution_plan
+        .redacted_secrets
+        .contains(&"play-0/tok_item_0/loop_expansion/item".to_string()));
+}
+
+#[test]
+fn test_play_hosts_resolve_groups_and_limit_patterns() {
+    let planner = ExecutionPlanner::new();
+
+    let parsed_playbook = ParsedPlaybook {
+        name: "groups-playbook".to_string(),
+        plays: vec![ParsedPlay {
+            name: "Web Play".to_string(),
+            hosts: vec!["webservers:!canary".to_string()],
+            tasks: vec![ParsedTask {
+                id: "task-1".to_string(),
+                name: "Ping".to_string(),
+                module: "ping".to_string(),
+                args: HashMap::new(),
+                dependencies: vec![],
+                tags: vec![],
+                when: None,
+                notify: vec![],
+                vars: HashMap::new(),
+                loop_items: None,
+                changed_when: None,
+                failed_when: None,
+                ignore_errors: false,
+                no_log: false,
+                delegate_to: None,
+            }],
+            handlers: vec![],
+            vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
+        }],
+        vars: HashMap::new(),
+    };
+
+    let mut groups = HashMap::new();
+    groups.insert(
+        "webservers".to_string(),
+        vec!["web1".to_string(), "web2".to_string(), "web3".to_string()],
+    );
+    groups.insert("canary".to_string(), vec!["web1".to_string()]);
+
+    let parsed_inventory = ParsedInventory {
+        hosts: vec![
+            "web1".to_string(),
+            "web2".to_string(),
+            "web3".to_string(),
+            "db1".to_string(),
+        ],
+        groups,
+        vars: HashMap::new(),
+        host_facts: HashMap::new(),
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
+    };
+
+    let mut planning_options = PlanningOptions {
+        limit: None,
+        tags: vec![],
+        skip_tags: vec![],
+        check_mode: false,
+        diff_mode: false,
+        forks: 50,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
+        strategy: ExecutionStrategy::Linear,
+        binary_threshold: 5,
+        force_binary: false,
+        force_ssh: true,
+        reproducible: false,
+    };
+
+    let execution_plan = planner
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .expect("Planning should succeed");
+    assert_eq!(execution_plan.plays[0].hosts, vec!["web2", "web3"]);
+
+    planning_options.limit = Some("~web[23]".to_string());
+    let execution_plan = planner
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .expect("Planning should succeed");
+    assert_eq!(execution_plan.hosts, vec!["web2", "web3"]);
+
+    planning_options.limit = Some("webservers[0:".to_string());
+    let result = planner.plan_execution(&parsed_playbook, &parsed_inventory, &planning_options);
+    assert!(matches!(result, Err(PlanError::InvalidHostPattern { .. })));
+}
+
+fn create_ping_plan() -> ExecutionPlan {
+    let parsed_playbook = ParsedPlaybook {
+        name: "digest".to_string(),
+        plays: vec![ParsedPlay {
+            name: "Digest Play".to_string(),
+            hosts: vec!["all".to_string()],
+            tasks: vec![ParsedTask {
+                id: "task-1".to_string(),
+                name: "Ping".to_string(),
+                module: "ping".to_string(),
+                args: HashMap::new(),
+                dependencies: vec![],
+                tags: vec![],
+                when: None,
+                notify: vec![],
+                vars: HashMap::new(),
+                loop_items: None,
+                changed_when: None,
+                failed_when: None,
+                ignore_errors: false,
+                no_log: false,
+                delegate_to: None,
+            }],
+            handlers: vec![],
+            vars: HashMap::new(),
+            serial: None,
+            max_fail_percentage: None,
+            any_errors_fatal: false,
+            order: None,
+        }],
+        vars: HashMap::new(),
+    };
+    let parsed_inventory = ParsedInventory {
+        hosts: vec!["server1".to_string(), "server2".to_string()],
+        groups: HashMap::new(),
+        vars: HashMap::new(),
+        host_facts: HashMap::new(),
+        group_children: HashMap::new(),
+        group_vars: HashMap::new(),
+        host_vars: HashMap::new(),
+    };
+    let planning_options = PlanningOptions {
+        limit: None,
+        tags: vec![],
+        skip_tags: vec![],
+        check_mode: false,
+        diff_mode: false,
+        forks: 50,
+        serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
+        strategy: ExecutionStrategy::Linear,
+        binary_threshold: 5,
+        force_binary: false,
+        force_ssh: true,
+        reproducible: false,
+    };
+
+    ExecutionPlanner::new()
+        .plan_execution(&parsed_playbook, &parsed_inventory, &planning_options)
+        .unwrap()
+}
+
+#[test]
+fn test_plan_digest_detects_tampering() {
+    let plan = create_ping_plan();
+    let integrity = PlanIntegrity::new();
+    assert!(integrity.verify(&plan).is_ok());
+
+    // The digest survives a round trip through JSON
+    let json = serde_json::to_string(&plan).unwrap();
+    let mut reloaded: ExecutionPlan = serde_json::from_str(&json).unwrap();
+    assert!(integrity.verify(&reloaded).is_ok());
+
+    reloaded.hosts.push("intruder".to_string());
+    assert!(matches!(
+        integrity.verify(&reloaded),
+        Err(PlanError::PlanDigestMismatch { .. })
+    ));
+
+    reloaded.plan_digest = None;
+    assert!(matches!(
+        integrity.verify(&reloaded),
+        Err(PlanError::MissingPlanDigest)
+    ));
+}
+
+#[test]
+fn test_signed_plan_verifies_until_changed() {
+    let signer = PlanSigner::generate();
+    let verifier = PlanVerifier::from_hex(&signer.public_key_hex()).unwrap();
+
+    let mut plan = create_ping_plan();
+    assert!(matches!(
+        verifier.verify(&plan),
+        Err(PlanError::UnsignedPlan)
+    ));
+
+    signer.sign(&mut plan).unwrap();
+    assert!(verifier.verify(&plan).is_ok());
+
+    // Signing with a key loaded from its hex form gives the same signer
+    let reloaded = PlanSigner::from_hex(&signer.secret_key_hex()).unwrap();
+    assert_eq!(reloaded.public_key_hex(), signer.public_key_hex());
+
+    // A tampered task fails even when the digest is recomputed
+    let mut tampered = plan.clone();
+    tampered.plays[0].batches[0].tasks[0].module = "shell".to_string();
+    PlanIntegrity::new().seal(&mut tampered).unwrap();
+    assert!(matches!(
+        verifier.verify(&tampered),
+        Err(PlanError::InvalidSignature)
+    ));
+
+    // Re-signing a tampered plan with another key is caught too
+    let other = PlanSigner::generate();
+    other.sign(&mut tampered).unwrap();
+    assert!(matches!(
+        verifier.verify(&tampered),
+        Err(PlanError::SignerMismatch { .. })
+    ));
+
+    // Plans edited after planning can't be signed
+    let mut edited = create_ping_plan();
+    edited.hosts.push("intruder".to_string());
+    assert!(matches!(
+        signer.sign(&mut edited),
+        Err(PlanError::PlanDigestMismatch { .. })
+    ));
+
+    assert!(PlanVerifier::from_hex("abcd").is_err());
+}
+
+#[test]
+fn test_binary_plan_round_trip() {
+    let mut plan = create_ping_plan();
+    PlanSigner::generate().sign(&mut plan).unwrap();
+    let json = serde_json::to_vec(&plan).unwrap();
+
+    for format in [
+        BinaryPlanFormat::new(),
+        BinaryPlanFormat::new().with_compression(3),
+    ] {
+        let bytes = format.encode(&plan).unwrap();
+        assert!(BinaryPlanFormat::is_binary_plan(&bytes));
+        assert!(bytes.len() < json.len());
+
+        // Either format decodes both encodings, and the digest still holds
+        let decoded = BinaryPlanFormat::new().read(bytes.as_slice()).unwrap();
+        assert_eq!(serde_json::to_vec(&decoded).unwrap(), json);
+        assert!(PlanIntegrity::new().verify(&decoded).is_ok());
+    }
+}
+
+#[test]
+fn test_plans_from_older_schema_versions_load() {
+    let plan = create_ping_plan();
+    assert_eq!(plan.metadata.schema_version, PLAN_SCHEMA_VERSION);
+
+    // Version 1 plans had a single serial size and rolling batch size
+    let mut legacy = serde_json::to_value(&plan).unwrap();
+    let metadata = legacy["metadata"].as_object_mut().unwrap();
+    metadata.remove("schema_version");
+    legacy["metadata"]["planning_options"]["serial"] = serde_json::json!(2);
+    legacy["plays"][0]["strategy"] = serde_json::json!({ "Rolling": { "batch_size": 2 } });
+    assert!(serde_json::from_value::<ExecutionPlan>(legacy.clone()).is_err());
+
+    let migrator = PlanMigrator::new();
+    assert_eq!(migrator.schema_version(&legacy).unwrap(), 1);
+    let upgraded = migrator.load(legacy).unwrap();
+    assert_eq!(upgraded.metadata.schema_version, PLAN_SCHEMA_VERSION);
+    assert_eq!(
+        upgraded.metadata.planning_options.serial,
+        Some(vec![BatchSize::Hosts(2)])
+    );
+    assert!(matches!(
+        &upgraded.plays[0].strategy,
+        ExecutionStrategy::Rolling { batch_sizes, .. } if batch_sizes == &[BatchSize::Hosts(2)]
+    ));
+
+    // Current plans load unchanged
+    let json = serde_json::to_string(&plan).unwrap();
+    let reloaded = migrator.load_json(&json).unwrap();
+    assert!(PlanIntegrity::new().verify(&reloaded).is_ok());
+}
+
+fn create_deployment(id: &str, tasks: &[&str]) -> BinaryDeployment {
+    serde_json::from_value(serde_json::json!({
+        "deployment_id": id,
+        "play_id": "play-0",
+        "target_hosts": ["server1", "server2"],
+        "binary_name": format!("rustle-runner-{id}"),
+        "tasks": tasks,
+        "modules": ["ping"],
+        "embedded_data": {
+            "execution_plan": "{}",
+            "static_files": [],
+            "variables": {},
+            "facts_required": []
+        },
+        "execution_mode": "Standalone",
+        "estimated_size": 1024,
+        "compilation_requirements": {
+            "target_arch": "x86_64",
+            "target_os": "linux",
+            "rust_version": "1.70.0",
+            "cross_compilation": false,
+            "static_linking": true
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_plan_diff() {
+    let differ = PlanDiffer::new();
+    let mut old = create_ping_plan();
+    let mut second = old.plays[0].batches[0].clone();
+    second.batch_id = "batch-1".to_string();
+    second.tasks[0].task_id = "task-2".to_string();
+    old.plays[0].batches.push(second);
+    old.binary_deployments = vec![create_deployment("group_0", &["task-1", "task-2"])];
+
+    // Volatile metadata doesn't count
+    let mut new = old.clone();
+    new.metadata.created_at = chrono::Utc::now();
+    new.plan_digest = None;
+    assert!(differ.diff(&old, &new).is_empty());
+
+    new.hosts.push("server3".to_string());
+    new.plays[0].batches.swap(0, 1);
+    let task = &mut new.plays[0].batches[1].tasks[0];
+    task.hosts.remove(0);
+    task.args
+        .insert("data".to_string(), serde_json::json!("pong"));
+    task.risk_level = RiskLevel::High;
+    new.plays[0].batches[0].tasks.clear();
+    new.binary_deployments = vec![
+        create_deployment("group_0", &["task-1"]),
+        create_deployment("group_1", &["task-2"]),
+    ];
+
+    let diff = differ.diff(&old, &new);
+    assert_eq!(diff.hosts_added, vec!["server3"]);
+
+    assert_eq!(diff.tasks.len(), 2);
+    let changed = &diff.tasks[0];
+    assert_eq!(changed.task_id, "task-1");
+    assert_eq!(changed.kind, DiffKind::Changed);
+    assert_eq!(changed.hosts_removed, vec!["server1"]);
+    assert_eq!(changed.args[0].key, "data");
+    assert_eq!(changed.args[0].old, None);
+    assert!(changed.risk_level.is_some());
+    assert_eq!(diff.tasks[1].task_id, "task-2");
+    assert_eq!(diff.tasks[1].kind, DiffKind::Removed);
+    assert_eq!(diff.tasks[1].hosts_removed, vec!["server1", "server2"]);
+
+    let moved: Vec<_> = diff
+        .batches
+        .iter()
+        .map(|batch| (batch.batch_id.as_str(), batch.position.clone()))
+        .collect();
+    assert_eq!(moved.len(), 2);
+    assert_eq!(moved[0].1.as_ref().map(|p| (p.old, p.new)), Some((0, 1)));
+    assert_eq!(diff.batches[1].tasks_removed, vec!["task-2"]);
+
+    assert_eq!(diff.binary_deployments.len(), 2);
+    assert_eq!(
+        diff.binary_deployments[0].split_into,
+        vec!["group_0", "group_1"]
+    );
+    assert_eq!(diff.binary_deployments[1].kind, DiffKind::Added);
+
+    // The reverse change merges the deployments back together
+    let reverse = differ.diff(&new, &old);
+    let merged = reverse
+        .binary_deployments
+        .iter()
+        .find(|d| d.deployment_id == "group_0")
+        .unwrap();
+    assert_eq!(merged.merged_from, vec!["group_0", "group_1"]);
+    assert_eq!(reverse.tasks[1].kind, DiffKind::Added);
+}
+
+#[test]
+fn test_plan_diff_matches_deployments_within_plays() {
+    let differ = PlanDiffer::new();
+    let mut old = create_ping_plan();
+    let mut second_play = old.plays[0].clone();
+    second_play.play_id = "play-1".to_string();
+    old.plays.push(second_play);
+
+    // Every play numbers its deployments from group_0
+    let tasks = |prefix: &str, count: usize| -> Vec<String> {
+        (0..count).map(|index| format!("{prefix}{index}")).collect()
+    };
+    let deployment = |play_id: &str, tasks: &[String]| {
+        let tasks: Vec<&str> = tasks.iter().map(String::as_str).collect();
+        let mut deployment = create_deployment("group_0", &tasks);
+        deployment.play_id = play_id.to_string();
+        deployment
+    };
+    old.binary_deployments = vec![
+        deployment("play-0", &tasks("a", 6)),
+        deployment("play-1", &tasks("b", 6)),
+    ];
+
+    let mut new = old.clone();
+    new.binary_deployments[1] = deployment("play-1", &tasks("b", 7));
+
+    let diff = differ.diff(&old, &new);
+    assert_eq!(diff.binary_deployments.len(), 1);
+    let changed = &diff.binary_deployments[0];
+    assert_eq!(changed.play_id, "play-1");
+    assert_eq!(changed.deployment_id, "group_0");
+    assert_eq!(changed.tasks_added, vec!["b6"]);
+    assert!(changed.tasks_removed.is_empty());
+    assert!(changed.split_into.is_empty() && changed.merged_from.is_empty());
+
+    // Splitting play-1's deployment leaves play-0's alone
+    let mut split = old.clone();
+    split.binary_deployments = vec![
+        deployment("play-0", &tasks("a", 6)),
+        deployment("play-1", &tasks("b", 3)),
+        {
+            let mut rest = create_deployment("group_1", &["b3", "b4", "b5"]);
+            rest.play_id = "play-1".to_string();
+            rest
+        },
+    ];
+    let diff = differ.diff(&old, &split);
+    let keys: Vec<(&str, &str)> = diff
+        .binary_deployments
+        .iter()
+        .map(|d| (d.play_id.as_str(), d.deployment_id.as_str()))
+        .collect();
+    assert_eq!(keys, vec![("play-1", "group_0"), ("play-1", "group_1")]);
+    assert_eq!(
+        diff.binary_deployments[0].split_into,
+        vec!["group_0", "group_1"]
+    );
+}
+
+#[test]
+fn test_plan_visualizations() {
+    let mut plan = create_ping_plan();
+    let batch = &mut plan.plays[0].batches[0];
+    let mut second = batch.tasks[0].clone();
+    second.task_id = "task-2".to_string();
+    second.name = "Check <config> & \"env\"".to_string();
+    second.risk_level = RiskLevel::High;
+    second.dependencies = vec!["task-1".to_string()];
+    batch.tasks.push(second);
+    batch.parallel_groups = vec![rustle_plan::types::ParallelGroup {
+        group_id: "group_0".to_string(),
+        tasks: vec!["task-1".to_string(), "task-2".to_string()],
+        max_parallelism: 2,
+        shared_resources: vec![],
+    }];
+    batch.binary_deployment = Some("group_0".to_string());
+    let mut same_hosts = create_deployment("group_1", &["task-2"]);
+    same_hosts.target_hosts.reverse();
+    plan.binary_deployments = vec![create_deployment("group_0", &["task-1"]), same_hosts];
+
+    // A later play numbers its deployments from group_0 again
+    let mut second_play = plan.plays[0].clone();
+    second_play.play_id = "play-1".to_string();
+    plan.plays.push(second_play);
+    let mut other_hosts = create_deployment("group_0", &["task-1"]);
+    other_hosts.play_id = "play-1".to_string();
+    other_hosts.target_hosts = vec!["server3".to_string()];
+    plan.binary_deployments.push(other_hosts);
+
+    let visualizer = PlanVisualizer::new();
+    let mermaid = visualizer.to_mermaid(&plan);
+    assert!(mermaid.starts_with("flowchart TD\n"));
+    assert!(mermaid.contains("p0b0t1[\"Check #lt;config#gt; & #quot;env#quot;<br/>ping\"]:::high"));
+    assert!(mermaid.contains("p0b0t0 --> p0b0t1"));
+
+    let plantuml = visualizer.to_plantuml(&plan);
+    assert!(plantuml.starts_with("@startuml\n") && plantuml.ends_with("@enduml\n"));
+    assert!(plantuml.contains("<<high>> as p0b0t1"));
+
+    let dot = visualizer.to_dot(&plan);
+    assert!(dot.contains(r#""task-1" -> "task-2";"#));
+    assert!(dot.contains(r#"[label="Check <config> & \"env\""]"#));
+
+    let html = visualizer.to_html(&plan);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("Parallel group group_0"));
+    assert!(html.contains("Check &lt;config&gt; &amp; &quot;env&quot;"));
+    assert!(html.contains("class=\"task risk-high\""));
+    // Deployments to the same hosts share a color, whatever the host order
+    assert_eq!(html.matches("hsl(0, 65%, 55%)").count(), 3);
+    assert!(html.contains("border-left-color: hsl(137, 65%, 55%)"));
+    assert!(!html.contains("<script") && !html.contains("http"));
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use rustle_plan::{ExecutionPlanner, ExecutionStrategy, PlanningOptions};
+use rustle_plan::{ExecutionPlanner, ExecutionStrategy, HostOrder, PlanningOptions};
 use std::fs;
 use std::path::PathBuf;
 
@@ -72,10 +72,15 @@ fn test_execution_planning_with_rustle_output() -> Result<()> {
         diff_mode: false,
         forks: 50,
         serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
         strategy: ExecutionStrategy::Linear,
         binary_threshold: 5,
         force_binary: false,
         force_ssh: false,
+        reproducible: false,
     };
 
     // Create planner and generate execution plan
@@ -171,10 +176,15 @@ fn test_execution_planning_with_system_facts() -> Result<()> {
         diff_mode: false,
         forks: 50,
         serial: None,
+        max_fail_percentage: None,
+        any_errors_fatal: false,
+        host_order: HostOrder::Inventory,
+        redact_keys: vec![],
         strategy: ExecutionStrategy::Linear,
         binary_threshold: 5,
         force_binary: false,
         force_ssh: false,
+        reproducible: false,
     };
 
     // Create planner and generate execution plan
@@ -257,6 +267,14 @@ fn parse_rustle_output(
         tasks: Vec<RustleParseTask>,
         handlers: Vec<RustleParseHandler>,
         vars: HashMap<String, serde_json::Value>,
+        #[serde(default, deserialize_with = "deserialize_serial")]
+        serial: Option<Vec<rustle_plan::BatchSize>>, // A size or a ramp of sizes
+        #[serde(default)]
+        max_fail_percentage: Option<u32>,
+        #[serde(default)]
+        any_errors_fatal: bool,
+        #[serde(default)]
+        order: Option<rustle_plan::HostOrder>,
     }
 
     #[derive(Deserialize)]
@@ -278,6 +296,8 @@ fn parse_rustle_output(
         module: String,
         args: HashMap<String, serde_json::Value>,
         when: Option<String>,
+        #[serde(default, deserialize_with = "deserialize_hosts")]
+        listen: Vec<String>, // A single topic or a list of topics
     }
 
     #[derive(Deserialize)]
@@ -312,6 +332,13 @@ fn parse_rustle_output(
                     tags: task.tags,
                     when: task.when,
                     notify: task.notify,
+                    vars: HashMap::new(),
+                    loop_items: None,
+                    changed_when: None,
+                    failed_when: None,
+                    ignore_errors: false,
+                    no_log: false,
+                    delegate_to: None,
                 })
                 .collect();
 
@@ -324,6 +351,8 @@ fn parse_rustle_output(
                     module: handler.module,
                     args: handler.args,
                     when: handler.when,
+                    listen: handler.listen,
+                    no_log: false,
                 })
                 .collect();
 
@@ -333,6 +362,10 @@ fn parse_rustle_output(
                 tasks,
                 handlers,
                 vars: play.vars,
+                serial: play.serial,
+                max_fail_percentage: play.max_fail_percentage,
+                any_errors_fatal: play.any_errors_fatal,
+                order: play.order,
             }
         })
         .collect();
@@ -349,6 +382,9 @@ fn parse_rustle_output(
             groups: inventory.groups,
             vars: inventory.vars,
             host_facts: std::collections::HashMap::new(),
+            group_children: std::collections::HashMap::new(),
+            group_vars: std::collections::HashMap::new(),
+            host_vars: std::collections::HashMap::new(),
         }
     } else {
         create_default_inventory()
@@ -363,6 +399,9 @@ fn create_default_inventory() -> rustle_plan::ParsedInventory {
         groups: std::collections::HashMap::new(),
         vars: std::collections::HashMap::new(),
         host_facts: std::collections::HashMap::new(),
+        group_children: std::collections::HashMap::new(),
+        group_vars: std::collections::HashMap::new(),
+        host_vars: std::collections::HashMap::new(),
     }
 }
 
@@ -386,3 +425,26 @@ where
         StringOrVecOrNull::Null => Ok(vec!["localhost".to_string()]), // Default to localhost when hosts is null
     }
 }
+
+fn deserialize_serial<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<rustle_plan::BatchSize>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SizeOrVec {
+        Size(rustle_plan::BatchSize),
+        Vec(Vec<rustle_plan::BatchSize>),
+    }
+
+    Ok(
+        Option::<SizeOrVec>::deserialize(deserializer)?.map(|serial| match serial {
+            SizeOrVec::Size(size) => vec![size],
+            SizeOrVec::Vec(sizes) => sizes,
+        }),
+    )
+}